@@ -0,0 +1,172 @@
+use glam::{Vec2, vec2};
+use image::{ImageError, open};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, TAU};
+
+/// The shape of the opening light passes through in a lens, which gives
+/// out of focus highlights, bokeh, their shape.
+///
+/// Shapes are sized to fit within [-1, 1] on each axis and scaled by the
+/// lens that uses them.
+pub trait Aperture: Sync + Send {
+    /// Maps `u`, uniform over the unit square, to a point on the aperture,
+    /// spread as evenly as its transmission.
+    fn sample(&self, u: Vec2) -> Vec2;
+
+    /// Whether light gets through the aperture at `point`.
+    fn contains(&self, point: Vec2) -> bool;
+}
+
+/// A round opening, as a lens wide open.
+pub struct Circular;
+
+impl Aperture for Circular {
+    fn sample(&self, u: Vec2) -> Vec2 {
+        concentric_disk(u)
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        point.length_squared() <= 1.0
+    }
+}
+
+/// A regular polygon inscribed in the unit circle, as the straight blades
+/// of an iris diaphragm make once stopped down.
+pub struct Polygonal {
+    blades: u32,
+    /// Of the first corner anticlockwise from +x, in radians.
+    rotation: f32,
+}
+
+impl Polygonal {
+    /// `rotation` is in degrees.
+    pub fn new(blades: u32, rotation: f32) -> Self {
+        assert!(blades >= 3, "an aperture needs at least three blades");
+        Self {
+            blades,
+            rotation: rotation.to_radians(),
+        }
+    }
+
+    fn corner(&self, index: u32) -> Vec2 {
+        Vec2::from_angle(self.rotation + index as f32 * TAU / self.blades as f32)
+    }
+}
+
+impl Aperture for Polygonal {
+    fn sample(&self, u: Vec2) -> Vec2 {
+        // One triangle from the centre per blade, all the same area.
+        let scaled = u.x * self.blades as f32;
+        let index = (scaled as u32).min(self.blades - 1);
+        let along = (scaled - index as f32).sqrt();
+        along * ((1.0 - u.y) * self.corner(index) + u.y * self.corner(index + 1))
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        let sector = TAU / self.blades as f32;
+        let angle = (point.y.atan2(point.x) - self.rotation).rem_euclid(sector);
+        // The distance to the edge along the middle of the sector.
+        let apothem = (sector / 2.0).cos();
+        point.length() * (angle - sector / 2.0).cos() <= apothem
+    }
+}
+
+/// An opening cut to any shape, read from a greyscale image: where it is
+/// white light passes, where it is black it doesn't, and grey lets some
+/// through. The image is centred and its longer side spans [-1, 1].
+pub struct ImageAperture {
+    width: usize,
+    height: usize,
+    transmission: Vec<f32>,
+    /// Running totals of `transmission`, for picking pixels in proportion.
+    cdf: Vec<f32>,
+}
+
+impl ImageAperture {
+    pub fn new(transmission: Vec<f32>, width: usize, height: usize) -> Self {
+        assert_eq!(transmission.len(), width * height);
+        let cdf: Vec<f32> = transmission
+            .iter()
+            .scan(0.0, |total, &value| {
+                *total += value.max(0.0);
+                Some(*total)
+            })
+            .collect();
+        assert!(
+            cdf.last().is_some_and(|&total| total > 0.0),
+            "an aperture image must let some light through"
+        );
+        Self {
+            width,
+            height,
+            transmission,
+            cdf,
+        }
+    }
+
+    pub fn load(file_path: &str) -> Result<Self, ImageError> {
+        let image = open(file_path)?.into_luma8();
+        let transmission = image
+            .pixels()
+            .map(|p| p[0] as f32 / u8::MAX as f32)
+            .collect();
+        Ok(Self::new(
+            transmission,
+            image.width() as usize,
+            image.height() as usize,
+        ))
+    }
+
+    /// The size of a pixel on the aperture.
+    fn pixel_size(&self) -> f32 {
+        2.0 / self.width.max(self.height) as f32
+    }
+}
+
+impl Aperture for ImageAperture {
+    fn sample(&self, u: Vec2) -> Vec2 {
+        let total = self.cdf[self.cdf.len() - 1];
+        let target = u.x * total;
+        let index = self
+            .cdf
+            .partition_point(|&sum| sum <= target)
+            .min(self.cdf.len() - 1);
+
+        // Where `target` fell within the pixel places the sample across it.
+        let start = if index == 0 { 0.0 } else { self.cdf[index - 1] };
+        let across = ((target - start) / (self.cdf[index] - start)).clamp(0.0, 1.0);
+        let pixel = vec2(
+            (index % self.width) as f32 + across,
+            (index / self.width) as f32 + u.y,
+        );
+        let size = self.pixel_size();
+        vec2(
+            (pixel.x - self.width as f32 / 2.0) * size,
+            (self.height as f32 / 2.0 - pixel.y) * size,
+        )
+    }
+
+    fn contains(&self, point: Vec2) -> bool {
+        let size = self.pixel_size();
+        let x = (point.x / size + self.width as f32 / 2.0).floor();
+        let y = (self.height as f32 / 2.0 - point.y / size).floor();
+        if x < 0.0 || y < 0.0 || x >= self.width as f32 || y >= self.height as f32 {
+            return false;
+        }
+        self.transmission[y as usize * self.width + x as usize] >= 0.5
+    }
+}
+
+/// Shirley and Chiu's mapping of the unit square to the unit disk, which
+/// keeps neighbouring samples together.
+pub fn concentric_disk(u: Vec2) -> Vec2 {
+    let offset = 2.0 * u - Vec2::ONE;
+    if offset == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+    let (radius, theta) = if offset.x.abs() > offset.y.abs() {
+        (offset.x, FRAC_PI_4 * (offset.y / offset.x))
+    } else {
+        (offset.y, FRAC_PI_2 - FRAC_PI_4 * (offset.x / offset.y))
+    };
+    radius * Vec2::from_angle(theta)
+}
@@ -1,12 +1,14 @@
 use crate::buffer::Buffer;
 use crate::color::Color;
-use crate::hittable::Hittable;
 use crate::hittable::hittable_list::HittableList;
+use crate::hittable::{HitRecord, Hittable};
 use crate::interval::Interval;
 use crate::material::ScatterResult;
-use crate::ray::Ray;
-use crate::utils::random_in_unit_disk;
-use glam::{Vec3, vec3};
+use crate::material::interior::Collision;
+use crate::projection::{Perspective, Projection};
+use crate::ray::{Ray, RayDifferentials};
+use crate::stereo::Stereo;
+use glam::{Vec2, Vec3, vec2, vec3};
 use rayon::prelude::*;
 use std::cmp::max;
 use std::sync::mpsc::Sender;
@@ -24,6 +26,12 @@ pub struct CameraProperties {
     pub up: Vec3,
     pub defocus_angle: f32,
     pub focus_dist: f32,
+    /// Replaces the thin lens perspective set by `v_fov`, `defocus_angle`
+    /// and `focus_dist`.
+    pub projection: Option<Box<dyn Projection>>,
+    /// Renders a view for each eye into one image, each `image_width` wide
+    /// at `aspect_ratio`.
+    pub stereo: Option<Stereo>,
 }
 
 impl Default for CameraProperties {
@@ -40,6 +48,8 @@ impl Default for CameraProperties {
             up: vec3(0.0, 1.0, 0.0),
             defocus_angle: 0.0,
             focus_dist: 10.0,
+            projection: None,
+            stereo: None,
         }
     }
 }
@@ -47,10 +57,12 @@ impl Default for CameraProperties {
 pub struct Camera {
     pub image_width: usize,
     pub image_height: usize,
+    /// The size of the view, which is one eye's half of the image in stereo.
+    view_width: usize,
+    view_height: usize,
     center: Vec3,
-    pixel00_loc: Vec3,
-    pixel_delta_u: Vec3,
-    pixel_delta_v: Vec3,
+    projection: Box<dyn Projection>,
+    stereo: Option<Stereo>,
     samples_per_pixel: Option<u32>,
     sqrt_spp: Option<u32>,
     recip_sqrt_spp: Option<f32>,
@@ -59,44 +71,34 @@ pub struct Camera {
     u: Vec3,
     v: Vec3,
     w: Vec3,
-    defocus_angle: f32,
-    defocus_disk_u: Vec3,
-    defocus_disk_v: Vec3,
 }
 
 impl Camera {
     pub fn new(properties: CameraProperties) -> Self {
         let aspect_ratio = properties.aspect_ratio;
         let image_width = properties.image_width;
-        let defocus_angle = properties.defocus_angle;
 
         let image_height = (image_width as f32 / aspect_ratio) as usize;
         let image_height = max(image_height, 1);
+        let (view_width, view_height) = (image_width, image_height);
+        let (image_width, image_height) = match &properties.stereo {
+            Some(stereo) => stereo.image_size(view_width, view_height),
+            None => (view_width, view_height),
+        };
 
         let center = properties.look_from;
 
-        let theta = properties.v_fov.to_radians();
-        let h = (theta / 2.0).tan();
-        let viewport_height = 2.0 * h * properties.focus_dist;
-        let viewport_width = viewport_height * (image_width as f32 / image_height as f32);
-
         let w = (properties.look_from - properties.look_at).normalize();
         let u = properties.up.cross(w).normalize();
         let v = w.cross(u).normalize();
 
-        let viewport_u = viewport_width * u;
-        let viewport_v = viewport_height * -v;
-
-        let pixel_delta_u = viewport_u / (image_width as f32);
-        let pixel_delta_v = viewport_v / (image_height as f32);
-
-        let viewport_upper_left =
-            center - (properties.focus_dist * w) - (viewport_u / 2.0) - (viewport_v / 2.0);
-        let pixel00_loc = viewport_upper_left + 0.5 * (pixel_delta_u + pixel_delta_v);
-
-        let defocus_radius = properties.focus_dist * (defocus_angle / 2.0).to_radians().tan();
-        let defocus_disk_u = u * defocus_radius;
-        let defocus_disk_v = v * defocus_radius;
+        let projection = properties.projection.unwrap_or_else(|| {
+            Box::new(Perspective::new(
+                properties.v_fov,
+                properties.defocus_angle,
+                properties.focus_dist,
+            ))
+        });
 
         let (sqrt_spp, recip_sqrt_spp) = if let Some(spp) = properties.samples_per_pixel {
             let sqrt_spp = (spp as f32).sqrt();
@@ -109,10 +111,11 @@ impl Camera {
         Self {
             image_width,
             image_height,
+            view_width,
+            view_height,
             center,
-            pixel00_loc,
-            pixel_delta_u,
-            pixel_delta_v,
+            projection,
+            stereo: properties.stereo,
             samples_per_pixel: properties.samples_per_pixel,
             sqrt_spp,
             recip_sqrt_spp,
@@ -121,9 +124,6 @@ impl Camera {
             u,
             v,
             w,
-            defocus_angle: properties.defocus_angle,
-            defocus_disk_u,
-            defocus_disk_v,
         }
     }
 
@@ -143,8 +143,10 @@ impl Camera {
                 .par_iter_mut()
                 .enumerate()
                 .for_each(|(index, pixel)| {
-                    let ray = self.get_ray(index, loop_count as u32);
-                    let new_color = self.ray_color(&ray, self.max_depth, world);
+                    let new_color = match self.get_ray(index, loop_count as u32) {
+                        Some(ray) => self.ray_color(&ray, self.max_depth, world),
+                        None => Vec3::ZERO,
+                    };
                     let old_color = pixel.vec3();
                     let color = (old_color * (loop_count - 1.0) / loop_count)
                         + (new_color * (1.0 / loop_count));
@@ -161,29 +163,72 @@ impl Camera {
         }
     }
 
-    fn get_ray(&self, pixel_loc: usize, loop_count: u32) -> Ray {
-        let j = (pixel_loc / self.image_width) as f32;
-        let i = (pixel_loc % self.image_width) as f32;
+    /// The ray for a sample in the pixel at `pixel_loc`, if the projection
+    /// covers it.
+    fn get_ray(&self, pixel_loc: usize, loop_count: u32) -> Option<Ray> {
+        let j = pixel_loc / self.image_width;
+        let i = pixel_loc % self.image_width;
+        let (eye, i, j) = match &self.stereo {
+            Some(stereo) => {
+                let (eye, i, j) = stereo.eye_pixel(i, j, self.view_width, self.view_height);
+                (Some((stereo, eye)), i, j)
+            }
+            None => (None, i, j),
+        };
 
         let offset = sample_square();// sample_square_stratified(pixel_loc as u32, loop_count);
-        let pixel_sample = self.pixel00_loc
-            + ((i + offset.x) * self.pixel_delta_u)
-            + ((j + offset.y) * self.pixel_delta_v);
+        let pixel_size = self.pixel_size();
+        let point = self.image_point(i, j, offset);
+        let lens = vec2(rand::random(), rand::random());
 
-        let ray_origin = if self.defocus_angle <= 0.0 {
-            self.center
-        } else {
-            self.defocus_disk_sample()
+        // Rays through the neighbouring pixels, through the same point of
+        // the lens, give the differentials.
+        let camera_ray = |point: Vec2| {
+            let (mut origin, mut direction) =
+                self.projection.ray(point, self.aspect_ratio(), lens)?;
+            if let Some((stereo, eye)) = eye {
+                (origin, direction) = stereo.eye_ray(eye, origin, direction);
+            }
+            let origin = self.center + self.to_world(origin);
+            Some((origin, self.to_world(direction)))
         };
-        let ray_direction = pixel_sample - ray_origin;
+        let (ray_origin, ray_direction) = camera_ray(point)?;
         let ray_time = rand::random::<f32>();
 
-        Ray::new(ray_origin, ray_direction, ray_time)
+        let differentials = camera_ray(point + vec2(pixel_size.x, 0.0))
+            .zip(camera_ray(point + vec2(0.0, pixel_size.y)))
+            .map(
+                |((rx_origin, rx_direction), (ry_origin, ry_direction))| RayDifferentials {
+                    rx_origin,
+                    rx_direction,
+                    ry_origin,
+                    ry_direction,
+                },
+            );
+
+        Some(Ray::new(ray_origin, ray_direction, ray_time).with_differentials(differentials))
     }
 
-    fn defocus_disk_sample(&self) -> Vec3 {
-        let p = random_in_unit_disk();
-        self.center + (p.x * self.defocus_disk_u) + (p.y * self.defocus_disk_v)
+    fn pixel_size(&self) -> Vec2 {
+        vec2(
+            (self.view_width as f32).recip(),
+            (self.view_height as f32).recip(),
+        )
+    }
+
+    /// Where on the image a sample `offset` from the centre of pixel (i, j)
+    /// falls.
+    fn image_point(&self, i: usize, j: usize, offset: Vec3) -> Vec2 {
+        vec2(i as f32 + 0.5 + offset.x, j as f32 + 0.5 + offset.y) * self.pixel_size()
+    }
+
+    fn aspect_ratio(&self) -> f32 {
+        self.view_width as f32 / self.view_height as f32
+    }
+
+    /// From camera space, looking down -z, to the world.
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        v.x * self.u + v.y * self.v + v.z * self.w
     }
 
     fn ray_color(&self, ray: &Ray, depth: u32, world: &dyn Hittable) -> Vec3 {
@@ -191,22 +236,53 @@ impl Camera {
             return Vec3::ZERO;
         }
 
-        let Some(rec) = world.hit(ray, Interval::new(0.0001, f32::INFINITY)) else {
+        let Some(mut rec) = world.hit(ray, Interval::new(0.0001, f32::INFINITY)) else {
             return self.background;
         };
+        rec.compute_footprint(ray);
+
+        // Whatever fills the shape the ray is inside absorbs some of the light
+        // on the way, and may scatter it before it gets that far.
+        let mut transmittance = Vec3::ONE;
+        if let Some(interior) = ray.media.current()
+            && !interior.is_clear()
+        {
+            let ray_length = ray.direction.length();
+            match interior.sample_collision(rec.t * ray_length) {
+                Collision::Scattered { distance, weight } => {
+                    let rec = HitRecord::in_medium(
+                        ray,
+                        distance / ray_length,
+                        interior.phase_function.clone(),
+                    );
+                    let Some(ScatterResult {
+                        attenuation,
+                        scattered,
+                    }) = rec.material.scatter(ray, &rec)
+                    else {
+                        return Vec3::ZERO;
+                    };
+                    let scattered = scattered.with_media(ray.media.clone());
+                    return weight * attenuation * self.ray_color(&scattered, depth - 1, world);
+                }
+                Collision::Passed { weight } => transmittance = weight,
+            }
+        }
 
-        let emission_color = rec.material.emitted(rec.u, rec.v, rec.point);
+        let emission_color = rec.material.emitted(ray, &rec);
         let Some(ScatterResult {
             attenuation,
             scattered,
         }) = rec.material.scatter(ray, &rec)
         else {
-            return emission_color;
+            return transmittance * emission_color;
         };
+        let media = ray.media.through(&rec, scattered.direction);
+        let scattered = scattered.with_media(media);
 
         let scatter_color = attenuation * self.ray_color(&scattered, depth - 1, world);
 
-        emission_color + scatter_color
+        transmittance * (emission_color + scatter_color)
     }
 }
 
@@ -227,3 +303,34 @@ fn sample_square() -> Vec3 {
         0.0,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projection::Orthographic;
+
+    #[test]
+    fn the_centre_pixel_looks_straight_ahead() {
+        let projections: [Option<Box<dyn Projection>>; 2] =
+            [None, Some(Box::new(Orthographic::new(4.0)))];
+        for projection in projections {
+            let camera = Camera::new(CameraProperties {
+                aspect_ratio: 1.0,
+                image_width: 101,
+                look_from: vec3(1.0, 2.0, 3.0),
+                look_at: vec3(-2.0, 0.5, -1.0),
+                projection,
+                ..CameraProperties::default()
+            });
+            assert_eq!((camera.view_width, camera.view_height), (101, 101));
+
+            let point = camera.image_point(50, 50, Vec3::ZERO);
+            let (_, direction) = camera
+                .projection
+                .ray(point, camera.aspect_ratio(), vec2(0.5, 0.5))
+                .unwrap();
+            let direction = camera.to_world(direction).normalize();
+            assert!(direction.abs_diff_eq(-camera.w, 1e-5), "{direction}");
+        }
+    }
+}
@@ -1,4 +1,5 @@
-use glam::Vec3;
+use glam::{Mat3, Vec3, vec3};
+use std::sync::LazyLock;
 
 #[derive(Clone)]
 pub struct Color {
@@ -10,16 +11,6 @@ impl Color {
         Color { value }
     }
 
-    pub fn from_rgb(r: u8, g: u8, b: u8) -> Color {
-        Color {
-            value: Vec3 {
-                x: r as f32 / 255.0,
-                y: g as f32 / 255.0,
-                z: b as f32 / 255.0,
-            },
-        }
-    }
-
     pub fn vec3(&self) -> &Vec3 {
         &self.value
     }
@@ -35,10 +26,6 @@ impl Color {
         (self.value.z * 255.0).round() as u8
     }
 
-    pub fn rgb(&self) -> (u8, u8, u8) {
-        (self.red(), self.green(), self.blue())
-    }
-
     pub fn rgb_value(&self) -> u32 {
         // let mut color: u32 = 0;
         // color |= ((v.x * 255f32) as u32) << 16;
@@ -65,9 +52,105 @@ impl Color {
 }
 
 
+/// Decodes an sRGB encoded value in [0, 1] with the piecewise sRGB curve.
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.040_45 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
 pub fn linear_to_gamma(value: f32) -> f32 {
     if value > 0.0 {
         return value.sqrt();
     }
     0.0
 }
+
+/// Wavelength range, in nanometres, used when integrating a spectrum to RGB.
+pub const VISIBLE_MIN: f32 = 380.0;
+pub const VISIBLE_MAX: f32 = 780.0;
+const SPECTRAL_SAMPLES: usize = 41;
+
+/// CIE 1931 colour matching functions at `wavelength` nanometres, using the
+/// multi-lobe gaussian fit from Wyman, Sloan and Shirley (2013).
+pub fn cie_xyz(wavelength: f32) -> Vec3 {
+    fn lobe(x: f32, mu: f32, sigma_low: f32, sigma_high: f32) -> f32 {
+        let t = (x - mu) / if x < mu { sigma_low } else { sigma_high };
+        (-0.5 * t * t).exp()
+    }
+
+    let l = wavelength;
+    vec3(
+        1.056 * lobe(l, 599.8, 37.9, 31.0) + 0.362 * lobe(l, 442.0, 16.0, 26.7)
+            - 0.065 * lobe(l, 501.1, 20.4, 26.2),
+        0.821 * lobe(l, 568.8, 46.9, 40.5) + 0.286 * lobe(l, 530.9, 16.3, 31.1),
+        1.217 * lobe(l, 437.0, 11.8, 36.0) + 0.681 * lobe(l, 459.0, 26.0, 13.8),
+    )
+}
+
+pub fn xyz_to_linear_srgb(xyz: Vec3) -> Vec3 {
+    const XYZ_TO_SRGB: Mat3 = Mat3::from_cols(
+        vec3(3.240_454_2, -0.969_266, 0.055_643_4),
+        vec3(-1.537_138_5, 1.876_010_8, -0.204_025_9),
+        vec3(-0.498_531_4, 0.041_556, 1.057_225_2),
+    );
+    XYZ_TO_SRGB * xyz
+}
+
+/// Integrates `spectrum` against the colour matching functions.
+fn spectrum_to_xyz(spectrum: impl Fn(f32) -> f32) -> Vec3 {
+    let step = (VISIBLE_MAX - VISIBLE_MIN) / (SPECTRAL_SAMPLES - 1) as f32;
+    let xyz = (0..SPECTRAL_SAMPLES)
+        .map(|i| VISIBLE_MIN + i as f32 * step)
+        .map(|wavelength| cie_xyz(wavelength) * spectrum(wavelength))
+        .fold(Vec3::ZERO, |acc, xyz| acc + xyz);
+    xyz * step
+}
+
+fn integrate_spectrum(spectrum: impl Fn(f32) -> f32) -> Vec3 {
+    xyz_to_linear_srgb(spectrum_to_xyz(spectrum))
+}
+
+static FLAT_SPECTRUM_RGB: LazyLock<Vec3> = LazyLock::new(|| integrate_spectrum(|_| 1.0));
+
+/// Converts a reflectance spectrum to linear sRGB, white balanced so that a
+/// flat spectrum of 1.0 maps to white.
+pub fn reflectance_spectrum_to_rgb(spectrum: impl Fn(f32) -> f32) -> Vec3 {
+    integrate_spectrum(spectrum) / *FLAT_SPECTRUM_RGB
+}
+
+/// Linear sRGB colour of a black body at `temperature` kelvin, scaled to unit
+/// luminance so it can be multiplied by an intensity.
+pub fn blackbody(temperature: f32) -> Vec3 {
+    if temperature <= 0.0 {
+        return Vec3::ZERO;
+    }
+
+    let xyz = spectrum_to_xyz(|wavelength| planck(wavelength, temperature));
+
+    if xyz.y <= 0.0 {
+        return Vec3::ZERO;
+    }
+    xyz_to_linear_srgb(xyz / xyz.y).max(Vec3::ZERO)
+}
+
+/// Luminance, in nits, of a black body at `temperature` kelvin. It climbs
+/// steeply: 1000 K barely glows while 2000 K is thousands of times brighter.
+pub fn blackbody_luminance(temperature: f32) -> f32 {
+    if temperature <= 0.0 {
+        return 0.0;
+    }
+
+    const MAX_LUMINOUS_EFFICACY: f32 = 683.0;
+    MAX_LUMINOUS_EFFICACY * spectrum_to_xyz(|wavelength| planck(wavelength, temperature)).y
+}
+
+/// Planck's law: spectral radiance in W/(sr m² nm) at `wavelength`
+/// nanometres.
+fn planck(wavelength: f32, temperature: f32) -> f32 {
+    const C1: f32 = 1.191_043e20;
+    const C2: f32 = 1.438_777e7;
+    C1 * wavelength.powi(-5) / ((C2 / (wavelength * temperature)).exp() - 1.0)
+}
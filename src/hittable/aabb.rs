@@ -15,7 +15,12 @@ impl Aabb {
         instance.pad_to_minimums();
         instance
     }
-    pub fn hit(&self, ray: &Ray, mut ray_t: Interval) -> bool {
+    pub fn hit(&self, ray: &Ray, ray_t: Interval) -> bool {
+        self.clip(ray, ray_t).is_some()
+    }
+
+    /// The part of `ray_t` over which the ray is inside the box.
+    pub fn clip(&self, ray: &Ray, mut ray_t: Interval) -> Option<Interval> {
         for axis in 0..3 {
             let interval = self.axis_interval(axis);
             let axis_dir_inv = 1.0 / ray.direction[axis];
@@ -31,10 +36,10 @@ impl Aabb {
                 ray_t.max = t0.min(ray_t.max);
             }
             if ray_t.max <= ray_t.min {
-                return false;
+                return None;
             }
         }
-        true
+        Some(ray_t)
     }
 
     pub fn axis_interval(&self, n: usize) -> &Interval {
@@ -54,6 +59,14 @@ impl Aabb {
         }
     }
 
+    /// False for the infinite box of a `Plane`, and for objects that don't
+    /// report a box at all.
+    pub fn is_bounded(&self) -> bool {
+        [&self.x, &self.y, &self.z]
+            .iter()
+            .all(|interval| interval.min.is_finite() && interval.max.is_finite())
+    }
+
     pub const EMPTY: Self = Self {
         x: Interval::EMPTY,
         y: Interval::EMPTY,
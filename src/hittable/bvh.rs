@@ -77,6 +77,10 @@ impl Hittable for BvhNode {
 
         hit_right.or(hit_left)
     }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
 }
 
 fn box_compare(a: &Arc<dyn Hittable>, b: &Arc<dyn Hittable>, axis_index: usize) -> Ordering {
@@ -0,0 +1,145 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::disk::{disk_extent, perpendicular_frame};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::{Vec3, vec3};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+/// A cone with a base of `radius` at `base` and its apex at `base + axis`,
+/// open at the base unless `capped`.
+///
+/// On the side `u` runs around the axis and `v` from the base to the apex; on
+/// the cap `v` runs out from the centre, as on a `Disk`.
+pub struct Cone {
+    base: Vec3,
+    e1: Vec3,
+    e2: Vec3,
+    w: Vec3,
+    height: f32,
+    radius: f32,
+    capped: bool,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Cone {
+    pub fn new(base: Vec3, axis: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        assert!(radius > 0.0);
+        let height = axis.length();
+        let w = axis / height;
+        let (e1, e2) = perpendicular_frame(w);
+
+        let extent = disk_extent(w, radius);
+        let bottom = Aabb::from((base - extent, base + extent));
+        let apex = Aabb::from((base + axis, base + axis));
+
+        Self {
+            base,
+            e1,
+            e2,
+            w,
+            height,
+            radius,
+            capped: false,
+            material,
+            bounding_box: Aabb::from((&bottom, &apex)),
+        }
+    }
+
+    /// Closes the base with a disk.
+    pub fn capped(mut self) -> Self {
+        self.capped = true;
+        self
+    }
+
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        vec3(v.dot(self.e1), v.dot(self.e2), v.dot(self.w))
+    }
+
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        v.x * self.e1 + v.y * self.e2 + v.z * self.w
+    }
+}
+
+impl Hittable for Cone {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let origin = self.to_local(ray.origin - self.base);
+        let direction = self.to_local(ray.direction);
+
+        let mut closest: Option<(f32, bool)> = None;
+        let mut consider = |t: f32, on_side: bool| {
+            if ray_interval.surrounds(t) && closest.is_none_or(|(best, _)| t < best) {
+                closest = Some((t, on_side));
+            }
+        };
+
+        // x^2 + y^2 = k^2 (height - z)^2, with k the slope of the side.
+        let k = self.radius / self.height;
+        let k2 = k * k;
+        let below_apex = self.height - origin.z;
+        let a =
+            direction.x * direction.x + direction.y * direction.y - k2 * direction.z * direction.z;
+        let h = origin.x * direction.x + origin.y * direction.y + k2 * below_apex * direction.z;
+        let c = origin.x * origin.x + origin.y * origin.y - k2 * below_apex * below_apex;
+
+        let mut side_roots = [f32::NAN; 2];
+        if a.abs() > f32::EPSILON {
+            let discriminant = h * h - a * c;
+            if discriminant >= 0.0 {
+                let sqrt_d = discriminant.sqrt();
+                side_roots = [(-h - sqrt_d) / a, (-h + sqrt_d) / a];
+            }
+        } else if h.abs() > f32::EPSILON {
+            // Parallel to the side, so the ray crosses it only once.
+            side_roots[0] = -c / (2.0 * h);
+        }
+        for t in side_roots {
+            // The equation also describes a mirrored cone above the apex.
+            let z = origin.z + t * direction.z;
+            if (0.0..=self.height).contains(&z) {
+                consider(t, true);
+            }
+        }
+
+        if self.capped && direction.z.abs() > f32::EPSILON {
+            let t = -origin.z / direction.z;
+            let p = origin + t * direction;
+            if p.x * p.x + p.y * p.y <= self.radius * self.radius {
+                consider(t, false);
+            }
+        }
+
+        let (t, on_side) = closest?;
+        let local = origin + t * direction;
+        let phi = local.y.atan2(local.x).rem_euclid(TAU);
+        let around = TAU * self.to_world(vec3(-local.y, local.x, 0.0));
+        let radial = self.to_world(vec3(phi.cos(), phi.sin(), 0.0)) * self.radius;
+
+        let (outward_normal, uv, dpdv) = if on_side {
+            let ring_radius = local.truncate().length();
+            let normal = self
+                .to_world(vec3(local.x, local.y, k * ring_radius))
+                .normalize_or(self.w);
+            (
+                normal,
+                (phi / TAU, local.z / self.height),
+                self.w * self.height - radial,
+            )
+        } else {
+            let distance = local.truncate().length();
+            (-self.w, (phi / TAU, distance / self.radius), radial)
+        };
+
+        let rec = HitRecord::new(ray.at(t), t, ray, outward_normal, self.material.clone(), uv)
+            .with_tangents(around, dpdv);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
@@ -3,6 +3,7 @@ use crate::hittable::{HitRecord, Hittable};
 use crate::interval::Interval;
 use crate::material::Material;
 use crate::material::isotropic::Isotropic;
+use crate::material::medium::MediumMaterial;
 use crate::material::texture::Texture;
 use crate::ray::Ray;
 use glam::Vec3;
@@ -11,7 +12,7 @@ use std::sync::Arc;
 pub struct ConstantMedium {
     boundary: Arc<dyn Hittable>,
     neg_inv_density: f32,
-    phase_function: Arc<dyn Material>,
+    material: Arc<MediumMaterial>,
 }
 
 impl ConstantMedium {
@@ -19,7 +20,7 @@ impl ConstantMedium {
         Self {
             boundary,
             neg_inv_density: -density.recip(),
-            phase_function: Arc::new(Isotropic::from(texture)),
+            material: Arc::new(MediumMaterial::new(Arc::new(Isotropic::from(texture)))),
         }
     }
 
@@ -27,9 +28,25 @@ impl ConstantMedium {
         Self {
             boundary,
             neg_inv_density: -density.recip(),
-            phase_function: Arc::new(Isotropic::from(albedo)),
+            material: Arc::new(MediumMaterial::new(Arc::new(Isotropic::from(albedo)))),
         }
     }
+
+    /// Scatters by `phase_function` in place of the default isotropic one,
+    /// such as a `HenyeyGreenstein`.
+    pub fn with_phase_function(mut self, phase_function: Arc<dyn Material>) -> Self {
+        let material = (*self.material).clone();
+        self.material = Arc::new(material.with_phase_function(phase_function));
+        self
+    }
+
+    /// Makes the medium glow with `emission`, looked up at each collision
+    /// point.
+    pub fn with_emission(mut self, emission: Box<dyn Texture>) -> Self {
+        let material = (*self.material).clone();
+        self.material = Arc::new(material.with_emission(Arc::from(emission)));
+        self
+    }
 }
 
 impl Hittable for ConstantMedium {
@@ -54,7 +71,7 @@ impl Hittable for ConstantMedium {
 
         let ray_length = r.direction.length();
         let distance_inside_boundary = (rec2.t - rec1.t) * ray_length;
-        let hit_distance = self.neg_inv_density * rand::random_range(0.0f32..=1.0).log2();
+        let hit_distance = self.neg_inv_density * rand::random_range(0.0f32..=1.0).ln();
 
         if hit_distance > distance_inside_boundary {
             return None;
@@ -62,15 +79,7 @@ impl Hittable for ConstantMedium {
 
         let t = rec1.t + hit_distance / ray_length;
 
-        let rec = HitRecord {
-            point: r.at(t),
-            normal: Vec3::X,
-            material: self.phase_function.clone(),
-            t,
-            u: 0.0,
-            v: 0.0,
-            front_face: true,
-        };
+        let rec = HitRecord::in_medium(r, t, self.material.clone());
 
         Some(rec)
     }
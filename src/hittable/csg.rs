@@ -0,0 +1,130 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::ray::Ray;
+use std::sync::Arc;
+
+#[derive(Copy, Clone)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    /// The left object with the right one carved out of it.
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Constructive solid geometry over two closed objects, such as spheres,
+/// cuboids or other `Csg`s.
+///
+/// Both objects' crossings are merged along the ray, tracking whether it is
+/// inside each, and only those where it enters or leaves the combined solid
+/// are kept. Surfaces keep the material of the object they came from.
+pub struct Csg {
+    left: Arc<dyn Hittable>,
+    right: Arc<dyn Hittable>,
+    operation: CsgOperation,
+    bounding_box: Aabb,
+}
+
+impl Csg {
+    pub fn new(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>, operation: CsgOperation) -> Self {
+        let (a, b) = (left.bounding_box(), right.bounding_box());
+        let bounding_box = match operation {
+            CsgOperation::Union => Aabb::from((a, b)),
+            CsgOperation::Intersection => Aabb::new(
+                Interval::new(a.x.min.max(b.x.min), a.x.max.min(b.x.max)),
+                Interval::new(a.y.min.max(b.y.min), a.y.max.min(b.y.max)),
+                Interval::new(a.z.min.max(b.z.min), a.z.max.min(b.z.max)),
+            ),
+            CsgOperation::Difference => Aabb::new(a.x, a.y, a.z),
+        };
+
+        Self {
+            left,
+            right,
+            operation,
+            bounding_box,
+        }
+    }
+
+    pub fn union(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(left, right, CsgOperation::Union)
+    }
+
+    pub fn intersection(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(left, right, CsgOperation::Intersection)
+    }
+
+    pub fn difference(left: Arc<dyn Hittable>, right: Arc<dyn Hittable>) -> Self {
+        Self::new(left, right, CsgOperation::Difference)
+    }
+}
+
+impl Hittable for Csg {
+    fn hit(&self, r: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        if !self.bounding_box.hit(r, ray_interval) {
+            return None;
+        }
+        self.hits(r, ray_interval).into_iter().next()
+    }
+
+    fn hits(&self, r: &Ray, ray_interval: Interval) -> Vec<HitRecord> {
+        // Look past the end of the interval, otherwise a ray that starts
+        // inside an object but doesn't leave it in time would seem outside.
+        let open_ended = Interval::new(ray_interval.min, f32::INFINITY);
+        let left = self.left.hits(r, open_ended);
+        let right = self.right.hits(r, open_ended);
+
+        // The first crossing leaving an object means the ray started inside.
+        let mut in_left = left.first().is_some_and(|rec| !rec.front_face);
+        let mut in_right = right.first().is_some_and(|rec| !rec.front_face);
+        let mut inside = self.operation.contains(in_left, in_right);
+
+        let mut left = left.into_iter().peekable();
+        let mut right = right.into_iter().peekable();
+        let mut hits = Vec::new();
+        loop {
+            let from_left = match (left.peek(), right.peek()) {
+                (Some(a), Some(b)) => a.t <= b.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => break,
+            };
+            let mut rec = if from_left {
+                let rec = left.next().unwrap();
+                in_left = rec.front_face;
+                rec
+            } else {
+                let rec = right.next().unwrap();
+                in_right = rec.front_face;
+                rec
+            };
+            if rec.t > ray_interval.max {
+                break;
+            }
+
+            let now_inside = self.operation.contains(in_left, in_right);
+            if now_inside != inside {
+                // The normal already faces the ray; only which side of the
+                // combined solid the ray is on can differ from the child's.
+                rec.front_face = now_inside;
+                inside = now_inside;
+                hits.push(rec);
+            }
+        }
+        hits
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
@@ -0,0 +1,267 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::{Vec3, vec3};
+use std::sync::Arc;
+
+/// How the width of a `Curve` is presented to the ray.
+#[derive(Copy, Clone)]
+pub enum CurveShape {
+    /// A flat strip that always turns to face the ray, for fine hair and fur.
+    Ribbon,
+    /// Shaded as a round tube, for thicker strands seen close up.
+    Tube,
+}
+
+/// A cubic Bézier segment whose width varies linearly along it, for hair,
+/// grass and fur.
+///
+/// The curve is intersected by splitting it in the ray's own frame until the
+/// pieces are flat enough to treat as lines, discarding any whose bounds miss
+/// the ray. `u` runs along the curve and `v` across its width.
+pub struct Curve {
+    control_points: [Vec3; 4],
+    widths: [f32; 2],
+    /// The part of a longer strand this segment covers, for texturing.
+    u_range: [f32; 2],
+    shape: CurveShape,
+    max_depth: u32,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Curve {
+    /// `widths` are at the start and the end of the segment.
+    pub fn new(
+        control_points: [Vec3; 4],
+        widths: [f32; 2],
+        shape: CurveShape,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        Self::segment(control_points, widths, [0.0, 1.0], shape, material)
+    }
+
+    /// Splits a piecewise cubic Bézier through `3n + 1` control points into one
+    /// `Curve` per segment, each bounded on its own. The width tapers from
+    /// `widths[0]` at the root to `widths[1]` at the tip, and `u` runs from 0
+    /// to 1 over the whole strand.
+    pub fn strand(
+        points: &[Vec3],
+        widths: [f32; 2],
+        shape: CurveShape,
+        material: Arc<dyn Material>,
+    ) -> Vec<Curve> {
+        assert!(points.len() >= 4 && (points.len() - 1).is_multiple_of(3));
+        let segments = (points.len() - 1) / 3;
+        let along = |i: usize| i as f32 / segments as f32;
+        let width = |u: f32| widths[0] + (widths[1] - widths[0]) * u;
+
+        (0..segments)
+            .map(|i| {
+                let u_range = [along(i), along(i + 1)];
+                Self::segment(
+                    [
+                        points[3 * i],
+                        points[3 * i + 1],
+                        points[3 * i + 2],
+                        points[3 * i + 3],
+                    ],
+                    u_range.map(width),
+                    u_range,
+                    shape,
+                    material.clone(),
+                )
+            })
+            .collect()
+    }
+
+    fn segment(
+        control_points: [Vec3; 4],
+        widths: [f32; 2],
+        u_range: [f32; 2],
+        shape: CurveShape,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        assert!(widths[0] >= 0.0 && widths[1] >= 0.0);
+        let max_width = widths[0].max(widths[1]);
+
+        // The hull of the control points contains the curve.
+        let half_width = Vec3::splat(0.5 * max_width);
+        let low = control_points.iter().fold(Vec3::INFINITY, |a, &p| a.min(p));
+        let high = control_points
+            .iter()
+            .fold(Vec3::NEG_INFINITY, |a, &p| a.max(p));
+
+        // Splits needed before the pieces are within a fraction of the width
+        // of a straight line, from the curve's second differences.
+        let bend = (0..2)
+            .map(|i| {
+                (control_points[i] - 2.0 * control_points[i + 1] + control_points[i + 2])
+                    .abs()
+                    .max_element()
+            })
+            .fold(0.0, f32::max);
+        let tolerance = 0.05 * max_width;
+        let max_depth = if bend > 0.0 && tolerance > 0.0 {
+            ((2.0_f32.sqrt() * 6.0 * bend / (8.0 * tolerance)).log2() / 2.0).clamp(0.0, 10.0) as u32
+        } else {
+            0
+        };
+
+        Self {
+            control_points,
+            widths,
+            u_range,
+            shape,
+            max_depth,
+            material,
+            bounding_box: Aabb::from((low - half_width, high + half_width)),
+        }
+    }
+
+    fn width_at(&self, u: f32) -> f32 {
+        self.widths[0] + (self.widths[1] - self.widths[0]) * u
+    }
+
+    /// Searches the part of the curve from `u0` to `u1`, given by `points` in
+    /// ray space, for the nearest crossing within `range` of distances along
+    /// the ray. Narrows `range` to each crossing it finds.
+    fn intersect(
+        &self,
+        points: [Vec3; 4],
+        (u0, u1): (f32, f32),
+        depth: u32,
+        range: &mut Interval,
+        found: &mut Option<(f32, f32)>,
+    ) {
+        let half_width = 0.5 * self.width_at(u0).max(self.width_at(u1));
+        let low = points.iter().fold(Vec3::INFINITY, |a, &p| a.min(p)) - half_width;
+        let high = points.iter().fold(Vec3::NEG_INFINITY, |a, &p| a.max(p)) + half_width;
+        if low.x > 0.0 || high.x < 0.0 || low.y > 0.0 || high.y < 0.0 {
+            return;
+        }
+        if high.z < range.min || low.z > range.max {
+            return;
+        }
+
+        if depth > 0 {
+            let (left, right) = split_bezier(&points, 0.5);
+            let middle = 0.5 * (u0 + u1);
+            self.intersect(left, (u0, middle), depth - 1, range, found);
+            self.intersect(right, (middle, u1), depth - 1, range, found);
+            return;
+        }
+
+        // Flat enough to treat as a line, cut off square at either end so
+        // neighbouring pieces neither overlap nor leave a gap.
+        let ahead = |from: Vec3, towards: Vec3| (towards - from).truncate().dot(-from.truncate());
+        if ahead(points[0], points[1]) < 0.0 || ahead(points[3], points[2]) < 0.0 {
+            return;
+        }
+
+        let line = (points[3] - points[0]).truncate();
+        if line.length_squared() == 0.0 {
+            return;
+        }
+        let w = (-points[0].truncate().dot(line) / line.length_squared()).clamp(0.0, 1.0);
+        let u = u0 + (u1 - u0) * w;
+
+        let closest = bezier(&points, w);
+        let half_width = 0.5 * self.width_at(u);
+        if closest.truncate().length_squared() > half_width * half_width {
+            return;
+        }
+        if !range.surrounds(closest.z) {
+            return;
+        }
+
+        range.max = closest.z;
+        *found = Some((closest.z, u));
+    }
+}
+
+fn bezier(points: &[Vec3; 4], u: f32) -> Vec3 {
+    let [a, b, c] = [0, 1, 2].map(|i| points[i].lerp(points[i + 1], u));
+    a.lerp(b, u).lerp(b.lerp(c, u), u)
+}
+
+fn bezier_derivative(points: &[Vec3; 4], u: f32) -> Vec3 {
+    let s = 1.0 - u;
+    3.0 * (s * s * (points[1] - points[0])
+        + 2.0 * s * u * (points[2] - points[1])
+        + u * u * (points[3] - points[2]))
+}
+
+/// De Casteljau's construction, giving the control points of either side.
+fn split_bezier(points: &[Vec3; 4], u: f32) -> ([Vec3; 4], [Vec3; 4]) {
+    let [a, b, c] = [0, 1, 2].map(|i| points[i].lerp(points[i + 1], u));
+    let (d, e) = (a.lerp(b, u), b.lerp(c, u));
+    let middle = d.lerp(e, u);
+    ([points[0], a, d, middle], [middle, e, c, points[3]])
+}
+
+impl Hittable for Curve {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        // In ray space the ray runs up the z axis, and z is the distance
+        // travelled along it.
+        let length = ray.direction.length();
+        let forward = ray.direction / length;
+        let (right, up) = forward.any_orthonormal_pair();
+        let points = self.control_points.map(|p| {
+            let p = p - ray.origin;
+            vec3(p.dot(right), p.dot(up), p.dot(forward))
+        });
+
+        let mut range = Interval::new(ray_interval.min * length, ray_interval.max * length);
+        let mut found = None;
+        self.intersect(points, (0.0, 1.0), self.max_depth, &mut range, &mut found);
+        let (distance, u) = found?;
+
+        // Face the ray across the curve, with `side` along its width.
+        let center = bezier(&self.control_points, u);
+        let tangent = bezier_derivative(&self.control_points, u);
+        let along = tangent.normalize_or(right);
+        let facing = (forward.dot(along) * along - forward).normalize_or(-forward);
+        let side = along.cross(facing);
+
+        let half_width = 0.5 * self.width_at(u);
+        let mut t = distance / length;
+        let offset = if half_width > 0.0 {
+            ((ray.at(t) - center).dot(side) / half_width).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let outward_normal = match self.shape {
+            CurveShape::Ribbon => facing,
+            CurveShape::Tube => {
+                // Step back from the centre line to the near side of the tube.
+                let bulge = (1.0 - offset * offset).sqrt();
+                t -= half_width * bulge / length;
+                if !ray_interval.surrounds(t) {
+                    return None;
+                }
+                (bulge * facing + offset * side).normalize()
+            }
+        };
+
+        let [u_start, u_end] = self.u_range;
+        let rec = HitRecord::new(
+            ray.at(t),
+            t,
+            ray,
+            outward_normal,
+            self.material.clone(),
+            (u_start + (u_end - u_start) * u, 0.5 * (offset + 1.0)),
+        )
+        .with_tangents(tangent / (u_end - u_start), side * 2.0 * half_width);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
@@ -0,0 +1,144 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::texture::Texture;
+use crate::ray::Ray;
+use glam::vec3;
+use std::sync::Arc;
+
+/// How a mask value decides whether a hit is kept.
+#[derive(Copy, Clone)]
+pub enum AlphaTest {
+    /// Hits where the mask is below the threshold are ignored.
+    Threshold(f32),
+    /// Hits are kept with probability equal to the mask, which gives soft,
+    /// noise-free edges once averaged over many samples.
+    Stochastic,
+}
+
+/// Which part of the mask texture holds the opacity.
+#[derive(Copy, Clone)]
+enum MaskChannel {
+    Alpha,
+    Luminance,
+}
+
+/// Cuts holes in `object` wherever the opacity mask says it is transparent,
+/// for leaves, fences and decals.
+pub struct Cutout {
+    object: Arc<dyn Hittable>,
+    mask: Box<dyn Texture>,
+    channel: MaskChannel,
+    test: AlphaTest,
+}
+
+impl Cutout {
+    /// Uses the alpha channel of `mask`, such as a PNG with transparency.
+    pub fn new(object: Arc<dyn Hittable>, mask: Box<dyn Texture>, test: AlphaTest) -> Self {
+        Self {
+            object,
+            mask,
+            channel: MaskChannel::Alpha,
+            test,
+        }
+    }
+
+    /// Uses the brightness of `mask`, so any texture can act as the mask.
+    pub fn from_luminance(
+        object: Arc<dyn Hittable>,
+        mask: Box<dyn Texture>,
+        test: AlphaTest,
+    ) -> Self {
+        Self {
+            object,
+            mask,
+            channel: MaskChannel::Luminance,
+            test,
+        }
+    }
+
+    fn is_opaque(&self, rec: &HitRecord) -> bool {
+        let opacity = match self.channel {
+            MaskChannel::Alpha => self.mask.alpha_at(rec),
+            MaskChannel::Luminance => self.mask.value_at(rec).dot(vec3(0.2126, 0.7152, 0.0722)),
+        };
+
+        match self.test {
+            AlphaTest::Threshold(threshold) => opacity >= threshold,
+            AlphaTest::Stochastic => rand::random::<f32>() < opacity,
+        }
+    }
+}
+
+impl Hittable for Cutout {
+    fn hit(&self, r: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let mut interval = ray_interval;
+
+        // Keep looking past transparent hits, e.g. to the far side of a sphere.
+        loop {
+            let mut rec = self.object.hit(r, interval)?;
+            // The mask is filtered over the pixel's footprint so it doesn't
+            // alias in the distance.
+            rec.compute_footprint(r);
+            if self.is_opaque(&rec) {
+                return Some(rec);
+            }
+            // A fixed step vanishes in the rounding of large distances, and
+            // the same hit would be found again forever.
+            interval = Interval::new((rec.t + 0.0000_1).max(rec.t.next_up()), interval.max);
+        }
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.object.bounding_box()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hittable::quad::Quad;
+    use crate::material::lambertian::Lambertian;
+    use glam::Vec3;
+
+    /// Transparent on the left half, opaque on the right.
+    struct HalfMask;
+
+    impl Texture for HalfMask {
+        fn value(&self, _u: f32, _v: f32, _point: Vec3) -> Vec3 {
+            Vec3::ZERO
+        }
+
+        fn alpha(&self, u: f32, _v: f32, _point: Vec3) -> f32 {
+            if u < 0.5 { 0.0 } else { 1.0 }
+        }
+    }
+
+    /// A unit square facing +z at the given depth, with `u` along x.
+    fn leaf(z: f32) -> Cutout {
+        let material = Arc::new(Lambertian::from(Vec3::ONE));
+        let quad: Arc<dyn Hittable> =
+            Arc::new(Quad::new(vec3(0.0, 0.0, z), Vec3::X, Vec3::Y, material));
+        Cutout::new(quad, Box::new(HalfMask), AlphaTest::Threshold(0.5))
+    }
+
+    fn hit(cutout: &Cutout, x: f32) -> Option<HitRecord> {
+        cutout.hit(
+            &Ray::new(vec3(x, 0.5, 1.0), -Vec3::Z, 0.0),
+            Interval::new(0.0001, f32::INFINITY),
+        )
+    }
+
+    #[test]
+    fn alpha_masks_cut_holes() {
+        assert!(hit(&leaf(0.0), 0.25).is_none());
+        let rec = hit(&leaf(0.0), 0.75).unwrap();
+        assert!((rec.t - 1.0).abs() < 1e-5, "hit at t = {}", rec.t);
+    }
+
+    #[test]
+    fn holes_far_away_are_passed_through() {
+        assert!(hit(&leaf(-1.0e4), 0.25).is_none());
+        assert!(hit(&leaf(-1.0e4), 0.75).is_some());
+    }
+}
@@ -0,0 +1,133 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::disk::{disk_extent, perpendicular_frame};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::{Vec3, vec3};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+/// A cylinder from `base` to `base + axis`, open unless `capped`.
+///
+/// On the side `u` runs around the axis and `v` from base to top; on the caps
+/// `u` is the same angle and `v` runs out from the centre, as on a `Disk`.
+pub struct Cylinder {
+    base: Vec3,
+    e1: Vec3,
+    e2: Vec3,
+    w: Vec3,
+    height: f32,
+    radius: f32,
+    capped: bool,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Cylinder {
+    pub fn new(base: Vec3, axis: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        assert!(radius > 0.0);
+        let height = axis.length();
+        let w = axis / height;
+        let (e1, e2) = perpendicular_frame(w);
+
+        let extent = disk_extent(w, radius);
+        let bottom = Aabb::from((base - extent, base + extent));
+        let top = Aabb::from((base + axis - extent, base + axis + extent));
+
+        Self {
+            base,
+            e1,
+            e2,
+            w,
+            height,
+            radius,
+            capped: false,
+            material,
+            bounding_box: Aabb::from((&bottom, &top)),
+        }
+    }
+
+    /// Closes both ends with disks.
+    pub fn capped(mut self) -> Self {
+        self.capped = true;
+        self
+    }
+
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        vec3(v.dot(self.e1), v.dot(self.e2), v.dot(self.w))
+    }
+
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        v.x * self.e1 + v.y * self.e2 + v.z * self.w
+    }
+}
+
+impl Hittable for Cylinder {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let origin = self.to_local(ray.origin - self.base);
+        let direction = self.to_local(ray.direction);
+
+        let mut closest: Option<(f32, bool)> = None;
+        let mut consider = |t: f32, on_side: bool| {
+            if ray_interval.surrounds(t) && closest.is_none_or(|(best, _)| t < best) {
+                closest = Some((t, on_side));
+            }
+        };
+
+        let a = direction.x * direction.x + direction.y * direction.y;
+        let h = origin.x * direction.x + origin.y * direction.y;
+        let c = origin.x * origin.x + origin.y * origin.y - self.radius * self.radius;
+        let discriminant = h * h - a * c;
+        if a > f32::EPSILON && discriminant >= 0.0 {
+            let sqrt_d = discriminant.sqrt();
+            for t in [(-h - sqrt_d) / a, (-h + sqrt_d) / a] {
+                let z = origin.z + t * direction.z;
+                if (0.0..=self.height).contains(&z) {
+                    consider(t, true);
+                }
+            }
+        }
+
+        if self.capped && direction.z.abs() > f32::EPSILON {
+            for cap in [0.0, self.height] {
+                let t = (cap - origin.z) / direction.z;
+                let p = origin + t * direction;
+                if p.x * p.x + p.y * p.y <= self.radius * self.radius {
+                    consider(t, false);
+                }
+            }
+        }
+
+        let (t, on_side) = closest?;
+        let local = origin + t * direction;
+        let phi = local.y.atan2(local.x).rem_euclid(TAU);
+        let around = TAU * self.to_world(vec3(-local.y, local.x, 0.0));
+        let radial = self.to_world(vec3(phi.cos(), phi.sin(), 0.0)) * self.radius;
+
+        let (outward_normal, uv, dpdv) = if on_side {
+            (
+                radial / self.radius,
+                (phi / TAU, local.z / self.height),
+                self.w * self.height,
+            )
+        } else {
+            let distance = local.truncate().length();
+            let normal = if local.z > 0.5 * self.height {
+                self.w
+            } else {
+                -self.w
+            };
+            (normal, (phi / TAU, distance / self.radius), radial)
+        };
+
+        let rec = HitRecord::new(ray.at(t), t, ray, outward_normal, self.material.clone(), uv)
+            .with_tangents(around, dpdv);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
@@ -0,0 +1,92 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::{Vec3, vec2};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+/// A flat disk facing along `normal`. `u` runs around the rim from the first
+/// axis of the frame picked by `perpendicular_frame`, `v` out from the centre.
+pub struct Disk {
+    center: Vec3,
+    normal: Vec3,
+    e1: Vec3,
+    e2: Vec3,
+    radius: f32,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Disk {
+    pub fn new(center: Vec3, normal: Vec3, radius: f32, material: Arc<dyn Material>) -> Self {
+        assert!(radius > 0.0);
+        let normal = normal.normalize();
+        let (e1, e2) = perpendicular_frame(normal);
+        let extent = disk_extent(normal, radius);
+
+        Self {
+            center,
+            normal,
+            e1,
+            e2,
+            radius,
+            material,
+            bounding_box: Aabb::from((center - extent, center + extent)),
+        }
+    }
+}
+
+impl Hittable for Disk {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction);
+        if denom.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(self.center - ray.origin) / denom;
+        if !ray_interval.surrounds(t) {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = point - self.center;
+        let local = vec2(offset.dot(self.e1), offset.dot(self.e2));
+        let distance = local.length();
+        if distance > self.radius {
+            return None;
+        }
+
+        let phi = local.y.atan2(local.x).rem_euclid(TAU);
+        let radial = (self.e1 * phi.cos() + self.e2 * phi.sin()) * self.radius;
+        let rec = HitRecord::new(
+            point,
+            t,
+            ray,
+            self.normal,
+            self.material.clone(),
+            (phi / TAU, distance / self.radius),
+        )
+        .with_tangents(TAU * (local.x * self.e2 - local.y * self.e1), radial);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
+
+/// Two unit vectors perpendicular to `axis`, ordered so that their cross
+/// product is `axis`.
+pub(super) fn perpendicular_frame(axis: Vec3) -> (Vec3, Vec3) {
+    let (e1, _) = axis.any_orthonormal_pair();
+    (e1, axis.cross(e1))
+}
+
+/// Half the extent along each world axis of a circle of `radius` facing along
+/// the unit vector `normal`.
+pub(super) fn disk_extent(normal: Vec3, radius: f32) -> Vec3 {
+    radius * (Vec3::ONE - normal * normal).max(Vec3::ZERO).map(f32::sqrt)
+}
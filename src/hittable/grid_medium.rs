@@ -0,0 +1,441 @@
+use crate::color::{blackbody, blackbody_luminance};
+use crate::hittable::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::material::isotropic::Isotropic;
+use crate::material::medium::MediumMaterial;
+use crate::material::texture::Texture;
+use crate::perlin::Perlin;
+use crate::ray::Ray;
+use glam::{Affine3A, Vec3, vec3};
+use std::sync::Arc;
+
+/// Density samples over a regular 3D grid, looked up with trilinear
+/// interpolation between sample centres.
+pub struct DensityGrid {
+    resolution: [usize; 3],
+    voxels: Voxels,
+    max_value: f32,
+}
+
+enum Voxels {
+    /// Ordered x fastest, then y, then z.
+    Dense(Vec<f32>),
+    /// Cubes of `brick_size` voxels on a side, stored only where they hold
+    /// something other than the background, as simulations mostly do not.
+    Sparse {
+        brick_size: usize,
+        bricks_per_axis: [usize; 3],
+        /// The start of each brick's values in `values`, or `None` for
+        /// background, ordered like voxels.
+        offsets: Vec<Option<usize>>,
+        values: Vec<f32>,
+        background: f32,
+    },
+}
+
+impl DensityGrid {
+    pub fn new(resolution: [usize; 3], values: Vec<f32>) -> Self {
+        assert!(resolution.iter().all(|&n| n > 0));
+        assert_eq!(values.len(), resolution.iter().product::<usize>());
+        assert!(values.iter().all(|&value| value >= 0.0));
+
+        let max_value = values.iter().copied().fold(0.0, f32::max);
+        Self {
+            resolution,
+            voxels: Voxels::Dense(values),
+            max_value,
+        }
+    }
+
+    /// A grid that is `background` everywhere except in the given bricks,
+    /// each `brick_size` voxels on a side with values ordered x fastest and
+    /// keyed by the voxel at its lowest corner, a multiple of `brick_size`.
+    /// A later brick at the same place replaces an earlier one.
+    pub fn from_bricks(
+        resolution: [usize; 3],
+        brick_size: usize,
+        background: f32,
+        bricks: impl IntoIterator<Item = ([usize; 3], Vec<f32>)>,
+    ) -> Self {
+        assert!(resolution.iter().all(|&n| n > 0) && brick_size > 0);
+        assert!(background >= 0.0);
+
+        let bricks_per_axis = resolution.map(|n| n.div_ceil(brick_size));
+        let brick_volume = brick_size.pow(3);
+        let brick_count = bricks_per_axis
+            .iter()
+            .try_fold(1usize, |count, &n| count.checked_mul(n))
+            .expect("too many bricks to index");
+        let mut offsets = vec![None; brick_count];
+        let mut values = Vec::new();
+        let mut max_value = background;
+
+        for (origin, brick) in bricks {
+            assert!(origin.iter().all(|&c| c % brick_size == 0));
+            assert_eq!(brick.len(), brick_volume);
+            assert!(brick.iter().all(|&value| value >= 0.0));
+
+            let [bx, by, bz] = origin.map(|c| c / brick_size);
+            assert!(bx < bricks_per_axis[0] && by < bricks_per_axis[1] && bz < bricks_per_axis[2]);
+            let slot = &mut offsets[(bz * bricks_per_axis[1] + by) * bricks_per_axis[0] + bx];
+            let offset = *slot.get_or_insert(values.len());
+            if offset == values.len() {
+                values.extend_from_slice(&brick);
+            } else {
+                values[offset..offset + brick_volume].copy_from_slice(&brick);
+            }
+            max_value = brick.iter().copied().fold(max_value, f32::max);
+        }
+
+        Self {
+            resolution,
+            voxels: Voxels::Sparse {
+                brick_size,
+                bricks_per_axis,
+                offsets,
+                values,
+                background,
+            },
+            max_value,
+        }
+    }
+
+    /// Samples `density(p)` at the centre of every cell, with `p` running
+    /// from 0 to 1 across the grid on each axis. Negative values are clamped
+    /// to empty space.
+    pub fn from_fn(resolution: [usize; 3], density: impl Fn(Vec3) -> f32) -> Self {
+        let [nx, ny, nz] = resolution;
+        let values = (0..nz)
+            .flat_map(|z| (0..ny).flat_map(move |y| (0..nx).map(move |x| (x, y, z))))
+            .map(|(x, y, z)| {
+                let p = (vec3(x as f32, y as f32, z as f32) + 0.5)
+                    / vec3(nx as f32, ny as f32, nz as f32);
+                density(p).max(0.0)
+            })
+            .collect();
+        Self::new(resolution, values)
+    }
+
+    /// Wispy smoke from Perlin turbulence, `frequency` times across the grid.
+    pub fn from_noise(resolution: [usize; 3], perlin: &Perlin, frequency: f32) -> Self {
+        Self::from_fn(resolution, |p| perlin.turbulence(p * frequency, 7))
+    }
+
+    fn value(&self, x: usize, y: usize, z: usize) -> f32 {
+        match &self.voxels {
+            Voxels::Dense(values) => {
+                let [nx, ny, _] = self.resolution;
+                values[(z * ny + y) * nx + x]
+            }
+            Voxels::Sparse {
+                brick_size,
+                bricks_per_axis,
+                offsets,
+                values,
+                background,
+            } => {
+                let n = *brick_size;
+                let brick = ((z / n) * bricks_per_axis[1] + y / n) * bricks_per_axis[0] + x / n;
+                match offsets[brick] {
+                    Some(offset) => values[offset + ((z % n) * n + y % n) * n + x % n],
+                    None => *background,
+                }
+            }
+        }
+    }
+
+    /// The density at `p` in [0, 1] on each axis across the grid, holding the
+    /// outermost samples out to the edges.
+    pub fn lookup(&self, p: Vec3) -> f32 {
+        let size = vec3(
+            self.resolution[0] as f32,
+            self.resolution[1] as f32,
+            self.resolution[2] as f32,
+        );
+        let last = size - 1.0;
+        let g = (p * size - 0.5).clamp(Vec3::ZERO, last);
+        let base = g.floor().min(last);
+        let f = g - base;
+
+        let [x0, y0, z0] = base.to_array().map(|c| c as usize);
+        let x1 = (x0 + 1).min(self.resolution[0] - 1);
+        let y1 = (y0 + 1).min(self.resolution[1] - 1);
+        let z1 = (z0 + 1).min(self.resolution[2] - 1);
+
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let front = lerp(
+            lerp(self.value(x0, y0, z0), self.value(x1, y0, z0), f.x),
+            lerp(self.value(x0, y1, z0), self.value(x1, y1, z0), f.x),
+            f.y,
+        );
+        let back = lerp(
+            lerp(self.value(x0, y0, z1), self.value(x1, y0, z1), f.x),
+            lerp(self.value(x0, y1, z1), self.value(x1, y1, z1), f.x),
+            f.y,
+        );
+        lerp(front, back, f.z)
+    }
+
+    pub fn max_value(&self) -> f32 {
+        self.max_value
+    }
+}
+
+/// A participating medium whose density varies through a box, such as smoke
+/// or cloud, given by a `DensityGrid` stretched over `bounds`.
+///
+/// Collisions are found by delta tracking: stepping by free-flight distances
+/// through a uniform medium as dense as the densest voxel, and keeping each
+/// collision with the probability that it is real rather than a null one.
+pub struct GridMedium {
+    grid: DensityGrid,
+    bounds: Aabb,
+    /// From world space to where the grid spans the unit cube.
+    to_grid: Affine3A,
+    density: f32,
+    majorant: f32,
+    material: Arc<MediumMaterial>,
+}
+
+impl GridMedium {
+    /// `density` scales the grid's values into collisions per unit length.
+    pub fn new(grid: DensityGrid, bounds: Aabb, density: f32, texture: Box<dyn Texture>) -> Self {
+        let to_grid = unit_cube_to(&bounds).inverse();
+        let phase_function = Arc::new(Isotropic::from(texture));
+        Self::from_parts(grid, bounds, to_grid, density, phase_function)
+    }
+
+    pub fn from_color(grid: DensityGrid, bounds: Aabb, density: f32, albedo: Vec3) -> Self {
+        let to_grid = unit_cube_to(&bounds).inverse();
+        let phase_function = Arc::new(Isotropic::from(albedo));
+        Self::from_parts(grid, bounds, to_grid, density, phase_function)
+    }
+
+    /// Places the grid by `transform`, which takes the unit cube the grid
+    /// spans into world space and may rotate or shear it, as for a `Volume`
+    /// loaded from a file.
+    pub fn transformed(grid: DensityGrid, transform: Affine3A, density: f32, albedo: Vec3) -> Self {
+        let corners = (0..8).map(|i| {
+            let corner = vec3((i & 1) as f32, ((i >> 1) & 1) as f32, (i >> 2) as f32);
+            transform.transform_point3(corner)
+        });
+        let (min, max) = corners.fold(
+            (Vec3::INFINITY, Vec3::NEG_INFINITY),
+            |(min, max), corner| (min.min(corner), max.max(corner)),
+        );
+        let phase_function = Arc::new(Isotropic::from(albedo));
+        Self::from_parts(
+            grid,
+            Aabb::from((min, max)),
+            transform.inverse(),
+            density,
+            phase_function,
+        )
+    }
+
+    fn from_parts(
+        grid: DensityGrid,
+        bounds: Aabb,
+        to_grid: Affine3A,
+        density: f32,
+        phase_function: Arc<dyn Material>,
+    ) -> Self {
+        let majorant = density * grid.max_value();
+        Self {
+            grid,
+            bounds,
+            to_grid,
+            density,
+            majorant,
+            material: Arc::new(MediumMaterial::new(phase_function)),
+        }
+    }
+
+    /// Scatters by `phase_function` in place of the default isotropic one,
+    /// such as a `HenyeyGreenstein`.
+    pub fn with_phase_function(mut self, phase_function: Arc<dyn Material>) -> Self {
+        let material = (*self.material).clone();
+        self.material = Arc::new(material.with_phase_function(phase_function));
+        self
+    }
+
+    /// Makes the medium glow with `emission`, looked up at each collision
+    /// point.
+    pub fn with_emission(mut self, emission: Box<dyn Texture>) -> Self {
+        let material = (*self.material).clone();
+        self.material = Arc::new(material.with_emission(Arc::from(emission)));
+        self
+    }
+
+    /// Makes the medium glow with `color` times the values of `grid`, which
+    /// is placed like the density.
+    pub fn with_emission_grid(self, grid: DensityGrid, color: Vec3) -> Self {
+        let to_grid = self.to_grid;
+        self.with_emission(Box::new(EmissionGrid {
+            grid,
+            to_grid,
+            color,
+        }))
+    }
+
+    /// Makes the medium glow like a black body at the temperatures in kelvin
+    /// of `temperature`, placed like the density, as fire and explosions do.
+    /// Real flames are thousands of nits bright, so `scale` brings them down
+    /// to the brightness of the rest of the scene.
+    pub fn with_temperature_grid(self, temperature: DensityGrid, scale: f32) -> Self {
+        let to_grid = self.to_grid;
+        self.with_emission(Box::new(BlackbodyGrid::new(temperature, to_grid, scale)))
+    }
+
+    /// Collisions per unit length at `p`, none outside the grid.
+    pub fn density_at(&self, p: Vec3) -> f32 {
+        grid_position(self.to_grid, p).map_or(0.0, |q| self.density * self.grid.lookup(q))
+    }
+
+    /// The distance to the next tentative collision, in ray parameter, in a
+    /// medium as dense as the majorant everywhere.
+    fn free_flight(&self, ray_length: f32) -> f32 {
+        -(1.0 - rand::random::<f32>()).ln() / (self.majorant * ray_length)
+    }
+}
+
+impl Hittable for GridMedium {
+    fn hit(&self, r: &Ray, ray_t: Interval) -> Option<HitRecord> {
+        let span = self.bounds.clip(r, ray_t)?;
+        if self.majorant <= 0.0 {
+            return None;
+        }
+
+        let ray_length = r.direction.length();
+        let mut t = span.min;
+        loop {
+            t += self.free_flight(ray_length);
+            if t >= span.max {
+                return None;
+            }
+            if rand::random::<f32>() * self.majorant < self.density_at(r.at(t)) {
+                return Some(HitRecord::in_medium(r, t, self.material.clone()));
+            }
+        }
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounds
+    }
+}
+
+/// Takes the unit cube onto `bounds`.
+fn unit_cube_to(bounds: &Aabb) -> Affine3A {
+    let min = vec3(bounds.x.min, bounds.y.min, bounds.z.min);
+    let max = vec3(bounds.x.max, bounds.y.max, bounds.z.max);
+    Affine3A::from_translation(min) * Affine3A::from_scale(max - min)
+}
+
+/// Where `p` falls in a grid spanning the unit cube, if inside it.
+fn grid_position(to_grid: Affine3A, p: Vec3) -> Option<Vec3> {
+    let q = to_grid.transform_point3(p);
+    (q.cmpge(Vec3::ZERO).all() && q.cmple(Vec3::ONE).all()).then_some(q)
+}
+
+struct EmissionGrid {
+    grid: DensityGrid,
+    to_grid: Affine3A,
+    color: Vec3,
+}
+
+impl Texture for EmissionGrid {
+    fn value(&self, _u: f32, _v: f32, point: Vec3) -> Vec3 {
+        grid_position(self.to_grid, point).map_or(Vec3::ZERO, |q| self.color * self.grid.lookup(q))
+    }
+}
+
+/// Blackbody emission from a temperature grid, with the radiance tabulated
+/// up to the hottest voxel since integrating Planck's law at every collision
+/// would be slow.
+struct BlackbodyGrid {
+    temperature: DensityGrid,
+    to_grid: Affine3A,
+    radiance: Vec<Vec3>,
+}
+
+impl BlackbodyGrid {
+    const TABLE_SIZE: usize = 256;
+
+    fn new(temperature: DensityGrid, to_grid: Affine3A, scale: f32) -> Self {
+        let step = temperature.max_value() / (Self::TABLE_SIZE - 1) as f32;
+        let radiance = (0..Self::TABLE_SIZE)
+            .map(|i| {
+                let kelvin = i as f32 * step;
+                blackbody(kelvin) * blackbody_luminance(kelvin) * scale
+            })
+            .collect();
+        Self {
+            temperature,
+            to_grid,
+            radiance,
+        }
+    }
+}
+
+impl Texture for BlackbodyGrid {
+    fn value(&self, _u: f32, _v: f32, point: Vec3) -> Vec3 {
+        let max = self.temperature.max_value();
+        let Some(q) = grid_position(self.to_grid, point) else {
+            return Vec3::ZERO;
+        };
+        if max <= 0.0 {
+            return Vec3::ZERO;
+        }
+
+        let kelvin = self.temperature.lookup(q);
+
+        let x = kelvin / max * (Self::TABLE_SIZE - 1) as f32;
+        let i = (x as usize).min(Self::TABLE_SIZE - 2);
+        self.radiance[i].lerp(self.radiance[i + 1], x - i as f32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The mean distance a ray travels through the medium before colliding,
+    /// which should be one over the density.
+    fn mean_free_path(medium: &GridMedium) -> f32 {
+        const RAYS: usize = 20_000;
+        // Stretched so the medium is about 2 long, to check distances are
+        // not measured in ray parameter.
+        let ray = Ray::new(vec3(-1.0, 0.1, 0.1), vec3(2.0, 0.0, 0.0), 0.0);
+        let total: f32 = (0..RAYS)
+            .map(|_| {
+                let rec = medium.hit(&ray, Interval::new(0.0, f32::INFINITY)).unwrap();
+                ray.at(rec.t).x
+            })
+            .sum();
+        total / RAYS as f32
+    }
+
+    fn bounds() -> Aabb {
+        Aabb::from((Vec3::ZERO, vec3(100.0, 1.0, 1.0)))
+    }
+
+    #[test]
+    fn free_paths_in_a_uniform_grid_average_one_over_the_density() {
+        let grid = DensityGrid::new([2, 2, 2], vec![0.5; 8]);
+        let medium = GridMedium::from_color(grid, bounds(), 4.0, Vec3::ONE);
+        let mean = mean_free_path(&medium);
+        assert!((mean - 0.5).abs() < 0.02, "mean free path {mean}");
+    }
+
+    #[test]
+    fn null_collisions_do_not_shorten_free_paths() {
+        // Twice as dense away from the ray, so half the tentative collisions
+        // along it are null.
+        let grid = DensityGrid::from_bricks([4, 4, 4], 2, 0.5, [([2, 2, 2], vec![1.0; 8])]);
+        let medium = GridMedium::from_color(grid, bounds(), 2.0, Vec3::ONE);
+        let mean = mean_free_path(&medium);
+        assert!((mean - 1.0).abs() < 0.04, "mean free path {mean}");
+    }
+}
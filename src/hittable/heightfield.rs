@@ -0,0 +1,334 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::triangle::intersect_triangle;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::{IVec2, Vec3, ivec2, vec2, vec3};
+use image::error::{ParameterError, ParameterErrorKind};
+use image::{ImageError, open};
+use std::sync::Arc;
+
+/// The column and row of a height sample.
+type Sample = (usize, usize);
+
+/// Terrain over a regular grid of height samples, covering `size.x` by
+/// `size.z` from `corner` with each sample scaled by `size.y`.
+///
+/// Each cell is split into two triangles, found by walking the cells under the
+/// ray (a 2D DDA) and skipping those it passes wholly above or below. Normals
+/// are interpolated from the neighbouring samples, and (u, v) spans the whole
+/// terrain from `corner` along x and z.
+pub struct Heightfield {
+    columns: usize,
+    rows: usize,
+    /// World space heights above `corner`, row by row along z.
+    heights: Vec<f32>,
+    normals: Vec<Vec3>,
+    corner: Vec3,
+    size: Vec3,
+    spacing: Vec3,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Heightfield {
+    /// `heights` holds `rows` rows of `columns` samples, the first row along
+    /// the `corner` edge.
+    pub fn new(
+        heights: Vec<f32>,
+        columns: usize,
+        rows: usize,
+        corner: Vec3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        assert!(columns >= 2 && rows >= 2);
+        assert_eq!(heights.len(), columns * rows);
+
+        let heights: Vec<f32> = heights.into_iter().map(|h| h * size.y).collect();
+        let spacing = vec3(
+            size.x / (columns - 1) as f32,
+            1.0,
+            size.z / (rows - 1) as f32,
+        );
+
+        // Central differences, one sided along the edges.
+        let height = |i: usize, j: usize| heights[j * columns + i];
+        let normals = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                let (left, right) = (i.saturating_sub(1), (i + 1).min(columns - 1));
+                let (back, front) = (j.saturating_sub(1), (j + 1).min(rows - 1));
+                let dx = (height(right, j) - height(left, j)) / ((right - left) as f32 * spacing.x);
+                let dz = (height(i, front) - height(i, back)) / ((front - back) as f32 * spacing.z);
+                vec3(-dx, 1.0, -dz).normalize()
+            })
+            .collect();
+
+        let (low, high) = heights
+            .iter()
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), &h| {
+                (low.min(h), high.max(h))
+            });
+        let bounding_box = Aabb::from((
+            corner + vec3(0.0, low, 0.0),
+            corner + vec3(size.x, high, size.z),
+        ));
+
+        Self {
+            columns,
+            rows,
+            heights,
+            normals,
+            corner,
+            size,
+            spacing,
+            material,
+            bounding_box,
+        }
+    }
+
+    /// Samples `height(u, v)` over a grid, with u and v running from 0 to 1
+    /// across the terrain. Any function of the position will do, such as a
+    /// `Perlin::fbm`.
+    pub fn from_fn(
+        columns: usize,
+        rows: usize,
+        corner: Vec3,
+        size: Vec3,
+        height: impl Fn(f32, f32) -> f32,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        let heights = (0..rows)
+            .flat_map(|j| (0..columns).map(move |i| (i, j)))
+            .map(|(i, j)| {
+                height(
+                    i as f32 / (columns - 1) as f32,
+                    j as f32 / (rows - 1) as f32,
+                )
+            })
+            .collect();
+        Self::new(heights, columns, rows, corner, size, material)
+    }
+
+    /// One sample per pixel of a grayscale image, black at `corner.y` and
+    /// white `size.y` above it. The top row of the image lies along the
+    /// `corner` edge. Images under 2 pixels on a side are an error, as they
+    /// don't make a single cell.
+    pub fn from_image(
+        file_path: &str,
+        corner: Vec3,
+        size: Vec3,
+        material: Arc<dyn Material>,
+    ) -> Result<Self, ImageError> {
+        // Sixteen bits keep the full precision of 16-bit images.
+        let image = open(file_path)?.into_luma16();
+        if image.width() < 2 || image.height() < 2 {
+            return Err(ImageError::Parameter(ParameterError::from_kind(
+                ParameterErrorKind::Generic(format!(
+                    "a {}x{} image is too small for a heightfield",
+                    image.width(),
+                    image.height()
+                )),
+            )));
+        }
+        let heights = image
+            .pixels()
+            .map(|p| p[0] as f32 / u16::MAX as f32)
+            .collect();
+        Ok(Self::new(
+            heights,
+            image.width() as usize,
+            image.height() as usize,
+            corner,
+            size,
+            material,
+        ))
+    }
+
+    fn index(&self, i: usize, j: usize) -> usize {
+        j * self.columns + i
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Vec3 {
+        self.corner
+            + vec3(
+                i as f32 * self.spacing.x,
+                self.heights[self.index(i, j)],
+                j as f32 * self.spacing.z,
+            )
+    }
+
+    /// The nearest hit on either triangle of a cell within `window`.
+    fn hit_cell(&self, ray: &Ray, cell: IVec2, window: Interval) -> Option<HitRecord> {
+        let (i, j) = (cell.x as usize, cell.y as usize);
+
+        // Skip the cell if the ray passes over or under all of it.
+        let corners = [(i, j), (i + 1, j), (i, j + 1), (i + 1, j + 1)];
+        let (low, high) = corners
+            .iter()
+            .map(|&(i, j)| self.heights[self.index(i, j)])
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(low, high), h| {
+                (low.min(h), high.max(h))
+            });
+        let y0 = ray.at(window.min).y - self.corner.y;
+        let y1 = ray.at(window.max).y - self.corner.y;
+        if y0.min(y1) > high || y0.max(y1) < low {
+            return None;
+        }
+
+        // Both wound so that their normals face up.
+        let triangles = [
+            [(i, j), (i + 1, j + 1), (i + 1, j)],
+            [(i, j), (i, j + 1), (i + 1, j + 1)],
+        ];
+
+        let mut closest: Option<(f32, [Sample; 3], f32, f32)> = None;
+        for triangle in triangles {
+            let [a, b, c] = triangle.map(|(i, j)| self.vertex(i, j));
+            if let Some((t, beta, gamma)) = intersect_triangle(ray, a, b - a, c - a)
+                && window.surrounds(t)
+                && closest.is_none_or(|(best, ..)| t < best)
+            {
+                closest = Some((t, triangle, beta, gamma));
+            }
+        }
+
+        let (t, [a, b, c], beta, gamma) = closest?;
+        let [pa, pb, pc] = [a, b, c].map(|(i, j)| self.vertex(i, j));
+        let geometric = (pb - pa).cross(pc - pa).normalize();
+
+        let point = ray.at(t);
+        let uv = (point - self.corner) / self.size;
+        let mut rec = HitRecord::new(
+            point,
+            t,
+            ray,
+            geometric,
+            self.material.clone(),
+            (uv.x, uv.z),
+        );
+
+        // Along u and v the surface rises with the slope of the triangle.
+        let slope = vec2(geometric.x, geometric.z) / -geometric.y;
+        let dpdu = self.size.x * vec3(1.0, slope.x, 0.0);
+        let dpdv = self.size.z * vec3(0.0, slope.y, 1.0);
+        rec = rec.with_tangents(dpdu, dpdv);
+
+        let [na, nb, nc] = [a, b, c].map(|(i, j)| self.normals[self.index(i, j)]);
+        let alpha = 1.0 - beta - gamma;
+        let shading = (alpha * na + beta * nb + gamma * nc).normalize_or(geometric);
+        rec.normal = if rec.front_face { shading } else { -shading };
+
+        Some(rec)
+    }
+}
+
+impl Hittable for Heightfield {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let span = self.bounding_box.clip(ray, ray_interval)?;
+
+        // Walk the cells in grid space, where each is one unit square in xz.
+        let origin = (ray.origin - self.corner) / self.spacing;
+        let origin = vec2(origin.x, origin.z);
+        let direction = ray.direction / self.spacing;
+        let direction = vec2(direction.x, direction.z);
+
+        let last = ivec2(self.columns as i32 - 2, self.rows as i32 - 2);
+        let start = origin + span.min * direction;
+        let mut cell = start.floor().as_ivec2().clamp(IVec2::ZERO, last);
+        let step = direction.signum().as_ivec2();
+
+        let boundary = |cell: i32, origin: f32, direction: f32| {
+            if direction > 0.0 {
+                (cell as f32 + 1.0 - origin) / direction
+            } else if direction < 0.0 {
+                (cell as f32 - origin) / direction
+            } else {
+                f32::INFINITY
+            }
+        };
+        let mut t_next = vec2(
+            boundary(cell.x, origin.x, direction.x),
+            boundary(cell.y, origin.y, direction.y),
+        );
+        let t_delta = direction.abs().recip();
+
+        // Cells overlap slightly so hits on their shared edges aren't lost.
+        let epsilon = 1e-4 * (span.max - span.min);
+        let mut t_enter = span.min;
+        loop {
+            let t_exit = t_next.min_element().min(span.max);
+            let window = Interval::new(
+                (t_enter - epsilon).max(ray_interval.min),
+                (t_exit + epsilon).min(ray_interval.max),
+            );
+            if let Some(rec) = self.hit_cell(ray, cell, window) {
+                return Some(rec);
+            }
+            if t_exit >= span.max {
+                return None;
+            }
+
+            if t_next.x < t_next.y {
+                cell.x += step.x;
+                t_next.x += t_delta.x;
+            } else {
+                cell.y += step.y;
+                t_next.y += t_delta.y;
+            }
+            if cell.cmplt(IVec2::ZERO).any() || cell.cmpgt(last).any() {
+                return None;
+            }
+            t_enter = t_exit;
+        }
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use image::{GrayImage, Luma};
+    use std::{env, fs};
+
+    fn load(width: u32, height: u32) -> Result<Heightfield, ImageError> {
+        let directory = env::temp_dir().join("heightfield_test");
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join(format!("{width}x{height}.png"));
+        GrayImage::from_fn(width, height, |x, _| Luma([if x == 0 { 0 } else { 255 }]))
+            .save(&file)
+            .unwrap();
+
+        Heightfield::from_image(
+            file.to_str().unwrap(),
+            Vec3::ZERO,
+            vec3(1.0, 2.0, 1.0),
+            Arc::new(Lambertian::from(Vec3::ONE)),
+        )
+    }
+
+    #[test]
+    fn loads_heights_from_an_image() {
+        let terrain = load(2, 2).unwrap();
+        assert_eq!(terrain.heights, [0.0, 2.0, 0.0, 2.0]);
+
+        let ray = Ray::new(vec3(0.75, 5.0, 0.5), -Vec3::Y, 0.0);
+        let rec = terrain
+            .hit(&ray, Interval::new(0.0, f32::INFINITY))
+            .unwrap();
+        assert!((rec.point.y - 1.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rejects_images_too_small_to_make_a_cell() {
+        for (width, height) in [(1, 1), (1, 4), (4, 1)] {
+            assert!(load(width, height).is_err());
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use super::{HitRecord, Hittable};
 use crate::hittable::aabb::Aabb;
+use crate::hittable::bvh::BvhNode;
 use crate::interval::Interval;
 use crate::ray::Ray;
 use std::sync::Arc;
@@ -25,6 +26,25 @@ impl HittableList {
         self.bounding_box = Aabb::from((self.bounding_box(), hittable.bounding_box()));
         self.objects.push(hittable)
     }
+
+    /// Gathers the bounded objects under a `BvhNode`. Unbounded ones, such as
+    /// a `Plane`, stay beside it, as their infinite boxes would swallow every
+    /// node above them.
+    pub fn into_bvh(self) -> HittableList {
+        let (mut bounded, unbounded): (Vec<_>, Vec<_>) = self
+            .objects
+            .into_iter()
+            .partition(|object| object.bounding_box().is_bounded());
+
+        let mut list = HittableList::new();
+        if !bounded.is_empty() {
+            list.add(Arc::new(BvhNode::new(&mut bounded)));
+        }
+        for object in unbounded {
+            list.add(object);
+        }
+        list
+    }
 }
 
 impl From<Arc<dyn Hittable>> for HittableList {
@@ -50,4 +70,8 @@ impl Hittable for HittableList {
         }
         rec
     }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
 }
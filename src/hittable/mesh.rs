@@ -0,0 +1,387 @@
+use crate::hittable::hittable_list::HittableList;
+use crate::hittable::triangle::Triangle;
+use crate::material::Material;
+use crate::material::texture::Texture;
+use glam::{Vec2, Vec3};
+use std::collections::HashMap;
+use std::ops::{Add, Mul};
+use std::sync::Arc;
+
+/// A polygon mesh with shared vertices, to be refined and then turned into
+/// `Triangle`s.
+///
+/// Faces list their corners counter-clockwise seen from the front. Because
+/// neighbouring faces share vertices rather than copies of them, subdividing
+/// and displacing keeps the surface watertight.
+#[derive(Clone)]
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub uvs: Option<Vec<Vec2>>,
+    pub colors: Option<Vec<Vec3>>,
+    pub faces: Vec<Vec<usize>>,
+}
+
+/// Each new vertex as a weighted sum of the old ones.
+type Stencil = Vec<(usize, f32)>;
+
+impl Mesh {
+    pub fn new(positions: Vec<Vec3>, faces: Vec<Vec<usize>>) -> Self {
+        assert!(faces.iter().all(|face| face.len() >= 3));
+        assert!(faces.iter().flatten().all(|&i| i < positions.len()));
+        Self {
+            positions,
+            uvs: None,
+            colors: None,
+            faces,
+        }
+    }
+
+    /// Texture coordinates per vertex, carried through subdivision by the
+    /// same rules as the positions, as are colours.
+    pub fn with_uvs(mut self, uvs: Vec<Vec2>) -> Self {
+        assert_eq!(uvs.len(), self.positions.len());
+        self.uvs = Some(uvs);
+        self
+    }
+
+    /// Colours per vertex, blended across each face into the hit's
+    /// `vertex_color` for a `VertexColorTexture` to show.
+    pub fn with_colors(mut self, colors: Vec<Vec3>) -> Self {
+        assert_eq!(colors.len(), self.positions.len());
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Loop subdivision, `levels` times over. Each triangle becomes four and
+    /// the surface converges on a smooth limit. Faces with more than three
+    /// corners are split into fans first.
+    pub fn loop_subdivide(&self, levels: u32) -> Mesh {
+        let mut mesh = self.triangulated();
+        for _ in 0..levels {
+            mesh = mesh.loop_step();
+        }
+        mesh
+    }
+
+    /// Catmull-Clark subdivision, `levels` times over. Every face becomes one
+    /// quad per corner, so after the first level the mesh is all quads.
+    pub fn catmull_clark(&self, levels: u32) -> Mesh {
+        let mut mesh = self.clone();
+        for _ in 0..levels {
+            mesh = mesh.catmull_clark_step();
+        }
+        mesh
+    }
+
+    /// Moves each vertex along its normal by `scale` times the texture's
+    /// brightness there, looked up at the vertex's (u, v) and position.
+    pub fn displace(&self, texture: &dyn Texture, scale: f32) -> Mesh {
+        let normals = self.vertex_normals();
+        let positions = self
+            .positions
+            .iter()
+            .zip(&normals)
+            .enumerate()
+            .map(|(i, (&p, &n))| {
+                let uv = self.uvs.as_ref().map_or(Vec2::ZERO, |uvs| uvs[i]);
+                let height = texture.value(uv.x, uv.y, p).element_sum() / 3.0;
+                p + n * height * scale
+            })
+            .collect();
+
+        Mesh {
+            positions,
+            ..self.clone()
+        }
+    }
+
+    /// Area weighted averages of the normals of the faces around each
+    /// vertex.
+    pub fn vertex_normals(&self) -> Vec<Vec3> {
+        let mut normals = vec![Vec3::ZERO; self.positions.len()];
+        for face in &self.faces {
+            // Twice the vector area, which also suits faces that aren't flat.
+            let p = |i: usize| self.positions[face[i % face.len()]];
+            let area = (0..face.len()).fold(Vec3::ZERO, |sum, i| sum + p(i).cross(p(i + 1)));
+            for &i in face {
+                normals[i] += area;
+            }
+        }
+        normals
+            .into_iter()
+            .map(|n| n.normalize_or(Vec3::Y))
+            .collect()
+    }
+
+    /// One smooth shaded `Triangle` per triangle of the mesh, ready for
+    /// `HittableList::into_bvh`.
+    pub fn triangles(&self, material: Arc<dyn Material>) -> HittableList {
+        let normals = self.vertex_normals();
+        let mut list = HittableList::new();
+        for face in &self.triangulated().faces {
+            let [a, b, c] = [face[0], face[1], face[2]];
+            let mut triangle = Triangle::new(
+                self.positions[a],
+                self.positions[b],
+                self.positions[c],
+                material.clone(),
+            )
+            .with_normals([normals[a], normals[b], normals[c]]);
+            if let Some(uvs) = &self.uvs {
+                triangle = triangle.with_uvs([uvs[a], uvs[b], uvs[c]]);
+            }
+            if let Some(colors) = &self.colors {
+                triangle = triangle.with_colors([colors[a], colors[b], colors[c]]);
+            }
+            list.add(Arc::new(triangle));
+        }
+        list
+    }
+
+    fn triangulated(&self) -> Mesh {
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|face| (1..face.len() - 1).map(|i| vec![face[0], face[i], face[i + 1]]))
+            .collect();
+        Mesh {
+            faces,
+            ..self.clone()
+        }
+    }
+
+    fn loop_step(&self) -> Mesh {
+        let topology = Topology::new(self);
+        let vertex_count = self.positions.len();
+
+        let mut stencils: Vec<Stencil> = (0..vertex_count)
+            .map(|v| {
+                if let Some(stencil) = topology.boundary_stencil(v) {
+                    return stencil;
+                }
+                let neighbours = topology.neighbours(v);
+                let n = neighbours.len() as f32;
+                let beta = if neighbours.len() == 3 {
+                    3.0 / 16.0
+                } else {
+                    3.0 / (8.0 * n)
+                };
+                let mut stencil = vec![(v, 1.0 - n * beta)];
+                stencil.extend(neighbours.into_iter().map(|w| (w, beta)));
+                stencil
+            })
+            .collect();
+
+        stencils.extend(topology.edges.iter().map(|edge| {
+            let [a, b] = edge.vertices;
+            if edge.faces.len() != 2 {
+                return vec![(a, 0.5), (b, 0.5)];
+            }
+            let mut stencil = vec![(a, 3.0 / 8.0), (b, 3.0 / 8.0)];
+            for &f in &edge.faces {
+                let opposite = self.faces[f].iter().find(|&&v| v != a && v != b);
+                stencil.push((*opposite.unwrap(), 1.0 / 8.0));
+            }
+            stencil
+        }));
+
+        let edge_vertex = |a: usize, b: usize| vertex_count + topology.edge(a, b);
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                let [a, b, c] = [face[0], face[1], face[2]];
+                let (ab, bc, ca) = (edge_vertex(a, b), edge_vertex(b, c), edge_vertex(c, a));
+                [
+                    vec![a, ab, ca],
+                    vec![b, bc, ab],
+                    vec![c, ca, bc],
+                    vec![ab, bc, ca],
+                ]
+            })
+            .collect();
+
+        self.refined(&stencils, faces)
+    }
+
+    fn catmull_clark_step(&self) -> Mesh {
+        let topology = Topology::new(self);
+        let vertex_count = self.positions.len();
+        let edge_base = vertex_count;
+        let face_base = vertex_count + topology.edges.len();
+
+        let face_point = |f: usize| -> Stencil {
+            let face = &self.faces[f];
+            let weight = 1.0 / face.len() as f32;
+            face.iter().map(|&v| (v, weight)).collect()
+        };
+        let scaled =
+            |stencil: Stencil, factor: f32| stencil.into_iter().map(move |(v, w)| (v, w * factor));
+
+        let mut stencils: Vec<Stencil> = (0..vertex_count)
+            .map(|v| {
+                if let Some(stencil) = topology.boundary_stencil(v) {
+                    return stencil;
+                }
+                // (F + 2R + (n - 3)v) / n, with F the average of the face
+                // points around the vertex and R of its edge midpoints.
+                let faces = &topology.vertex_faces[v];
+                let neighbours = topology.neighbours(v);
+                let n = neighbours.len() as f32;
+
+                let mut stencil = vec![(v, (n - 3.0) / n + 1.0 / n)];
+                for &f in faces {
+                    stencil.extend(scaled(face_point(f), 1.0 / (n * faces.len() as f32)));
+                }
+                stencil.extend(neighbours.into_iter().map(|w| (w, 1.0 / (n * n))));
+                stencil
+            })
+            .collect();
+
+        stencils.extend(topology.edges.iter().map(|edge| {
+            let [a, b] = edge.vertices;
+            if edge.faces.len() != 2 {
+                return vec![(a, 0.5), (b, 0.5)];
+            }
+            let mut stencil = vec![(a, 0.25), (b, 0.25)];
+            for &f in &edge.faces {
+                stencil.extend(scaled(face_point(f), 0.25));
+            }
+            stencil
+        }));
+
+        stencils.extend((0..self.faces.len()).map(face_point));
+
+        let faces = self
+            .faces
+            .iter()
+            .enumerate()
+            .flat_map(|(f, face)| {
+                let corners = face.len();
+                (0..corners).map(move |i| {
+                    let previous = face[(i + corners - 1) % corners];
+                    let next = face[(i + 1) % corners];
+                    (f, face[i], previous, next)
+                })
+            })
+            .map(|(f, v, previous, next)| {
+                vec![
+                    v,
+                    edge_base + topology.edge(v, next),
+                    face_base + f,
+                    edge_base + topology.edge(previous, v),
+                ]
+            })
+            .collect();
+
+        self.refined(&stencils, faces)
+    }
+
+    fn refined(&self, stencils: &[Stencil], faces: Vec<Vec<usize>>) -> Mesh {
+        Mesh {
+            positions: apply_stencils(stencils, &self.positions),
+            uvs: self.uvs.as_ref().map(|uvs| apply_stencils(stencils, uvs)),
+            colors: self
+                .colors
+                .as_ref()
+                .map(|colors| apply_stencils(stencils, colors)),
+            faces,
+        }
+    }
+}
+
+fn apply_stencils<T>(stencils: &[Stencil], values: &[T]) -> Vec<T>
+where
+    T: Copy + Default + Add<Output = T> + Mul<f32, Output = T>,
+{
+    stencils
+        .iter()
+        .map(|stencil| {
+            stencil
+                .iter()
+                .fold(T::default(), |sum, &(i, weight)| sum + values[i] * weight)
+        })
+        .collect()
+}
+
+struct Edge {
+    vertices: [usize; 2],
+    faces: Vec<usize>,
+}
+
+/// Which edges and faces meet where, numbered in the order they are first
+/// met so that subdividing the same mesh always gives the same result.
+struct Topology {
+    edges: Vec<Edge>,
+    edge_index: HashMap<(usize, usize), usize>,
+    vertex_edges: Vec<Vec<usize>>,
+    vertex_faces: Vec<Vec<usize>>,
+}
+
+impl Topology {
+    fn new(mesh: &Mesh) -> Self {
+        let mut topology = Self {
+            edges: Vec::new(),
+            edge_index: HashMap::new(),
+            vertex_edges: vec![Vec::new(); mesh.positions.len()],
+            vertex_faces: vec![Vec::new(); mesh.positions.len()],
+        };
+
+        for (f, face) in mesh.faces.iter().enumerate() {
+            for (i, &a) in face.iter().enumerate() {
+                let b = face[(i + 1) % face.len()];
+                let key = (a.min(b), a.max(b));
+                let edge = *topology.edge_index.entry(key).or_insert_with(|| {
+                    topology.edges.push(Edge {
+                        vertices: [key.0, key.1],
+                        faces: Vec::new(),
+                    });
+                    topology.vertex_edges[a].push(topology.edges.len() - 1);
+                    topology.vertex_edges[b].push(topology.edges.len() - 1);
+                    topology.edges.len() - 1
+                });
+                topology.edges[edge].faces.push(f);
+                topology.vertex_faces[a].push(f);
+            }
+        }
+
+        topology
+    }
+
+    fn edge(&self, a: usize, b: usize) -> usize {
+        self.edge_index[&(a.min(b), a.max(b))]
+    }
+
+    fn other_end(&self, edge: usize, v: usize) -> usize {
+        let [a, b] = self.edges[edge].vertices;
+        if a == v { b } else { a }
+    }
+
+    fn neighbours(&self, v: usize) -> Vec<usize> {
+        self.vertex_edges[v]
+            .iter()
+            .map(|&edge| self.other_end(edge, v))
+            .collect()
+    }
+
+    /// The rule for a vertex on an open edge of the mesh, which follows only
+    /// the boundary so both sides of a seam move alike. `None` for interior
+    /// vertices.
+    fn boundary_stencil(&self, v: usize) -> Option<Stencil> {
+        let boundary: Vec<usize> = self.vertex_edges[v]
+            .iter()
+            .filter(|&&edge| self.edges[edge].faces.len() != 2)
+            .map(|&edge| self.other_end(edge, v))
+            .collect();
+
+        match boundary[..] {
+            [] => None,
+            [a, b] if self.vertex_faces[v].len() > 1 => {
+                Some(vec![(v, 0.75), (a, 0.125), (b, 0.125)])
+            }
+            // Corners of a single face and non-manifold vertices stay where
+            // they are.
+            _ => Some(vec![(v, 1.0)]),
+        }
+    }
+}
@@ -5,14 +5,29 @@ pub mod sphere;
 pub mod quad;
 pub mod transform;
 pub mod constant_medium;
+pub mod cutout;
+pub mod normal_map;
+pub mod disk;
+pub mod cylinder;
+pub mod cone;
+pub mod torus;
+pub mod triangle;
+pub mod plane;
+pub mod sdf;
+pub mod csg;
+pub mod heightfield;
+pub mod curve;
+pub mod mesh;
+pub mod grid_medium;
 
 use crate::interval::Interval;
 use crate::material::Material;
-use crate::ray::Ray;
+use crate::ray::{Ray, RayDifferentials};
 use glam::Vec3;
 use std::sync::Arc;
 use crate::hittable::aabb::Aabb;
 
+#[derive(Clone)]
 pub struct HitRecord {
     pub point: Vec3,
     pub normal: Vec3,
@@ -21,6 +36,26 @@ pub struct HitRecord {
     pub u: f32,
     pub v: f32,
     pub front_face: bool,
+    /// The time of the ray that made the hit, for textures that animate.
+    pub time: f32,
+    /// Partial derivatives of the surface position with respect to `u` and `v`.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    pub footprint: Footprint,
+    /// Colour interpolated from the corners of a mesh that has them.
+    pub vertex_color: Option<Vec3>,
+}
+
+/// How the surface position and texture coordinates change from one pixel to
+/// the next, filled in from the ray differentials once the nearest hit is known.
+#[derive(Copy, Clone, Default)]
+pub struct Footprint {
+    pub dpdx: Vec3,
+    pub dpdy: Vec3,
+    pub dudx: f32,
+    pub dvdx: f32,
+    pub dudy: f32,
+    pub dvdy: f32,
 }
 
 impl HitRecord {
@@ -38,6 +73,7 @@ impl HitRecord {
         } else {
             -outward_normal
         };
+        let (dpdu, dpdv) = outward_normal.any_orthonormal_pair();
 
         Self {
             point,
@@ -47,6 +83,127 @@ impl HitRecord {
             u,
             v,
             front_face,
+            time: ray.time,
+            dpdu,
+            dpdv,
+            footprint: Footprint::default(),
+            vertex_color: None,
+        }
+    }
+
+    /// A scattering event inside a volume, which has no surface to take a
+    /// normal or texture coordinates from.
+    pub(crate) fn in_medium(ray: &Ray, t: f32, material: Arc<dyn Material>) -> Self {
+        Self {
+            point: ray.at(t),
+            normal: Vec3::X,
+            material,
+            t,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+            time: ray.time,
+            dpdu: Vec3::Y,
+            dpdv: Vec3::Z,
+            footprint: Footprint::default(),
+            vertex_color: None,
+        }
+    }
+
+    /// Replaces the arbitrary tangent frame picked by `new` with the real
+    /// surface derivatives, needed wherever textures are applied in tangent space.
+    fn with_tangents(mut self, dpdu: Vec3, dpdv: Vec3) -> Self {
+        self.dpdu = dpdu;
+        self.dpdv = dpdv;
+        self
+    }
+
+    /// Intersects the differential rays with the tangent plane at the hit and
+    /// expresses the offsets in terms of (u, v).
+    pub fn compute_footprint(&mut self, ray: &Ray) {
+        let Some(differentials) = ray.differentials else {
+            return;
+        };
+
+        let n = self.normal;
+        let d = n.dot(self.point);
+        let tx = (d - n.dot(differentials.rx_origin)) / n.dot(differentials.rx_direction);
+        let ty = (d - n.dot(differentials.ry_origin)) / n.dot(differentials.ry_direction);
+        if !tx.is_finite() || !ty.is_finite() {
+            return;
+        }
+
+        let dpdx = differentials.rx_origin + tx * differentials.rx_direction - self.point;
+        let dpdy = differentials.ry_origin + ty * differentials.ry_direction - self.point;
+
+        // Least squares solution of dp = du * dpdu + dv * dpdv.
+        let ata00 = self.dpdu.dot(self.dpdu);
+        let ata01 = self.dpdu.dot(self.dpdv);
+        let ata11 = self.dpdv.dot(self.dpdv);
+        let inv_det = (ata00 * ata11 - ata01 * ata01).recip();
+        let inv_det = if inv_det.is_finite() { inv_det } else { 0.0 };
+
+        let solve = |dp: Vec3| {
+            let atb0 = self.dpdu.dot(dp);
+            let atb1 = self.dpdv.dot(dp);
+            let du = (ata11 * atb0 - ata01 * atb1) * inv_det;
+            let dv = (ata00 * atb1 - ata01 * atb0) * inv_det;
+            (du.clamp(-1e8, 1e8), dv.clamp(-1e8, 1e8))
+        };
+        let (dudx, dvdx) = solve(dpdx);
+        let (dudy, dvdy) = solve(dpdy);
+
+        self.footprint = Footprint {
+            dpdx,
+            dpdy,
+            dudx,
+            dvdx,
+            dudy,
+            dvdy,
+        };
+    }
+
+    /// Differentials of a perfect mirror bounce. Curvature is ignored, so the
+    /// footprint is only approximate on curved surfaces.
+    pub fn reflected_differentials(&self, r_in: &Ray) -> Option<RayDifferentials> {
+        let differentials = r_in.differentials?;
+        Some(RayDifferentials {
+            rx_origin: self.point + self.footprint.dpdx,
+            rx_direction: differentials.rx_direction.reflect(self.normal),
+            ry_origin: self.point + self.footprint.dpdy,
+            ry_direction: differentials.ry_direction.reflect(self.normal),
+        })
+    }
+
+    /// Differentials of a refraction with relative index `eta`, or `None` if
+    /// either offset ray is totally internally reflected.
+    pub fn refracted_differentials(&self, r_in: &Ray, eta: f32) -> Option<RayDifferentials> {
+        let differentials = r_in.differentials?;
+        let rx_direction = differentials
+            .rx_direction
+            .normalize()
+            .refract(self.normal, eta);
+        let ry_direction = differentials
+            .ry_direction
+            .normalize()
+            .refract(self.normal, eta);
+        if rx_direction == Vec3::ZERO || ry_direction == Vec3::ZERO {
+            return None;
+        }
+        Some(RayDifferentials {
+            rx_origin: self.point + self.footprint.dpdx,
+            rx_direction,
+            ry_origin: self.point + self.footprint.dpdy,
+            ry_direction,
+        })
+    }
+
+    /// Normal pointing out of the surface, regardless of which side was hit.
+    pub fn outward_normal(&self) -> Vec3 {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
         }
     }
 }
@@ -54,6 +211,18 @@ impl HitRecord {
 pub trait Hittable: Sync + Send {
     fn hit(&self, r: &Ray, ray_interval: Interval) -> Option<HitRecord>;
 
+    /// Every crossing of the surface within `ray_interval`, nearest first.
+    /// On a closed object they alternate between entering (`front_face`) and
+    /// leaving. The default steps past one `hit` after another.
+    fn hits(&self, r: &Ray, ray_interval: Interval) -> Vec<HitRecord> {
+        let mut hits = Vec::new();
+        let mut interval = ray_interval;
+        while let Some(rec) = self.hit(r, interval) {
+            interval.min = rec.t + 0.000_01;
+            hits.push(rec);
+        }
+        hits
+    }
 
     fn bounding_box(&self) -> &Aabb {
         &Aabb::EMPTY
@@ -0,0 +1,134 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::texture::Texture;
+use crate::ray::Ray;
+use glam::Vec3;
+use std::sync::Arc;
+
+/// Perturbs the shading normal of `object` with a tangent-space normal map,
+/// usually an `ImageTexture` loaded with `ImageTexture::new_linear`.
+pub struct NormalMap {
+    object: Arc<dyn Hittable>,
+    texture: Box<dyn Texture>,
+    strength: f32,
+}
+
+impl NormalMap {
+    pub fn new(object: Arc<dyn Hittable>, texture: Box<dyn Texture>, strength: f32) -> Self {
+        Self {
+            object,
+            texture,
+            strength,
+        }
+    }
+}
+
+impl Hittable for NormalMap {
+    fn hit(&self, r: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let mut rec = self.object.hit(r, ray_interval)?;
+        // Filter the map over the pixel's footprint, as colour textures are.
+        rec.compute_footprint(r);
+
+        let n = rec.outward_normal();
+        let tangent = (rec.dpdu - n * n.dot(rec.dpdu)).normalize_or_zero();
+        if tangent == Vec3::ZERO {
+            return Some(rec);
+        }
+        let mut bitangent = n.cross(tangent);
+        if bitangent.dot(rec.dpdv) < 0.0 {
+            bitangent = -bitangent;
+        }
+
+        let sample = self.texture.value_at(&rec) * 2.0 - Vec3::ONE;
+        let local = Vec3::new(
+            sample.x * self.strength,
+            sample.y * self.strength,
+            sample.z.max(0.0),
+        );
+        let perturbed = (local.x * tangent + local.y * bitangent + local.z * n).normalize_or(n);
+
+        set_shading_normal(&mut rec, r, perturbed);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.object.bounding_box()
+    }
+}
+
+const DELTA: f32 = 0.000_5;
+
+/// Perturbs the shading normal of `object` from the gradient of a scalar
+/// height field, read from the first channel of any texture.
+pub struct BumpMap {
+    object: Arc<dyn Hittable>,
+    height: Box<dyn Texture>,
+    scale: f32,
+}
+
+impl BumpMap {
+    pub fn new(object: Arc<dyn Hittable>, height: Box<dyn Texture>, scale: f32) -> Self {
+        Self {
+            object,
+            height,
+            scale,
+        }
+    }
+
+    /// The height at `rec` moved `du` and `dv` across the surface.
+    fn height_at(&self, rec: &HitRecord, du: f32, dv: f32) -> f32 {
+        let mut shifted = rec.clone();
+        shifted.u += du;
+        shifted.v += dv;
+        shifted.point += du * rec.dpdu + dv * rec.dpdv;
+        self.height.value_at(&shifted).x * self.scale
+    }
+}
+
+impl Hittable for BumpMap {
+    fn hit(&self, r: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let mut rec = self.object.hit(r, ray_interval)?;
+        rec.compute_footprint(r);
+
+        // Step backwards at the upper edge so (u, v) stays inside [0, 1].
+        let du = if rec.u + DELTA > 1.0 { -DELTA } else { DELTA };
+        let dv = if rec.v + DELTA > 1.0 { -DELTA } else { DELTA };
+        let n = rec.outward_normal();
+
+        let height = self.height_at(&rec, 0.0, 0.0);
+        let height_u = self.height_at(&rec, du, 0.0);
+        let height_v = self.height_at(&rec, 0.0, dv);
+
+        let dpdu = rec.dpdu + (height_u - height) / du * n;
+        let dpdv = rec.dpdv + (height_v - height) / dv * n;
+
+        let mut perturbed = dpdu.cross(dpdv).normalize_or(n);
+        if perturbed.dot(n) < 0.0 {
+            perturbed = -perturbed;
+        }
+
+        set_shading_normal(&mut rec, r, perturbed);
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        self.object.bounding_box()
+    }
+}
+
+/// Replaces the record's normal with `outward`, flipped onto the side that was
+/// hit. A shading normal that would face away from the viewer is bent back
+/// towards them, otherwise materials scatter into the surface.
+fn set_shading_normal(rec: &mut HitRecord, r: &Ray, outward: Vec3) {
+    let mut normal = if rec.front_face { outward } else { -outward };
+
+    let to_viewer = -r.direction.normalize();
+    let min_cos = 0.01;
+    let cos_view = normal.dot(to_viewer);
+    if cos_view < min_cos {
+        normal = (normal + (min_cos - cos_view) * to_viewer).normalize();
+    }
+
+    rec.normal = normal;
+}
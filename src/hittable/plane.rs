@@ -0,0 +1,67 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::disk::perpendicular_frame;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::Vec3;
+use std::sync::Arc;
+
+/// An infinite plane through `point`, facing along `normal`. (u, v) are
+/// distances along the plane from `point`, so use a repeating texture.
+///
+/// Its bounding box is infinite, so keep it out of a `BvhNode`; see
+/// `HittableList::into_bvh`.
+pub struct Plane {
+    point: Vec3,
+    normal: Vec3,
+    e1: Vec3,
+    e2: Vec3,
+    material: Arc<dyn Material>,
+}
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: Arc<dyn Material>) -> Self {
+        let normal = normal.normalize();
+        let (e1, e2) = perpendicular_frame(normal);
+        Self {
+            point,
+            normal,
+            e1,
+            e2,
+            material,
+        }
+    }
+}
+
+impl Hittable for Plane {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction);
+        if denom.abs() < f32::EPSILON {
+            return None;
+        }
+
+        let t = self.normal.dot(self.point - ray.origin) / denom;
+        if !ray_interval.surrounds(t) {
+            return None;
+        }
+
+        let point = ray.at(t);
+        let offset = point - self.point;
+        let rec = HitRecord::new(
+            point,
+            t,
+            ray,
+            self.normal,
+            self.material.clone(),
+            (offset.dot(self.e1), offset.dot(self.e2)),
+        )
+        .with_tangents(self.e1, self.e2);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &Aabb::UNIVERSE
+    }
+}
@@ -69,7 +69,8 @@ impl Hittable for Quad {
             self.normal,
             self.material.clone(),
             plane_coord,
-        );
+        )
+        .with_tangents(self.u, self.v);
 
         Some(rec)
     }
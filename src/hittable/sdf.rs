@@ -0,0 +1,355 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::sphere::get_sphere_uv;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::{Quat, Vec3, vec2, vec3};
+use std::sync::Arc;
+
+/// A signed distance expression: negative inside the shape, positive outside,
+/// and never more than the true distance to the surface.
+///
+/// Shapes are centred on the origin; build scenes up with the combinators,
+/// e.g. `Sdf::Sphere(1.0).smooth_union(Sdf::Box(Vec3::ONE), 0.2).twist(0.5)`.
+pub enum Sdf {
+    Sphere(f32),
+    /// Half extents along each axis.
+    Box(Vec3),
+    /// Half extents and the radius the edges are rounded off by.
+    RoundedBox(Vec3, f32),
+    /// Major and minor radius, lying in the xz plane.
+    Torus(f32, f32),
+    /// A line segment between two points, thickened by a radius.
+    Capsule(Vec3, Vec3, f32),
+    /// The power 8 Mandelbulb and friends, by its distance estimator. Fits
+    /// inside a radius of about 1.2.
+    Mandelbulb {
+        power: f32,
+        iterations: u32,
+    },
+    Union(Box<Sdf>, Box<Sdf>),
+    Intersection(Box<Sdf>, Box<Sdf>),
+    /// The first shape with the second carved out of it.
+    Subtraction(Box<Sdf>, Box<Sdf>),
+    /// A union blended over a distance `k`.
+    SmoothUnion(Box<Sdf>, Box<Sdf>, f32),
+    Translate(Box<Sdf>, Vec3),
+    Rotate(Box<Sdf>, Quat),
+    Scale(Box<Sdf>, f32),
+    /// Repeats space every `period` along each axis; a zero component leaves
+    /// that axis alone. The shape should fit within one cell.
+    Repeat(Box<Sdf>, Vec3),
+    /// Rotates each horizontal slice about the y axis by `rate` radians per
+    /// unit of height.
+    Twist(Box<Sdf>, f32),
+}
+
+impl Sdf {
+    pub fn union(self, other: Sdf) -> Sdf {
+        Sdf::Union(Box::new(self), Box::new(other))
+    }
+
+    pub fn intersection(self, other: Sdf) -> Sdf {
+        Sdf::Intersection(Box::new(self), Box::new(other))
+    }
+
+    pub fn subtraction(self, other: Sdf) -> Sdf {
+        Sdf::Subtraction(Box::new(self), Box::new(other))
+    }
+
+    pub fn smooth_union(self, other: Sdf, k: f32) -> Sdf {
+        Sdf::SmoothUnion(Box::new(self), Box::new(other), k)
+    }
+
+    pub fn translate(self, offset: Vec3) -> Sdf {
+        Sdf::Translate(Box::new(self), offset)
+    }
+
+    pub fn rotate(self, rotation: Quat) -> Sdf {
+        Sdf::Rotate(Box::new(self), rotation)
+    }
+
+    pub fn scale(self, factor: f32) -> Sdf {
+        Sdf::Scale(Box::new(self), factor)
+    }
+
+    pub fn repeat(self, period: Vec3) -> Sdf {
+        Sdf::Repeat(Box::new(self), period)
+    }
+
+    pub fn twist(self, rate: f32) -> Sdf {
+        Sdf::Twist(Box::new(self), rate)
+    }
+
+    pub fn distance(&self, p: Vec3) -> f32 {
+        match self {
+            Sdf::Sphere(radius) => p.length() - radius,
+            Sdf::Box(half_extents) => box_distance(p, *half_extents),
+            Sdf::RoundedBox(half_extents, radius) => {
+                box_distance(p, *half_extents - Vec3::splat(*radius)) - radius
+            }
+            Sdf::Torus(major, minor) => vec2(vec2(p.x, p.z).length() - major, p.y).length() - minor,
+            Sdf::Capsule(a, b, radius) => {
+                let pa = p - a;
+                let ba = b - a;
+                let h = (pa.dot(ba) / ba.dot(ba)).clamp(0.0, 1.0);
+                (pa - ba * h).length() - radius
+            }
+            Sdf::Mandelbulb { power, iterations } => mandelbulb(p, *power, *iterations),
+            Sdf::Union(a, b) => a.distance(p).min(b.distance(p)),
+            Sdf::Intersection(a, b) => a.distance(p).max(b.distance(p)),
+            Sdf::Subtraction(a, b) => a.distance(p).max(-b.distance(p)),
+            Sdf::SmoothUnion(a, b, k) => {
+                let da = a.distance(p);
+                let db = b.distance(p);
+                let h = (0.5 + 0.5 * (db - da) / k).clamp(0.0, 1.0);
+                db + (da - db) * h - k * h * (1.0 - h)
+            }
+            Sdf::Translate(sdf, offset) => sdf.distance(p - offset),
+            Sdf::Rotate(sdf, rotation) => sdf.distance(rotation.inverse() * p),
+            Sdf::Scale(sdf, factor) => sdf.distance(p / factor) * factor,
+            Sdf::Repeat(sdf, period) => {
+                let cell = Vec3::select(period.cmpgt(Vec3::ZERO), (p / period).round(), Vec3::ZERO);
+                sdf.distance(p - cell * period)
+            }
+            Sdf::Twist(sdf, rate) => {
+                let (sin, cos) = (-rate * p.y).sin_cos();
+                sdf.distance(vec3(cos * p.x - sin * p.z, p.y, sin * p.x + cos * p.z))
+            }
+        }
+    }
+
+    /// Points out of the surface, from central differences.
+    fn normal(&self, p: Vec3) -> Vec3 {
+        let h = NORMAL_DELTA;
+        let gradient = vec3(
+            self.distance(p + Vec3::X * h) - self.distance(p - Vec3::X * h),
+            self.distance(p + Vec3::Y * h) - self.distance(p - Vec3::Y * h),
+            self.distance(p + Vec3::Z * h) - self.distance(p - Vec3::Z * h),
+        );
+        gradient.normalize_or(Vec3::Y)
+    }
+}
+
+fn box_distance(p: Vec3, half_extents: Vec3) -> f32 {
+    let q = p.abs() - half_extents;
+    q.max(Vec3::ZERO).length() + q.max_element().min(0.0)
+}
+
+fn mandelbulb(p: Vec3, power: f32, iterations: u32) -> f32 {
+    let mut z = p;
+    let mut dr = 1.0;
+    let mut r = z.length();
+
+    for _ in 0..iterations {
+        if !(1e-6..=2.0).contains(&r) {
+            break;
+        }
+
+        let theta = (z.z / r).acos() * power;
+        let phi = z.y.atan2(z.x) * power;
+        dr = r.powf(power - 1.0) * power * dr + 1.0;
+
+        let zr = r.powf(power);
+        z =
+            zr * vec3(
+                theta.sin() * phi.cos(),
+                theta.sin() * phi.sin(),
+                theta.cos(),
+            ) + p;
+        r = z.length();
+    }
+
+    if r < 1e-6 {
+        return 0.0;
+    }
+    0.5 * r.ln() * r / dr
+}
+
+const MAX_STEPS: usize = 512;
+const HIT_EPSILON: f32 = 1e-4;
+const NORMAL_DELTA: f32 = 1e-4;
+
+/// Renders an `Sdf` by sphere tracing: stepping along the ray by the distance
+/// to the nearest surface until it gets close enough to count as a hit.
+///
+/// The expression only needs to be valid inside `bounding_box`, which is also
+/// where marching starts and stops, so it must contain the whole shape.
+pub struct SdfHittable {
+    sdf: Sdf,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+    step_scale: f32,
+}
+
+impl SdfHittable {
+    pub fn new(sdf: Sdf, bounding_box: Aabb, material: Arc<dyn Material>) -> Self {
+        Self {
+            sdf,
+            material,
+            bounding_box,
+            step_scale: 1.0,
+        }
+    }
+
+    /// Shortens each step by `scale`. Needed when an operator such as a
+    /// strong twist or smooth union overestimates the distance and the march
+    /// tunnels through thin parts.
+    pub fn with_step_scale(mut self, scale: f32) -> Self {
+        assert!(0.0 < scale && scale <= 1.0);
+        self.step_scale = scale;
+        self
+    }
+
+    fn hit_record(&self, ray: &Ray, t: f32) -> HitRecord {
+        let point = ray.at(t);
+        let outward_normal = self.sdf.normal(point);
+        HitRecord::new(
+            point,
+            t,
+            ray,
+            outward_normal,
+            self.material.clone(),
+            get_sphere_uv(outward_normal),
+        )
+    }
+}
+
+impl Hittable for SdfHittable {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let span = self.bounding_box.clip(ray, ray_interval)?;
+        let length = ray.direction.length();
+
+        // Rays that start inside, such as those refracted into a dielectric,
+        // march on the negated distance to find the way out. Rays leaving a
+        // surface, as every bounce does, start too close to it to tell which
+        // side they're on, so step clear of it first.
+        let mut t = span.min;
+        let mut side = 0.0;
+
+        for _ in 0..MAX_STEPS {
+            if t > span.max {
+                return None;
+            }
+
+            let distance = self.sdf.distance(ray.at(t));
+            if side == 0.0 {
+                if distance.abs() < HIT_EPSILON {
+                    t += HIT_EPSILON / length;
+                    continue;
+                }
+                side = distance.signum();
+            }
+
+            let distance = side * distance;
+            if distance < HIT_EPSILON && t > ray_interval.min {
+                return ray_interval.surrounds(t).then(|| self.hit_record(ray, t));
+            }
+            t += distance.max(HIT_EPSILON) * self.step_scale / length;
+        }
+
+        // Out of steps, most likely grazing the surface.
+        None
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn unit_sphere() -> SdfHittable {
+        let bounds = Interval::new(-1.5, 1.5);
+        SdfHittable::new(
+            Sdf::Sphere(1.0),
+            Aabb::new(bounds, bounds, bounds),
+            Arc::new(Lambertian::from(Vec3::ONE)),
+        )
+    }
+
+    /// Where a ray straight down -z at (x, y) first hits `sdf`.
+    fn trace(sdf: Sdf, x: f32, y: f32) -> Option<f32> {
+        let bounds = Interval::new(-3.0, 3.0);
+        let shape = SdfHittable::new(
+            sdf,
+            Aabb::new(bounds, bounds, bounds),
+            Arc::new(Lambertian::from(Vec3::ONE)),
+        );
+        let ray = Ray::new(vec3(x, y, 3.0), -Vec3::Z, 0.0);
+        shape
+            .hit(&ray, Interval::new(0.0001, f32::INFINITY))
+            .map(|rec| rec.point.z)
+    }
+
+    #[test]
+    fn unions_hit_either_shape() {
+        let shapes = || {
+            Sdf::Sphere(0.5).translate(vec3(-1.5, 0.0, 0.0)).union(
+                // Stood up to face the ray.
+                Sdf::Torus(1.0, 0.25)
+                    .rotate(Quat::from_rotation_x(FRAC_PI_2))
+                    .translate(vec3(1.5, 0.0, 0.0)),
+            )
+        };
+        let sphere = trace(shapes(), -1.5, 0.0).unwrap();
+        assert!((sphere - 0.5).abs() < 1e-3, "sphere at z = {sphere}");
+        let tube = trace(shapes(), 2.5, 0.0).unwrap();
+        assert!((tube - 0.25).abs() < 1e-3, "tube at z = {tube}");
+        assert!(trace(shapes(), 1.5, 0.0).is_none(), "through the hole");
+    }
+
+    #[test]
+    fn intersections_hit_only_the_overlap() {
+        let rounded_cube = || Sdf::Box(Vec3::ONE).intersection(Sdf::Sphere(1.2));
+        let face = trace(rounded_cube(), 0.0, 0.0).unwrap();
+        assert!((face - 1.0).abs() < 1e-3, "face at z = {face}");
+        let corner = trace(rounded_cube(), 0.7, 0.7).unwrap();
+        let expected = (1.44_f32 - 0.98).sqrt();
+        assert!((corner - expected).abs() < 1e-3, "corner at z = {corner}");
+        assert!(trace(rounded_cube(), 0.9, 0.9).is_none());
+    }
+
+    #[test]
+    fn hits_the_near_side_from_outside() {
+        let sphere = unit_sphere();
+        let ray = Ray::new(vec3(0.0, 0.0, 3.0), -Vec3::Z, 0.0);
+        let rec = sphere
+            .hit(&ray, Interval::new(0.0001, f32::INFINITY))
+            .expect("the ray points at the sphere");
+        assert!((rec.t - 2.0).abs() < 1e-3, "entered at t = {}", rec.t);
+        assert!(rec.front_face);
+    }
+
+    #[test]
+    fn rays_from_the_surface_find_the_far_side() {
+        let sphere = unit_sphere();
+        let entry = sphere
+            .hit(
+                &Ray::new(vec3(0.0, 0.0, 3.0), -Vec3::Z, 0.0),
+                Interval::new(0.0001, f32::INFINITY),
+            )
+            .unwrap();
+
+        // Onwards from the hit point, as a refracted ray would go.
+        let inward = Ray::new(entry.point, -Vec3::Z, 0.0);
+        let exit = sphere
+            .hit(&inward, Interval::new(0.0001, f32::INFINITY))
+            .expect("a ray from the surface into the sphere leaves it again");
+        assert!((exit.point.z + 1.0).abs() < 1e-3, "left at {}", exit.point);
+        assert!(!exit.front_face);
+
+        // And back out the way it came, it misses.
+        let outward = Ray::new(entry.point, Vec3::Z, 0.0);
+        assert!(
+            sphere
+                .hit(&outward, Interval::new(0.0001, f32::INFINITY))
+                .is_none()
+        );
+    }
+}
@@ -72,6 +72,7 @@ impl Hittable for Sphere {
         }
         let point = ray.at(root);
         let outward_normal = (point - current_center) / self.radius;
+        let (dpdu, dpdv) = get_sphere_tangents(outward_normal);
 
         let rec = HitRecord::new(
             point,
@@ -80,7 +81,8 @@ impl Hittable for Sphere {
             outward_normal,
             self.material.clone(),
             get_sphere_uv(outward_normal),
-        );
+        )
+        .with_tangents(dpdu * self.radius, dpdv * self.radius);
 
         Some(rec)
     }
@@ -90,7 +92,7 @@ impl Hittable for Sphere {
     }
 }
 
-fn get_sphere_uv(p: Vec3) -> (f32, f32) {
+pub(super) fn get_sphere_uv(p: Vec3) -> (f32, f32) {
     let theta = (-p.y.clamp(-1.0, 1.0)).acos();
     let phi = (-p.z).atan2(p.x) + PI;
 
@@ -98,3 +100,16 @@ fn get_sphere_uv(p: Vec3) -> (f32, f32) {
     let v = theta / PI;
     (u, v)
 }
+
+/// Derivatives of a point on the unit sphere with respect to the (u, v) from
+/// `get_sphere_uv`. Falls back to an arbitrary frame at the poles.
+fn get_sphere_tangents(p: Vec3) -> (Vec3, Vec3) {
+    let sin_theta = (p.x * p.x + p.z * p.z).sqrt();
+    if sin_theta < 1e-6 {
+        return p.any_orthonormal_pair();
+    }
+
+    let dpdu = 2.0 * PI * Vec3::new(p.z, 0.0, -p.x);
+    let dpdv = PI * Vec3::new(-p.x * p.y / sin_theta, sin_theta, -p.y * p.z / sin_theta);
+    (dpdu, dpdv)
+}
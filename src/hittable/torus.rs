@@ -0,0 +1,188 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::disk::{disk_extent, perpendicular_frame};
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::polynomial::solve_quartic;
+use crate::ray::Ray;
+use glam::{Vec3, vec3};
+use std::f32::consts::TAU;
+use std::sync::Arc;
+
+/// A ring of tube `minor_radius` thick, swept around `axis` at `major_radius`
+/// from the centre. `u` runs around the axis and `v` around the tube.
+pub struct Torus {
+    center: Vec3,
+    e1: Vec3,
+    e2: Vec3,
+    w: Vec3,
+    major_radius: f32,
+    minor_radius: f32,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Torus {
+    pub fn new(
+        center: Vec3,
+        axis: Vec3,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Arc<dyn Material>,
+    ) -> Self {
+        assert!(major_radius > 0.0 && minor_radius > 0.0);
+        let w = axis.normalize();
+        let (e1, e2) = perpendicular_frame(w);
+
+        // The tube's centre line is a circle, so the torus reaches as far as
+        // that circle does along each axis, plus the tube.
+        let extent = disk_extent(w, major_radius) + Vec3::splat(minor_radius);
+
+        Self {
+            center,
+            e1,
+            e2,
+            w,
+            major_radius,
+            minor_radius,
+            material,
+            bounding_box: Aabb::from((center - extent, center + extent)),
+        }
+    }
+
+    fn to_local(&self, v: Vec3) -> Vec3 {
+        vec3(v.dot(self.e1), v.dot(self.e2), v.dot(self.w))
+    }
+
+    fn to_world(&self, v: Vec3) -> Vec3 {
+        v.x * self.e1 + v.y * self.e2 + v.z * self.w
+    }
+}
+
+impl Hittable for Torus {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let length = ray.direction.length();
+        let direction = self.to_local(ray.direction / length).as_dvec3();
+        let origin = self.to_local(ray.origin - self.center).as_dvec3();
+
+        // The quartic is badly conditioned far from the torus, so solve from
+        // where the ray enters the bounding sphere instead.
+        let bound = (self.major_radius + self.minor_radius) as f64;
+        let along = origin.dot(direction);
+        let miss_sq = origin.length_squared() - along * along;
+        if miss_sq > bound * bound {
+            return None;
+        }
+        let start = (-along - (bound * bound - miss_sq).sqrt()).max(0.0);
+        let origin = origin + start * direction;
+
+        // (|p|^2 - R^2 - r^2)^2 = 4R^2 (r^2 - p_z^2) along p = o + sd, |d| = 1.
+        let major_sq = (self.major_radius * self.major_radius) as f64;
+        let minor_sq = (self.minor_radius * self.minor_radius) as f64;
+        let e = origin.length_squared() - major_sq - minor_sq;
+        let f = origin.dot(direction);
+        let four_major_sq = 4.0 * major_sq;
+        let roots = solve_quartic([
+            e * e - four_major_sq * (minor_sq - origin.z * origin.z),
+            4.0 * f * e + 2.0 * four_major_sq * origin.z * direction.z,
+            2.0 * e + 4.0 * f * f + four_major_sq * direction.z * direction.z,
+            4.0 * f,
+            1.0,
+        ]);
+
+        let t = roots
+            .iter()
+            .map(|&s| ((s + start) / length as f64) as f32)
+            .filter(|&t| ray_interval.surrounds(t))
+            .min_by(f32::total_cmp)?;
+
+        let point = ray.at(t);
+        let local = self.to_local(point - self.center);
+        let phi = local.y.atan2(local.x).rem_euclid(TAU);
+        let ring = vec3(phi.cos(), phi.sin(), 0.0);
+        let tube = local - self.major_radius * ring;
+        let theta = local.z.atan2(tube.dot(ring)).rem_euclid(TAU);
+
+        let outward_normal = self.to_world(tube).normalize();
+        let dpdu = TAU * self.to_world(vec3(-local.y, local.x, 0.0));
+        let dpdv = TAU
+            * self.minor_radius
+            * self.to_world(vec3(
+                -theta.sin() * ring.x,
+                -theta.sin() * ring.y,
+                theta.cos(),
+            ));
+
+        let rec = HitRecord::new(
+            point,
+            t,
+            ray,
+            outward_normal,
+            self.material.clone(),
+            (phi / TAU, theta / TAU),
+        )
+        .with_tangents(dpdu, dpdv);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+
+    /// A ring 2 across its middle with a tube 0.5 thick, lying flat.
+    fn ring() -> Torus {
+        let material = Arc::new(Lambertian::from(Vec3::ONE));
+        Torus::new(Vec3::ZERO, Vec3::Y, 2.0, 0.5, material)
+    }
+
+    fn hit(origin: Vec3, direction: Vec3) -> Option<HitRecord> {
+        ring().hit(
+            &Ray::new(origin, direction, 0.0),
+            Interval::new(0.0001, f32::INFINITY),
+        )
+    }
+
+    #[test]
+    fn rays_down_the_axis_miss() {
+        assert!(hit(vec3(0.0, 5.0, 0.0), -Vec3::Y).is_none());
+        assert!(hit(vec3(1.2, 5.0, 0.0), -Vec3::Y).is_none());
+    }
+
+    #[test]
+    fn rays_hit_the_nearest_side_of_the_tube() {
+        let rec = hit(vec3(-5.0, 0.0, 0.0), Vec3::X).unwrap();
+        assert!((rec.t - 2.5).abs() < 1e-4, "hit at t = {}", rec.t);
+        assert!(rec.normal.abs_diff_eq(-Vec3::X, 1e-4));
+
+        // From the middle, out through the inside of the tube.
+        let rec = hit(Vec3::ZERO, vec3(0.0, 0.0, 2.0)).unwrap();
+        assert!((rec.t - 0.75).abs() < 1e-4, "hit at t = {}", rec.t);
+        assert!(rec.normal.abs_diff_eq(-Vec3::Z, 1e-4));
+
+        // Down through the tube, parallel to the axis.
+        let rec = hit(vec3(2.0, 5.0, 0.0), -Vec3::Y).unwrap();
+        assert!((rec.t - 4.5).abs() < 1e-4, "hit at t = {}", rec.t);
+    }
+
+    #[test]
+    fn grazing_rays_touch_only_the_top() {
+        // Just under the top of the tube, where the two crossings nearly meet.
+        let rec = hit(vec3(-5.0, 0.499, 0.0), Vec3::X).unwrap();
+        let across = (0.25f32 - 0.499 * 0.499).sqrt();
+        assert!(
+            (rec.t - (3.0 - across)).abs() < 1e-3,
+            "hit at t = {}",
+            rec.t
+        );
+        assert!(rec.normal.y > 0.99);
+
+        assert!(hit(vec3(-5.0, 0.501, 0.0), Vec3::X).is_none());
+    }
+}
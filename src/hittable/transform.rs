@@ -123,6 +123,18 @@ impl Hittable for RotateY {
             (-self.sin_theta * rec.normal.x) + (self.cos_theta * rec.normal.z),
         );
 
+        rec.dpdu = vec3(
+            (self.cos_theta * rec.dpdu.x) + (self.sin_theta * rec.dpdu.z),
+            rec.dpdu.y,
+            (-self.sin_theta * rec.dpdu.x) + (self.cos_theta * rec.dpdu.z),
+        );
+
+        rec.dpdv = vec3(
+            (self.cos_theta * rec.dpdv.x) + (self.sin_theta * rec.dpdv.z),
+            rec.dpdv.y,
+            (-self.sin_theta * rec.dpdv.x) + (self.cos_theta * rec.dpdv.z),
+        );
+
         Some(rec)
     }
 
@@ -0,0 +1,176 @@
+use crate::hittable::aabb::Aabb;
+use crate::hittable::{HitRecord, Hittable};
+use crate::interval::Interval;
+use crate::material::Material;
+use crate::ray::Ray;
+use glam::{Vec2, Vec3};
+use std::sync::Arc;
+
+/// A triangle, front facing when its corners run counter-clockwise.
+///
+/// Without `with_uvs` the texture coordinates are the barycentric weights of
+/// `b` and `c`, matching the (u, v) of a `Quad` over the same corners.
+pub struct Triangle {
+    a: Vec3,
+    edge1: Vec3,
+    edge2: Vec3,
+    uvs: [Vec2; 3],
+    normals: Option<[Vec3; 3]>,
+    colors: Option<[Vec3; 3]>,
+    normal: Vec3,
+    material: Arc<dyn Material>,
+    bounding_box: Aabb,
+}
+
+impl Triangle {
+    pub fn new(a: Vec3, b: Vec3, c: Vec3, material: Arc<dyn Material>) -> Self {
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let bounding_box = Aabb::from((a.min(b).min(c), a.max(b).max(c)));
+
+        Self {
+            a,
+            edge1,
+            edge2,
+            uvs: [Vec2::ZERO, Vec2::X, Vec2::Y],
+            normals: None,
+            colors: None,
+            normal: edge1.cross(edge2).normalize(),
+            material,
+            bounding_box,
+        }
+    }
+
+    pub fn with_uvs(mut self, uvs: [Vec2; 3]) -> Self {
+        self.uvs = uvs;
+        self
+    }
+
+    /// Interpolates per-corner normals across the face for smooth shading.
+    /// The geometric normal still decides which side was hit.
+    pub fn with_normals(mut self, normals: [Vec3; 3]) -> Self {
+        self.normals = Some(normals.map(Vec3::normalize));
+        self
+    }
+
+    /// Per-corner colours, blended across the face into the hit's
+    /// `vertex_color`.
+    pub fn with_colors(mut self, colors: [Vec3; 3]) -> Self {
+        self.colors = Some(colors);
+        self
+    }
+
+    /// Surface derivatives with respect to the texture coordinates, or `None`
+    /// when the UVs are degenerate.
+    fn tangents(&self) -> Option<(Vec3, Vec3)> {
+        let duv1 = self.uvs[1] - self.uvs[0];
+        let duv2 = self.uvs[2] - self.uvs[0];
+        let det = duv1.perp_dot(duv2);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        let dpdu = (duv2.y * self.edge1 - duv1.y * self.edge2) / det;
+        let dpdv = (duv1.x * self.edge2 - duv2.x * self.edge1) / det;
+        Some((dpdu, dpdv))
+    }
+}
+
+/// Möller-Trumbore, returning the distance along `ray` to the triangle with
+/// corners `a`, `a + edge1` and `a + edge2`, and the barycentric weights of
+/// the second and third corners.
+pub fn intersect_triangle(ray: &Ray, a: Vec3, edge1: Vec3, edge2: Vec3) -> Option<(f32, f32, f32)> {
+    let p = ray.direction.cross(edge2);
+    let det = edge1.dot(p);
+    // Relative to the sizes it is built from, so tiny triangles aren't lost
+    // along with the ones the ray runs parallel to.
+    let scale = edge1.length() * edge2.length() * ray.direction.length();
+    if det.abs() <= f32::EPSILON * scale {
+        return None;
+    }
+    let inv_det = det.recip();
+
+    let s = ray.origin - a;
+    let beta = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&beta) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let gamma = ray.direction.dot(q) * inv_det;
+    if gamma < 0.0 || beta + gamma > 1.0 {
+        return None;
+    }
+
+    Some((edge2.dot(q) * inv_det, beta, gamma))
+}
+
+impl Hittable for Triangle {
+    fn hit(&self, ray: &Ray, ray_interval: Interval) -> Option<HitRecord> {
+        let (t, beta, gamma) = intersect_triangle(ray, self.a, self.edge1, self.edge2)?;
+        if !ray_interval.surrounds(t) {
+            return None;
+        }
+
+        let alpha = 1.0 - beta - gamma;
+        let uv = alpha * self.uvs[0] + beta * self.uvs[1] + gamma * self.uvs[2];
+        let mut rec = HitRecord::new(
+            ray.at(t),
+            t,
+            ray,
+            self.normal,
+            self.material.clone(),
+            (uv.x, uv.y),
+        );
+        if let Some((dpdu, dpdv)) = self.tangents() {
+            rec = rec.with_tangents(dpdu, dpdv);
+        }
+
+        if let Some([na, nb, nc]) = self.normals {
+            let shading = (alpha * na + beta * nb + gamma * nc).normalize_or(self.normal);
+            rec.normal = if rec.front_face { shading } else { -shading };
+        }
+        rec.vertex_color = self
+            .colors
+            .map(|[ca, cb, cc]| alpha * ca + beta * cb + gamma * cc);
+
+        Some(rec)
+    }
+
+    fn bounding_box(&self) -> &Aabb {
+        &self.bounding_box
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::material::lambertian::Lambertian;
+    use glam::vec3;
+
+    fn hit(scale: f32, direction: Vec3) -> Option<HitRecord> {
+        let material = Arc::new(Lambertian::from(Vec3::ONE));
+        let triangle = Triangle::new(
+            Vec3::ZERO,
+            vec3(scale, 0.0, 0.0),
+            vec3(0.0, scale, 0.0),
+            material,
+        );
+        let ray = Ray::new(vec3(0.25, 0.25, 1.0) * scale, direction, 0.0);
+        triangle.hit(&ray, Interval::new(0.0, f32::INFINITY))
+    }
+
+    #[test]
+    fn tiny_triangles_are_hit() {
+        for scale in [1.0, 1e-3, 1e-5] {
+            let rec = hit(scale, -Vec3::Z).expect("the ray points at the triangle");
+            assert!((rec.t / scale - 1.0).abs() < 1e-4, "hit at t = {}", rec.t);
+        }
+    }
+
+    #[test]
+    fn parallel_rays_miss() {
+        assert!(hit(1.0, Vec3::X).is_none());
+        assert!(hit(1e-3, Vec3::X).is_none());
+    }
+}
@@ -0,0 +1,293 @@
+use crate::aperture::{Aperture, Circular, concentric_disk};
+use crate::projection::Projection;
+use glam::{Vec2, Vec3, vec2, vec3};
+
+/// One surface of a lens prescription, as listed in optical design books
+/// and patents, front to back. Lengths are in millimetres.
+#[derive(Copy, Clone)]
+pub struct LensElement {
+    /// Radius of curvature, positive when the centre is behind the surface,
+    /// towards the film. 0 marks the aperture stop.
+    pub radius: f32,
+    /// Distance along the axis to the next surface, or from the last to the
+    /// film, which focusing changes.
+    pub thickness: f32,
+    /// Refractive index behind the surface; 0 or 1 is air.
+    pub refraction_index: f32,
+    /// Diameter of the surface's clear aperture, beyond which light is
+    /// blocked by the barrel.
+    pub aperture: f32,
+}
+
+impl LensElement {
+    fn is_stop(&self) -> bool {
+        self.radius == 0.0
+    }
+
+    fn index_behind(&self) -> f32 {
+        if self.refraction_index == 0.0 {
+            1.0
+        } else {
+            self.refraction_index
+        }
+    }
+}
+
+/// A camera lens built from its prescription, tracing every ray through each
+/// glass surface on the way out of the camera.
+///
+/// This gives the distortion, the vignetting, the fall-off in sharpness
+/// towards the corners and the bokeh of the real lens, and the aperture stop
+/// can be given any shape. The scene is taken to be in metres.
+pub struct LensSystem {
+    elements: Vec<LensElement>,
+    /// Where each surface meets the axis, with the film at 0 and the scene
+    /// towards -z, in millimetres.
+    vertices: Vec<f32>,
+    /// Diagonal of the film, in millimetres.
+    film_diagonal: f32,
+    aperture: Box<dyn Aperture>,
+    stop_radius: f32,
+    /// Radius of the disk on the rear surface that rays from anywhere on
+    /// the film can get through the lens from.
+    pupil_radius: f32,
+}
+
+impl LensSystem {
+    /// The lens `elements` focused at `focus_dist` metres, onto film
+    /// `film_diagonal` millimetres across, 43 for full frame.
+    ///
+    /// # Panics
+    ///
+    /// If the elements have no aperture stop, or the lens can't focus that
+    /// close.
+    pub fn new(elements: Vec<LensElement>, film_diagonal: f32, focus_dist: f32) -> Self {
+        let stop = elements
+            .iter()
+            .find(|element| element.is_stop())
+            .expect("a lens needs an aperture stop, a surface with radius 0");
+        let mut lens = Self {
+            vertices: Vec::new(),
+            film_diagonal,
+            aperture: Box::new(Circular),
+            stop_radius: stop.aperture / 2.0,
+            pupil_radius: 0.0,
+            elements,
+        };
+        lens.place_elements();
+        lens.focus(focus_dist * 1000.0);
+        lens.find_pupil();
+        lens
+    }
+
+    /// Stops the lens down to an opening `diameter` millimetres across, no
+    /// wider than the stop it was designed with.
+    pub fn with_aperture_diameter(mut self, diameter: f32) -> Self {
+        let stop = self.elements.iter().find(|element| element.is_stop());
+        let largest = stop.map_or(0.0, |stop| stop.aperture);
+        self.stop_radius = diameter.min(largest) / 2.0;
+        self.find_pupil();
+        self
+    }
+
+    /// Gives the aperture stop a shape, such as the polygon of an iris.
+    pub fn with_aperture(mut self, aperture: Box<dyn Aperture>) -> Self {
+        self.aperture = aperture;
+        self.find_pupil();
+        self
+    }
+
+    fn place_elements(&mut self) {
+        let mut z = 0.0;
+        self.vertices = vec![0.0; self.elements.len()];
+        for (vertex, element) in self.vertices.iter_mut().zip(&self.elements).rev() {
+            z -= element.thickness;
+            *vertex = z;
+        }
+    }
+
+    /// Moves the elements along the axis, as turning the focus ring does, so
+    /// a point `distance` millimetres in front of the film is sharp.
+    ///
+    /// The lens is treated as thick, with the principal planes and focal
+    /// length found by tracing rays close to the axis through it.
+    fn focus(&mut self, distance: f32) {
+        let height = 0.01 * self.elements[0].aperture / 2.0;
+        let front = self.vertices[0];
+        let rear = self.vertices[self.vertices.len() - 1];
+
+        // A ray from far away comes to a focus behind the lens, and bends at
+        // the rear principal plane; one from the film the same at the front.
+        let incoming = vec3(height, 0.0, front - 1.0);
+        let (origin, direction) = self
+            .trace_from_scene(incoming, Vec3::Z)
+            .expect("a ray along the axis cannot get through the lens");
+        let focal_point = origin.z - origin.x / direction.x * direction.z;
+        let rear_plane = origin.z + (height - origin.x) / direction.x * direction.z;
+        let focal_length = focal_point - rear_plane;
+
+        let outgoing = vec3(height, 0.0, rear + 1.0);
+        let (origin, direction) = self
+            .trace_from_film(outgoing, -Vec3::Z)
+            .expect("a ray along the axis cannot get through the lens");
+        let front_plane = origin.z + (height - origin.x) / direction.x * direction.z;
+
+        // The thick lens equation, for how far to move the lens away from the
+        // film.
+        let object = front_plane + distance;
+        let image = -rear_plane;
+        let discriminant = (object + image) * (object + image - 4.0 * focal_length);
+        assert!(
+            discriminant >= 0.0,
+            "the lens cannot focus {} m away",
+            distance / 1000.0
+        );
+        let shift = 0.5 * ((object - image) - discriminant.sqrt());
+
+        for vertex in &mut self.vertices {
+            *vertex -= shift;
+        }
+        if let Some(last) = self.elements.last_mut() {
+            last.thickness += shift;
+        }
+    }
+
+    /// Finds how much of the rear surface rays aimed from the film need to
+    /// cover, so few are wasted on the barrel. Every part of the film samples
+    /// the same disk, so the fall-off in light towards the corners is kept.
+    fn find_pupil(&mut self) {
+        const FILM_SAMPLES: usize = 16;
+        const GRID: usize = 64;
+
+        let rear = self.elements[self.elements.len() - 1].aperture / 2.0;
+        let rear_z = self.vertices[self.vertices.len() - 1];
+        let mut radius: f32 = 0.0;
+        for f in 0..=FILM_SAMPLES {
+            let film = vec3(
+                f as f32 / FILM_SAMPLES as f32 * self.film_diagonal / 2.0,
+                0.0,
+                0.0,
+            );
+            for i in 0..GRID {
+                for j in 0..GRID {
+                    let point = vec2(
+                        (i as f32 + 0.5) / GRID as f32 * 2.0 - 1.0,
+                        (j as f32 + 0.5) / GRID as f32 * 2.0 - 1.0,
+                    ) * rear;
+                    if point.length() <= rear
+                        && point.length() > radius
+                        && self
+                            .trace_from_film(film, point.extend(rear_z) - film)
+                            .is_some()
+                    {
+                        radius = point.length();
+                    }
+                }
+            }
+        }
+        assert!(radius > 0.0, "no light gets through the lens");
+
+        // Allow for the gaps between the points tried.
+        self.pupil_radius = (radius + 2.0 * rear / GRID as f32).min(rear);
+    }
+
+    /// Follows light back from the film out through the front of the lens,
+    /// or `None` if it hits the barrel, the stop or is totally reflected.
+    fn trace_from_film(&self, mut origin: Vec3, mut direction: Vec3) -> Option<(Vec3, Vec3)> {
+        direction = direction.normalize();
+        for i in (0..self.elements.len()).rev() {
+            let element = &self.elements[i];
+            let beyond = if i > 0 {
+                self.elements[i - 1].index_behind()
+            } else {
+                1.0
+            };
+            (origin, direction) =
+                self.cross(i, origin, direction, element.index_behind() / beyond)?;
+        }
+        Some((origin, direction))
+    }
+
+    /// Follows light from the scene in through the lens to the film.
+    fn trace_from_scene(&self, mut origin: Vec3, mut direction: Vec3) -> Option<(Vec3, Vec3)> {
+        direction = direction.normalize();
+        for i in 0..self.elements.len() {
+            let element = &self.elements[i];
+            let before = if i > 0 {
+                self.elements[i - 1].index_behind()
+            } else {
+                1.0
+            };
+            (origin, direction) =
+                self.cross(i, origin, direction, before / element.index_behind())?;
+        }
+        Some((origin, direction))
+    }
+
+    /// Takes a ray across surface `index`, refracting by the ratio of the
+    /// indices either side, `eta`.
+    fn cross(&self, index: usize, origin: Vec3, direction: Vec3, eta: f32) -> Option<(Vec3, Vec3)> {
+        let element = &self.elements[index];
+        let vertex = self.vertices[index];
+
+        if element.is_stop() {
+            let t = (vertex - origin.z) / direction.z;
+            let point = origin + t * direction;
+            let inside = self.aperture.contains(point.truncate() / self.stop_radius);
+            return (t > 0.0 && inside).then_some((point, direction));
+        }
+
+        let center = vec3(0.0, 0.0, vertex + element.radius);
+        let to_origin = origin - center;
+        let b = to_origin.dot(direction);
+        let c = to_origin.length_squared() - element.radius * element.radius;
+        let discriminant = b * b - c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        // Of the two crossings of the sphere, the surface is the one on the
+        // side of its vertex.
+        let nearer = (direction.z > 0.0) != (element.radius < 0.0);
+        let t = if nearer {
+            -b - discriminant.sqrt()
+        } else {
+            -b + discriminant.sqrt()
+        };
+        if t <= 0.0 {
+            return None;
+        }
+        let point = origin + t * direction;
+        if point.truncate().length() > element.aperture / 2.0 {
+            return None;
+        }
+
+        let mut normal = (point - center).normalize();
+        if normal.dot(direction) > 0.0 {
+            normal = -normal;
+        }
+        let refracted = direction.refract(normal, eta);
+        (refracted != Vec3::ZERO).then_some((point, refracted))
+    }
+
+    fn film_size(&self, aspect_ratio: f32) -> Vec2 {
+        let height = self.film_diagonal / (1.0 + aspect_ratio * aspect_ratio).sqrt();
+        vec2(height * aspect_ratio, height)
+    }
+}
+
+impl Projection for LensSystem {
+    fn ray(&self, point: Vec2, aspect_ratio: f32, lens: Vec2) -> Option<(Vec3, Vec3)> {
+        // The lens forms the image upside down and back to front.
+        let film_size = self.film_size(aspect_ratio);
+        let film = vec3(
+            (0.5 - point.x) * film_size.x,
+            (point.y - 0.5) * film_size.y,
+            0.0,
+        );
+        let rear_z = self.vertices[self.vertices.len() - 1];
+        let target = (concentric_disk(lens) * self.pupil_radius).extend(rear_z);
+
+        let (origin, direction) = self.trace_from_film(film, target - film)?;
+        Some((origin / 1000.0, direction))
+    }
+}
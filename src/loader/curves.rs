@@ -0,0 +1,133 @@
+use crate::hittable::curve::{Curve, CurveShape};
+use crate::hittable::hittable_list::HittableList;
+use crate::loader::LoaderError;
+use crate::material::Material;
+use glam::Vec3;
+use std::fs;
+use std::sync::Arc;
+
+/// Loads strands of hair or grass from a text file with one strand per line:
+/// the width at the root and at the tip, then the `3n + 1` control points of
+/// its Bézier segments as `x y z` triples. Blank lines and anything after a
+/// `#` are ignored.
+///
+/// ```text
+/// # root  tip    control points
+/// 0.02 0.005  0 0 0  0 0.3 0  0.1 0.6 0  0.2 1 0
+/// ```
+pub fn load_curves(
+    file_path: &str,
+    shape: CurveShape,
+    material: Arc<dyn Material>,
+) -> Result<HittableList, LoaderError> {
+    let text = fs::read_to_string(file_path).map_err(|source| LoaderError::Io {
+        path: file_path.to_string(),
+        source,
+    })?;
+    read_curves(file_path, &text, shape, material)
+}
+
+fn read_curves(
+    file_path: &str,
+    text: &str,
+    shape: CurveShape,
+    material: Arc<dyn Material>,
+) -> Result<HittableList, LoaderError> {
+    let mut curves = HittableList::new();
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| LoaderError::Parse {
+            path: file_path.to_string(),
+            line: index + 1,
+            message,
+        };
+
+        let content = line.split('#').next().unwrap_or_default();
+        let values = content
+            .split_whitespace()
+            .map(|token| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| error(format!("expected a number, found \"{token}\"")))
+            })
+            .collect::<Result<Vec<f32>, _>>()?;
+        if values.is_empty() {
+            continue;
+        }
+
+        let points = values.len().saturating_sub(2) / 3;
+        if values.len() < 14
+            || !(values.len() - 2).is_multiple_of(3)
+            || !(points - 1).is_multiple_of(3)
+        {
+            return Err(error(format!(
+                "expected two widths and 3n + 1 control points, found {} values",
+                values.len()
+            )));
+        }
+        if values[0] < 0.0 || values[1] < 0.0 {
+            return Err(error("widths must not be negative".to_string()));
+        }
+
+        let points: Vec<Vec3> = values[2..].chunks(3).map(Vec3::from_slice).collect();
+        for curve in Curve::strand(&points, [values[0], values[1]], shape, material.clone()) {
+            curves.add(Arc::new(curve));
+        }
+    }
+
+    Ok(curves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interval::Interval;
+    use crate::material::lambertian::Lambertian;
+    use crate::ray::Ray;
+    use glam::vec3;
+
+    fn read(text: &str) -> Result<HittableList, LoaderError> {
+        let material = Arc::new(Lambertian::from(Vec3::ONE));
+        read_curves("test.curves", text, CurveShape::Tube, material)
+    }
+
+    #[test]
+    fn reads_a_segment_per_three_points() {
+        let text = "# root  tip    control points
+0.2 0.2  0 0 0  0 1 0  0 2 0  0 3 0
+
+0.1 0.05  1 0 0  1 1 0  1 2 0  1 3 0  1 4 0  1 5 0  1 6 0  # two segments
+";
+        let curves = read(text).unwrap();
+        assert_eq!(curves.objects.len(), 3);
+
+        // Across the middle of the first strand, and over the top of it.
+        let ray = |y| Ray::new(vec3(0.0, y, -5.0), Vec3::Z, 0.0);
+        let hits = |y| {
+            curves.objects.iter().any(|curve| {
+                curve
+                    .hit(&ray(y), Interval::new(0.0, f32::INFINITY))
+                    .is_some()
+            })
+        };
+        assert!(hits(1.5));
+        assert!(!hits(3.5));
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let good = "0.1 0.1  0 0 0  0 1 0  0 2 0  0 3 0\n";
+        for bad in [
+            "0.1 0.1  0 0 0  0 1 0  0 2 0\n",
+            "0.1 0.1  0 0 0  0 1 0  0 2 0  0 3 0  0 4 0\n",
+            "0.1 0.1  0 0 0  0 1 0  0 2 0  0 3\n",
+            "0.1 wide  0 0 0  0 1 0  0 2 0  0 3 0\n",
+            "0.1 -0.1  0 0 0  0 1 0  0 2 0  0 3 0\n",
+        ] {
+            match read(&format!("{good}\n{bad}")) {
+                Err(LoaderError::Parse { line, .. }) => assert_eq!(line, 3, "{bad}"),
+                Err(error) => panic!("{bad}: expected a parse error, found \"{error}\""),
+                Ok(_) => panic!("{bad}: was accepted"),
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::lens::LensElement;
+use crate::loader::LoaderError;
+use std::fs;
+
+/// Loads a lens prescription with one surface per line, front to back: its
+/// radius of curvature, the thickness to the next surface, the refractive
+/// index behind it and its aperture diameter, all in millimetres. A radius of
+/// 0 marks the aperture stop. Blank lines and anything after a `#` are
+/// ignored, so the lens files that come with pbrt load as they are.
+///
+/// ```text
+/// # radius  thickness  index  aperture
+/// 29.475    3.76       1.67   25.2
+/// 84.83     0.12       1      25.2
+/// 0         4.5        0      17.1
+/// ```
+pub fn load_lens(file_path: &str) -> Result<Vec<LensElement>, LoaderError> {
+    let text = fs::read_to_string(file_path).map_err(|source| LoaderError::Io {
+        path: file_path.to_string(),
+        source,
+    })?;
+    read_lens(file_path, &text)
+}
+
+fn read_lens(file_path: &str, text: &str) -> Result<Vec<LensElement>, LoaderError> {
+    let mut elements = Vec::new();
+    let mut stops = 0;
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| LoaderError::Parse {
+            path: file_path.to_string(),
+            line: index + 1,
+            message,
+        };
+
+        let content = line.split('#').next().unwrap_or_default();
+        let values = content
+            .split_whitespace()
+            .map(|token| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| error(format!("expected a number, found \"{token}\"")))
+            })
+            .collect::<Result<Vec<f32>, _>>()?;
+        if values.is_empty() {
+            continue;
+        }
+
+        let [radius, thickness, refraction_index, aperture] = values[..] else {
+            return Err(error(format!(
+                "expected radius, thickness, index and aperture, found {} values",
+                values.len()
+            )));
+        };
+        if !values.iter().all(|value| value.is_finite()) {
+            return Err(error("values must be finite".to_string()));
+        }
+        if thickness < 0.0 || aperture <= 0.0 {
+            return Err(error(
+                "the thickness must not be negative and the aperture must be positive".to_string(),
+            ));
+        }
+        if refraction_index != 0.0 && refraction_index < 1.0 {
+            return Err(error(format!(
+                "a refractive index of {refraction_index} is less than air's"
+            )));
+        }
+        if radius != 0.0 && radius.abs() < aperture / 2.0 {
+            return Err(error(
+                "the aperture is wider than the curve of the surface".to_string(),
+            ));
+        }
+        if radius == 0.0 {
+            stops += 1;
+        }
+
+        elements.push(LensElement {
+            radius,
+            thickness,
+            refraction_index,
+            aperture,
+        });
+    }
+
+    if stops != 1 {
+        return Err(LoaderError::Format {
+            path: file_path.to_string(),
+            message: format!("expected one aperture stop, found {stops}"),
+        });
+    }
+
+    Ok(elements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lens::LensSystem;
+    use crate::projection::Projection;
+    use glam::{Vec2, vec2};
+
+    /// pbrt's dgauss.50mm.dat, which marks the stop with an index of 1.
+    const DOUBLE_GAUSS: &str = "# D-GAUSS F/2 22deg HFOV
+# US patent 2,673,491 Tronnier
+# radius\taxpos\tN\taperture
+29.475\t3.76\t1.67\t25.2
+84.83\t0.12\t1\t25.2
+19.275\t4.025\t1.67\t23
+40.77\t3.275\t1.699\t23
+12.75\t5.705\t1\t18
+
+0\t4.5\t1\t17.1  # the stop
+-14.495\t1.18\t1.603\t17
+40.77\t6.065\t1.658\t20
+-20.385\t0.19\t1\t20
+437.065\t3.22\t1.717\t20
+-39.73\t5\t1\t20
+";
+
+    #[test]
+    fn reads_pbrt_lens_files() {
+        let elements = read_lens("test.dat", DOUBLE_GAUSS).unwrap();
+        assert_eq!(elements.len(), 11);
+        assert_eq!(elements[5].radius, 0.0);
+        assert_eq!(elements[5].aperture, 17.1);
+        assert_eq!(elements[10].radius, -39.73);
+        assert_eq!(elements[10].thickness, 5.0);
+    }
+
+    #[test]
+    fn focuses_at_the_requested_distance() {
+        let elements = read_lens("test.dat", DOUBLE_GAUSS).unwrap();
+        for focus_dist in [0.6, 2.0, 10.0] {
+            let lens = LensSystem::new(elements.clone(), 43.0, focus_dist);
+            // Rays close to the axis from the middle of the film cross it
+            // again where the lens is focused.
+            for offset in [vec2(0.02, 0.0), vec2(-0.02, 0.0), vec2(0.0, 0.02)] {
+                let (origin, direction) = lens
+                    .ray(vec2(0.5, 0.5), 1.5, Vec2::splat(0.5) + offset)
+                    .unwrap();
+                let across = if offset.x != 0.0 { 0 } else { 1 };
+                let t = -origin[across] / direction[across];
+                let z = origin.z + t * direction.z;
+                assert!(
+                    (z + focus_dist).abs() < 0.01 * focus_dist,
+                    "focused at {} m rather than {focus_dist} m",
+                    -z
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let stop = "0 4.5 0 17.1\n";
+        for (text, line) in [
+            ("29.475 3.76 1.67\n", 1),
+            ("29.475 3.76 1.67 25.2 1\n", 1),
+            ("29.475 3.76 glass 25.2\n", 1),
+            ("29.475 3.76 1.67 inf\n", 1),
+            ("29.475 -3.76 1.67 25.2\n", 1),
+            ("29.475 3.76 0.5 25.2\n", 1),
+            ("\n10 3.76 1.67 25.2\n", 2),
+        ] {
+            match read_lens("test.dat", &format!("{text}{stop}")) {
+                Err(LoaderError::Parse { line: found, .. }) => assert_eq!(found, line, "{text}"),
+                Err(error) => panic!("{text}: expected a parse error, found \"{error}\""),
+                Ok(_) => panic!("{text}: was accepted"),
+            }
+        }
+    }
+
+    #[test]
+    fn needs_one_stop() {
+        let surface = "29.475 3.76 1.67 25.2\n";
+        let stop = "0 4.5 0 17.1\n";
+        for text in [surface.to_string(), format!("{stop}{surface}{stop}")] {
+            assert!(matches!(
+                read_lens("test.dat", &text),
+                Err(LoaderError::Format { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,52 @@
+pub mod curves;
+pub mod lens;
+pub mod ply;
+pub mod stl;
+pub mod volume;
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+
+/// A scene file that could not be read or made sense of.
+#[derive(Debug)]
+pub enum LoaderError {
+    Io {
+        path: String,
+        source: io::Error,
+    },
+    /// Malformed content, with the line it was found on.
+    Parse {
+        path: String,
+        line: usize,
+        message: String,
+    },
+    /// Malformed content with no line to point at, as in a binary file.
+    Format {
+        path: String,
+        message: String,
+    },
+}
+
+impl Display for LoaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            LoaderError::Io { path, source } => write!(f, "cannot read \"{path}\": {source}"),
+            LoaderError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{path}:{line}: {message}"),
+            LoaderError::Format { path, message } => write!(f, "{path}: {message}"),
+        }
+    }
+}
+
+impl Error for LoaderError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoaderError::Io { source, .. } => Some(source),
+            LoaderError::Parse { .. } | LoaderError::Format { .. } => None,
+        }
+    }
+}
@@ -0,0 +1,496 @@
+use crate::color::srgb_to_linear;
+use crate::hittable::mesh::Mesh;
+use crate::loader::LoaderError;
+use glam::{Vec2, Vec3};
+use std::fs;
+
+#[derive(Copy, Clone, PartialEq)]
+enum Encoding {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Copy, Clone)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Scalar> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    /// The largest value of an integer type, which stands for full intensity
+    /// in a colour. Floating point colours run up to 1.
+    fn full_scale(self) -> f64 {
+        match self {
+            Scalar::I8 => i8::MAX as f64,
+            Scalar::U8 => u8::MAX as f64,
+            Scalar::I16 => i16::MAX as f64,
+            Scalar::U16 => u16::MAX as f64,
+            Scalar::I32 => i32::MAX as f64,
+            Scalar::U32 => u32::MAX as f64,
+            Scalar::F32 | Scalar::F64 => 1.0,
+        }
+    }
+}
+
+enum Property {
+    Scalar(String, Scalar),
+    /// A count followed by that many items, as in a face's vertex indices.
+    List(String, Scalar, Scalar),
+}
+
+impl Property {
+    fn name(&self) -> &str {
+        match self {
+            Property::Scalar(name, _) | Property::List(name, ..) => name,
+        }
+    }
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Loads a triangle or polygon mesh from a PLY file, in ASCII or either
+/// binary byte order.
+///
+/// Vertex positions are required; texture coordinates (`u`/`v`, `s`/`t` or
+/// `texture_u`/`texture_v`) and colours (`red`, `green`, `blue`) are picked
+/// up when present, the colours taken to be sRGB. Other elements and
+/// properties are skipped.
+pub fn load_ply(file_path: &str) -> Result<Mesh, LoaderError> {
+    let data = fs::read(file_path).map_err(|source| LoaderError::Io {
+        path: file_path.to_string(),
+        source,
+    })?;
+    read_ply(file_path, &data)
+}
+
+/// Parses the contents of a PLY file, with `file_path` only for errors.
+fn read_ply(file_path: &str, data: &[u8]) -> Result<Mesh, LoaderError> {
+    let parse_error = |line: usize, message: String| LoaderError::Parse {
+        path: file_path.to_string(),
+        line,
+        message,
+    };
+
+    // The header is always text, ending at the line `end_header`.
+    let marker = b"end_header";
+    let header_end = data
+        .windows(marker.len())
+        .position(|window| window == marker)
+        .ok_or_else(|| parse_error(1, "no end_header line".to_string()))?;
+    let body_start = data[header_end..]
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map_or(data.len(), |offset| header_end + offset + 1);
+    let header = String::from_utf8_lossy(&data[..header_end]);
+
+    let mut lines = header.lines().enumerate().map(|(i, line)| (i + 1, line));
+    if lines.next().is_none_or(|(_, line)| line.trim() != "ply") {
+        return Err(parse_error(1, "not a PLY file".to_string()));
+    }
+
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for (number, line) in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            [] | ["comment", ..] | ["obj_info", ..] => {}
+            ["format", format, _version] => {
+                encoding = Some(match format {
+                    "ascii" => Encoding::Ascii,
+                    "binary_little_endian" => Encoding::BinaryLittleEndian,
+                    "binary_big_endian" => Encoding::BinaryBigEndian,
+                    _ => return Err(parse_error(number, format!("unknown format \"{format}\""))),
+                });
+            }
+            ["element", name, count] => {
+                let count = count
+                    .parse()
+                    .map_err(|_| parse_error(number, format!("bad element count \"{count}\"")))?;
+                elements.push(Element {
+                    name: name.to_string(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            ["property", ..] => {
+                let element = elements.last_mut().ok_or_else(|| {
+                    parse_error(number, "property before any element".to_string())
+                })?;
+                let scalar = |name: &str| {
+                    Scalar::parse(name)
+                        .ok_or_else(|| parse_error(number, format!("unknown type \"{name}\"")))
+                };
+                let property = match words[1..] {
+                    ["list", count, item, name] => {
+                        Property::List(name.to_string(), scalar(count)?, scalar(item)?)
+                    }
+                    [kind, name] => Property::Scalar(name.to_string(), scalar(kind)?),
+                    _ => return Err(parse_error(number, "malformed property".to_string())),
+                };
+                element.properties.push(property);
+            }
+            _ => return Err(parse_error(number, format!("unexpected \"{line}\""))),
+        }
+    }
+
+    let encoding = encoding.ok_or_else(|| parse_error(2, "no format line".to_string()))?;
+    let header_lines = header.lines().count() + 1;
+    let mut body = Body {
+        path: file_path,
+        data,
+        position: body_start,
+        encoding,
+        line: header_lines + 1,
+    };
+
+    let mut positions = Vec::new();
+    let mut uvs = Vec::new();
+    let mut colors = Vec::new();
+    let mut faces = Vec::new();
+    for element in &elements {
+        for index in 0..element.count {
+            let mut position = Vec3::ZERO;
+            let mut uv = Vec2::ZERO;
+            let mut color = Vec3::ZERO;
+            let (mut has_uv, mut has_color) = (false, false);
+
+            for property in &element.properties {
+                match property {
+                    Property::Scalar(name, scalar) => {
+                        let value = body.read(*scalar)?;
+                        match name.as_str() {
+                            "x" => position.x = value as f32,
+                            "y" => position.y = value as f32,
+                            "z" => position.z = value as f32,
+                            "u" | "s" | "texture_u" => (uv.x, has_uv) = (value as f32, true),
+                            "v" | "t" | "texture_v" => (uv.y, has_uv) = (value as f32, true),
+                            "red" | "green" | "blue" => {
+                                let channel = (value / scalar.full_scale()) as f32;
+                                let channel = srgb_to_linear(channel.clamp(0.0, 1.0));
+                                match name.as_str() {
+                                    "red" => color.x = channel,
+                                    "green" => color.y = channel,
+                                    _ => color.z = channel,
+                                }
+                                has_color = true;
+                            }
+                            _ => {}
+                        }
+                    }
+                    Property::List(name, count, item) => {
+                        // Not reserved up front, as a corrupt count could be
+                        // anything.
+                        let count = body.read(*count)? as usize;
+                        let mut items = Vec::new();
+                        for _ in 0..count {
+                            items.push(body.read(*item)?);
+                        }
+                        if element.name == "face" && is_vertex_indices(name) {
+                            if count < 3 {
+                                return Err(
+                                    body.error(format!("face {index} has only {count} corners"))
+                                );
+                            }
+                            if let Some(&bad) = items.iter().find(|&&i| i < 0.0 || i.fract() != 0.0)
+                            {
+                                return Err(
+                                    body.error(format!("face {index} has vertex index {bad}"))
+                                );
+                            }
+                            faces.push(items.into_iter().map(|i| i as usize).collect::<Vec<_>>());
+                        }
+                    }
+                }
+            }
+
+            if element.name == "vertex" {
+                positions.push(position);
+                if has_uv {
+                    uvs.push(uv);
+                }
+                if has_color {
+                    colors.push(color);
+                }
+            }
+            body.end_of_item();
+        }
+    }
+
+    let format_error = |message: String| LoaderError::Format {
+        path: file_path.to_string(),
+        message,
+    };
+    let Some(vertices) = elements.iter().find(|element| element.name == "vertex") else {
+        return Err(format_error("no vertex element".to_string()));
+    };
+    for axis in ["x", "y", "z"] {
+        if !vertices
+            .properties
+            .iter()
+            .any(|property| property.name() == axis)
+        {
+            return Err(format_error(format!(
+                "vertices have no \"{axis}\" property"
+            )));
+        }
+    }
+    if faces.is_empty() {
+        return Err(format_error("no faces".to_string()));
+    }
+    for (index, face) in faces.iter().enumerate() {
+        if let Some(&vertex) = face.iter().find(|&&i| i >= positions.len()) {
+            return Err(format_error(format!(
+                "face {index} refers to vertex {vertex}, but there are only {}",
+                positions.len()
+            )));
+        }
+    }
+
+    let vertex_count = positions.len();
+    let mut mesh = Mesh::new(positions, faces);
+    if uvs.len() == vertex_count {
+        mesh = mesh.with_uvs(uvs);
+    }
+    if colors.len() == vertex_count {
+        mesh = mesh.with_colors(colors);
+    }
+    Ok(mesh)
+}
+
+fn is_vertex_indices(name: &str) -> bool {
+    name == "vertex_indices" || name == "vertex_index"
+}
+
+/// Reads the values after the header one at a time, as text or binary.
+struct Body<'a> {
+    path: &'a str,
+    data: &'a [u8],
+    position: usize,
+    encoding: Encoding,
+    /// The line being read, in an ASCII file.
+    line: usize,
+}
+
+impl Body<'_> {
+    fn read(&mut self, scalar: Scalar) -> Result<f64, LoaderError> {
+        match self.encoding {
+            Encoding::Ascii => self.read_text(),
+            Encoding::BinaryLittleEndian | Encoding::BinaryBigEndian => {
+                let size = scalar.size();
+                let Some(bytes) = self.data.get(self.position..self.position + size) else {
+                    return Err(self.error("file ends early".to_string()));
+                };
+                self.position += size;
+
+                let little = self.encoding == Encoding::BinaryLittleEndian;
+                macro_rules! decode {
+                    ($t:ty) => {{
+                        let bytes = bytes.try_into().unwrap();
+                        (if little {
+                            <$t>::from_le_bytes(bytes)
+                        } else {
+                            <$t>::from_be_bytes(bytes)
+                        }) as f64
+                    }};
+                }
+                Ok(match scalar {
+                    Scalar::I8 => decode!(i8),
+                    Scalar::U8 => decode!(u8),
+                    Scalar::I16 => decode!(i16),
+                    Scalar::U16 => decode!(u16),
+                    Scalar::I32 => decode!(i32),
+                    Scalar::U32 => decode!(u32),
+                    Scalar::F32 => decode!(f32),
+                    Scalar::F64 => decode!(f64),
+                })
+            }
+        }
+    }
+
+    fn read_text(&mut self) -> Result<f64, LoaderError> {
+        // Each item sits on a line of its own, so don't run on to the next.
+        while let Some(&byte) = self.data.get(self.position)
+            && byte != b'\n'
+            && byte.is_ascii_whitespace()
+        {
+            self.position += 1;
+        }
+        let start = self.position;
+        while let Some(&byte) = self.data.get(self.position)
+            && !byte.is_ascii_whitespace()
+        {
+            self.position += 1;
+        }
+
+        let token = String::from_utf8_lossy(&self.data[start..self.position]);
+        if token.is_empty() {
+            return Err(self.error("too few values".to_string()));
+        }
+        token
+            .parse()
+            .map_err(|_| self.error(format!("expected a number, found \"{token}\"")))
+    }
+
+    /// In an ASCII file, moves on to the next line, skipping anything left on
+    /// this one.
+    fn end_of_item(&mut self) {
+        if self.encoding == Encoding::Ascii {
+            while let Some(&byte) = self.data.get(self.position) {
+                self.position += 1;
+                if byte == b'\n' {
+                    break;
+                }
+            }
+            self.line += 1;
+        }
+    }
+
+    fn error(&self, message: String) -> LoaderError {
+        match self.encoding {
+            Encoding::Ascii => LoaderError::Parse {
+                path: self.path.to_string(),
+                line: self.line,
+                message,
+            },
+            _ => LoaderError::Format {
+                path: self.path.to_string(),
+                message,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec2;
+
+    const VERTEX_PROPERTIES: &str = "element vertex 3
+property float x
+property float y
+property float z
+property float u
+property float v
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+    fn binary(format: &str, float: fn(f32) -> [u8; 4], int: impl Fn(i32) -> [u8; 4]) -> Vec<u8> {
+        let mut data = format!("ply\nformat {format} 1.0\n{VERTEX_PROPERTIES}").into_bytes();
+        for ([x, y], color) in [
+            ([0.0, 0.0], [255, 0, 0]),
+            ([1.0, 0.0], [0, 255, 0]),
+            ([0.0, 1.0], [0, 0, 255]),
+        ] {
+            for value in [x, y, 0.0, x, y] {
+                data.extend(float(value));
+            }
+            data.extend(color);
+        }
+        data.push(3);
+        for index in [0, 1, 2] {
+            data.extend(int(index));
+        }
+        data
+    }
+
+    fn assert_triangle(mesh: &Mesh) {
+        assert_eq!(mesh.positions, [Vec3::ZERO, Vec3::X, Vec3::Y]);
+        assert_eq!(mesh.faces, [vec![0, 1, 2]]);
+        assert_eq!(
+            mesh.uvs.as_deref(),
+            Some(&[Vec2::ZERO, vec2(1.0, 0.0), vec2(0.0, 1.0)][..])
+        );
+        assert_eq!(
+            mesh.colors.as_deref(),
+            Some(&[Vec3::X, Vec3::Y, Vec3::Z][..])
+        );
+    }
+
+    #[test]
+    fn reads_ascii() {
+        let text = format!(
+            "ply\nformat ascii 1.0\ncomment a triangle\n{VERTEX_PROPERTIES}\
+             0 0 0 0 0 255 0 0\n1 0 0 1 0 0 255 0\n0 1 0 0 1 0 0 255\n3 0 1 2\n"
+        );
+        assert_triangle(&read_ply("test.ply", text.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn reads_binary_in_either_byte_order() {
+        let little = binary("binary_little_endian", f32::to_le_bytes, i32::to_le_bytes);
+        assert_triangle(&read_ply("test.ply", &little).unwrap());
+        let big = binary("binary_big_endian", f32::to_be_bytes, i32::to_be_bytes);
+        assert_triangle(&read_ply("test.ply", &big).unwrap());
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let data = binary("binary_little_endian", f32::to_le_bytes, i32::to_le_bytes);
+        for length in [data.len() - 1, data.len() - 13, 20] {
+            assert!(read_ply("test.ply", &data[..length]).is_err());
+        }
+
+        let text = format!("ply\nformat ascii 1.0\n{VERTEX_PROPERTIES}0 0 0 0 0 255 0 0\n");
+        assert!(matches!(
+            read_ply("test.ply", text.as_bytes()),
+            Err(LoaderError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_faces_outside_the_vertices() {
+        for index in [3, -1] {
+            let data = binary("binary_big_endian", f32::to_be_bytes, |i| {
+                i32::to_be_bytes(if i == 2 { index } else { i })
+            });
+            assert!(read_ply("test.ply", &data).is_err(), "index {index}");
+        }
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        assert!(read_ply("test.ply", b"solid cube\nendsolid\n").is_err());
+        assert!(read_ply("test.ply", b"ply\nformat ascii 1.0\nend_header\n").is_err());
+        let text = format!("ply\nformat ascii 1.0\n{VERTEX_PROPERTIES}0 0 zero 0 0 255 0 0\n");
+        assert!(read_ply("test.ply", text.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,204 @@
+use crate::hittable::mesh::Mesh;
+use crate::loader::LoaderError;
+use glam::Vec3;
+use std::fs;
+
+const HEADER_SIZE: usize = 80;
+const TRIANGLE_SIZE: usize = 50;
+
+/// Loads a triangle mesh from an STL file, ASCII or binary.
+///
+/// STL stores every triangle with its own corners, so the mesh is faceted:
+/// neighbouring triangles don't share vertices and each is shaded flat, as
+/// suits machined parts. The normals in the file are ignored in favour of the
+/// winding of the corners.
+pub fn load_stl(file_path: &str) -> Result<Mesh, LoaderError> {
+    let data = fs::read(file_path).map_err(|source| LoaderError::Io {
+        path: file_path.to_string(),
+        source,
+    })?;
+    read_stl(file_path, &data)
+}
+
+/// Parses the contents of an STL file, with `file_path` only for errors.
+fn read_stl(file_path: &str, data: &[u8]) -> Result<Mesh, LoaderError> {
+    // Binary files may also start with "solid", so trust the size first.
+    let expected_size = binary_count(data).map(|count| HEADER_SIZE + 4 + count * TRIANGLE_SIZE);
+    let triangles = if expected_size == Some(data.len()) {
+        read_binary(data)
+    } else if data.trim_ascii_start().starts_with(b"solid") && !data.contains(&0) {
+        read_ascii(file_path, &String::from_utf8_lossy(data))?
+    } else if let Some(expected_size) = expected_size {
+        return Err(LoaderError::Format {
+            path: file_path.to_string(),
+            message: format!(
+                "binary STL should be {expected_size} bytes for its triangle count, but is {}",
+                data.len()
+            ),
+        });
+    } else {
+        return Err(LoaderError::Format {
+            path: file_path.to_string(),
+            message: "neither an ASCII nor a binary STL file".to_string(),
+        });
+    };
+
+    if triangles.iter().flatten().any(|corner| !corner.is_finite()) {
+        return Err(LoaderError::Format {
+            path: file_path.to_string(),
+            message: "a vertex is not a finite number".to_string(),
+        });
+    }
+    if triangles.is_empty() {
+        return Err(LoaderError::Format {
+            path: file_path.to_string(),
+            message: "no triangles".to_string(),
+        });
+    }
+
+    let positions = triangles.into_iter().flatten().collect::<Vec<_>>();
+    let faces = (0..positions.len() / 3)
+        .map(|i| vec![3 * i, 3 * i + 1, 3 * i + 2])
+        .collect();
+    Ok(Mesh::new(positions, faces))
+}
+
+/// The triangle count in a binary header, if the file is long enough to have
+/// one.
+fn binary_count(data: &[u8]) -> Option<usize> {
+    let count = data.get(HEADER_SIZE..HEADER_SIZE + 4)?;
+    Some(u32::from_le_bytes(count.try_into().unwrap()) as usize)
+}
+
+fn read_binary(data: &[u8]) -> Vec<[Vec3; 3]> {
+    data[HEADER_SIZE + 4..]
+        .chunks_exact(TRIANGLE_SIZE)
+        .map(|record| {
+            let float = |i: usize| f32::from_le_bytes(record[4 * i..4 * i + 4].try_into().unwrap());
+            let point = |i: usize| Vec3::new(float(i), float(i + 1), float(i + 2));
+            // The normal comes first and two attribute bytes last.
+            [point(3), point(6), point(9)]
+        })
+        .collect()
+}
+
+fn read_ascii(file_path: &str, text: &str) -> Result<Vec<[Vec3; 3]>, LoaderError> {
+    let mut triangles = Vec::new();
+    let mut corners = Vec::with_capacity(3);
+
+    for (index, line) in text.lines().enumerate() {
+        let error = |message: String| LoaderError::Parse {
+            path: file_path.to_string(),
+            line: index + 1,
+            message,
+        };
+
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["vertex", x, y, z] => {
+                let coordinate = |word: &str| {
+                    word.parse::<f32>()
+                        .map_err(|_| error(format!("expected a number, found \"{word}\"")))
+                };
+                if corners.len() == 3 {
+                    return Err(error("more than three vertices in a facet".to_string()));
+                }
+                corners.push(Vec3::new(coordinate(x)?, coordinate(y)?, coordinate(z)?));
+            }
+            ["vertex", ..] => return Err(error("a vertex needs three coordinates".to_string())),
+            ["endloop"] => {
+                let Ok(triangle) = <[Vec3; 3]>::try_from(corners.as_slice()) else {
+                    return Err(error(format!(
+                        "a facet needs three vertices, found {}",
+                        corners.len()
+                    )));
+                };
+                triangles.push(triangle);
+                corners.clear();
+            }
+            _ => {}
+        }
+    }
+
+    Ok(triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII: &str = "solid part
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 0 1 0
+  endloop
+endfacet
+endsolid part
+";
+
+    /// Two triangles, under a header that starts like an ASCII file, as
+    /// some exporters write.
+    fn binary(corners: [[f32; 9]; 2]) -> Vec<u8> {
+        let mut data = b"solid exported as binary".to_vec();
+        data.resize(HEADER_SIZE, b' ');
+        data.extend(2u32.to_le_bytes());
+        for corners in corners {
+            data.extend(
+                [0.0, 0.0, 1.0]
+                    .iter()
+                    .flat_map(|value: &f32| value.to_le_bytes()),
+            );
+            data.extend(corners.iter().flat_map(|value| value.to_le_bytes()));
+            data.extend([0, 0]);
+        }
+        data
+    }
+
+    const TRIANGLES: [[f32; 9]; 2] = [
+        [0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0],
+    ];
+
+    #[test]
+    fn reads_ascii() {
+        let mesh = read_stl("test.stl", ASCII.as_bytes()).unwrap();
+        assert_eq!(mesh.positions, [Vec3::ZERO, Vec3::X, Vec3::Y]);
+        assert_eq!(mesh.faces, [vec![0, 1, 2]]);
+    }
+
+    #[test]
+    fn reads_binary_even_when_it_starts_with_solid() {
+        let mesh = read_stl("test.stl", &binary(TRIANGLES)).unwrap();
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.positions[4], Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(mesh.faces, [vec![0, 1, 2], vec![3, 4, 5]]);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let data = binary(TRIANGLES);
+        for length in [data.len() - 1, HEADER_SIZE + 2, 0] {
+            assert!(read_stl("test.stl", &data[..length]).is_err());
+        }
+
+        let cut = ASCII.replace("    vertex 0 1 0\n", "");
+        assert!(matches!(
+            read_stl("test.stl", cut.as_bytes()),
+            Err(LoaderError::Parse { line: 6, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let garbled = ASCII.replace("vertex 1 0 0", "vertex 1 zero 0");
+        assert!(read_stl("test.stl", garbled.as_bytes()).is_err());
+
+        let mut corners = TRIANGLES;
+        corners[1][4] = f32::NAN;
+        assert!(read_stl("test.stl", &binary(corners)).is_err());
+
+        assert!(read_stl("test.stl", b"solid empty\nendsolid empty\n").is_err());
+    }
+}
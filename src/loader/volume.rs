@@ -0,0 +1,313 @@
+use crate::hittable::grid_medium::DensityGrid;
+use crate::loader::LoaderError;
+use glam::{Affine3A, Mat3A, Vec3, Vec3A};
+use std::collections::HashMap;
+use std::fs;
+
+const MAGIC: &[u8; 4] = b"SBVX";
+const VERSION: u32 = 1;
+/// The most bricks a grid may be divided into, each taking an index
+/// whether it is stored or not.
+const MAX_BRICKS: usize = 1 << 24;
+
+/// Voxel grids exported from a simulation, such as density and temperature,
+/// sharing one index space.
+pub struct Volume {
+    /// Takes the unit cube the grids span into world space, ready for
+    /// `GridMedium::transformed`.
+    pub transform: Affine3A,
+    pub grids: HashMap<String, DensityGrid>,
+}
+
+/// Loads a sparse brick volume, a simple binary format for the sparse grids
+/// simulation tools write out as OpenVDB, which a short script can convert.
+///
+/// All numbers are little endian, `u32` or `f32`:
+///
+/// ```text
+/// "SBVX"                 magic
+/// version                u32, 1
+/// resolution             3 × u32, voxels along x, y and z
+/// brick size             u32, voxels along each side of a brick
+/// transform              12 × f32, a 3 × 4 matrix row by row taking index
+///                        space, where voxel (i, j, k) spans [i, i + 1] and
+///                        so on, into world space
+/// grid count             u32
+/// each grid:
+///     name               u32 length, then that many bytes of UTF-8
+///     background         f32, the value wherever there is no brick
+///     brick count        u32
+///     each brick:
+///         origin         3 × u32, the voxel at its lowest corner, a
+///                        multiple of the brick size
+///         values         brick size³ × f32, x fastest, then y, then z
+/// ```
+///
+/// Bricks may run past the far edges of the grid; those values are unused.
+pub fn load_volume(file_path: &str) -> Result<Volume, LoaderError> {
+    let data = fs::read(file_path).map_err(|source| LoaderError::Io {
+        path: file_path.to_string(),
+        source,
+    })?;
+    read_volume(file_path, &data)
+}
+
+/// Parses the contents of a sparse brick volume, with `file_path` only for
+/// errors.
+fn read_volume(file_path: &str, data: &[u8]) -> Result<Volume, LoaderError> {
+    let mut reader = Reader {
+        path: file_path,
+        data,
+        position: 0,
+    };
+
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        return Err(reader.error("not a sparse brick volume".to_string()));
+    }
+    let version = reader.u32()?;
+    if version != VERSION {
+        return Err(reader.error(format!("unsupported version {version}")));
+    }
+
+    let resolution = [reader.index()?, reader.index()?, reader.index()?];
+    if resolution.contains(&0) {
+        return Err(reader.error("the resolution must be at least 1 on each axis".to_string()));
+    }
+    let brick_size = reader.index()?;
+    if brick_size == 0 {
+        return Err(reader.error("the brick size must be at least 1".to_string()));
+    }
+    let bricks = resolution.iter().try_fold(1usize, |count, &n| {
+        count.checked_mul(n.div_ceil(brick_size))
+    });
+    if bricks.is_none_or(|bricks| bricks > MAX_BRICKS) {
+        return Err(reader.error(format!(
+            "a {resolution:?} grid in bricks of {brick_size} is too large"
+        )));
+    }
+
+    let mut rows = [[0.0; 4]; 3];
+    for row in &mut rows {
+        for value in row.iter_mut() {
+            *value = reader.f32()?;
+        }
+    }
+    let column = |i: usize| Vec3A::new(rows[0][i], rows[1][i], rows[2][i]);
+    let index_to_world = Affine3A {
+        matrix3: Mat3A::from_cols(column(0), column(1), column(2)),
+        translation: column(3),
+    };
+    if !index_to_world.is_finite() || index_to_world.matrix3.determinant().abs() < f32::EPSILON {
+        return Err(reader.error("the transform cannot be inverted".to_string()));
+    }
+    let size = Vec3::new(
+        resolution[0] as f32,
+        resolution[1] as f32,
+        resolution[2] as f32,
+    );
+    let transform = index_to_world * Affine3A::from_scale(size);
+
+    let Some(brick_bytes) = brick_size
+        .checked_pow(3)
+        .and_then(|volume| volume.checked_mul(4))
+    else {
+        return Err(reader.error(format!("a brick size of {brick_size} is too large")));
+    };
+    let valid = |value: f32| value.is_finite() && value >= 0.0;
+
+    let grid_count = reader.u32()?;
+    let mut grids = HashMap::new();
+    for _ in 0..grid_count {
+        let name_length = reader.index()?;
+        let name = String::from_utf8(reader.bytes(name_length)?.to_vec())
+            .map_err(|_| reader.error("a grid name is not UTF-8".to_string()))?;
+        let background = reader.f32()?;
+        if !valid(background) {
+            return Err(reader.error(format!("grid \"{name}\" has an invalid background")));
+        }
+
+        let brick_count = reader.u32()?;
+        let mut bricks = Vec::new();
+        for _ in 0..brick_count {
+            let origin = [reader.index()?, reader.index()?, reader.index()?];
+            if origin.iter().any(|&c| c % brick_size != 0)
+                || origin.iter().zip(resolution).any(|(&c, n)| c >= n)
+            {
+                return Err(reader.error(format!(
+                    "grid \"{name}\" has a brick at {origin:?}, off the brick lattice or the grid"
+                )));
+            }
+
+            let values: Vec<f32> = reader
+                .bytes(brick_bytes)?
+                .chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+                .collect();
+            if !values.iter().all(|&value| valid(value)) {
+                return Err(reader.error(format!(
+                    "grid \"{name}\" has a negative or invalid value in the brick at {origin:?}"
+                )));
+            }
+            bricks.push((origin, values));
+        }
+
+        let grid = DensityGrid::from_bricks(resolution, brick_size, background, bricks);
+        if grids.insert(name.clone(), grid).is_some() {
+            return Err(reader.error(format!("more than one grid is called \"{name}\"")));
+        }
+    }
+
+    Ok(Volume { transform, grids })
+}
+
+struct Reader<'a> {
+    path: &'a str,
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], LoaderError> {
+        let Some(bytes) = self.data[self.position..].get(..count) else {
+            return Err(self.error("file ends early".to_string()));
+        };
+        self.position += count;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, LoaderError> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn index(&mut self) -> Result<usize, LoaderError> {
+        Ok(self.u32()? as usize)
+    }
+
+    fn f32(&mut self) -> Result<f32, LoaderError> {
+        Ok(f32::from_le_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn error(&self, message: String) -> LoaderError {
+        LoaderError::Format {
+            path: self.path.to_string(),
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec3;
+
+    /// Writes a file in the format `load_volume` reads.
+    fn write(
+        resolution: [u32; 3],
+        brick_size: u32,
+        transform: [f32; 12],
+        grids: &[(&str, f32, &[([u32; 3], Vec<f32>)])],
+    ) -> Vec<u8> {
+        let mut data = MAGIC.to_vec();
+        let u32s = |data: &mut Vec<u8>, values: &[u32]| {
+            for value in values {
+                data.extend(value.to_le_bytes());
+            }
+        };
+        u32s(&mut data, &[VERSION]);
+        u32s(&mut data, &resolution);
+        u32s(&mut data, &[brick_size]);
+        data.extend(transform.iter().flat_map(|value| value.to_le_bytes()));
+        u32s(&mut data, &[grids.len() as u32]);
+        for (name, background, bricks) in grids {
+            u32s(&mut data, &[name.len() as u32]);
+            data.extend(name.as_bytes());
+            data.extend(background.to_le_bytes());
+            u32s(&mut data, &[bricks.len() as u32]);
+            for (origin, values) in *bricks {
+                u32s(&mut data, origin);
+                data.extend(values.iter().flat_map(|value| value.to_le_bytes()));
+            }
+        }
+        data
+    }
+
+    const IDENTITY: [f32; 12] = [
+        1.0, 0.0, 0.0, 0.0, //
+        0.0, 1.0, 0.0, 0.0, //
+        0.0, 0.0, 1.0, 0.0,
+    ];
+
+    /// A 4 × 2 × 2 grid in bricks of two, with a brick of ramps at the
+    /// origin and one of constant 5 at the far end.
+    fn two_bricks() -> Vec<u8> {
+        let ramp = (0..8).map(|i| i as f32).collect();
+        let bricks = [([0, 0, 0], ramp), ([2, 0, 0], vec![5.0; 8])];
+        write([4, 2, 2], 2, IDENTITY, &[("density", 0.5, &bricks)])
+    }
+
+    /// The value stored for voxel (x, y, z), found at its centre.
+    fn voxel(grid: &DensityGrid, resolution: Vec3, x: usize, y: usize, z: usize) -> f32 {
+        grid.lookup((vec3(x as f32, y as f32, z as f32) + 0.5) / resolution)
+    }
+
+    #[test]
+    fn round_trips_bricks_and_background() {
+        let data = two_bricks();
+        let volume = read_volume("test.sbvx", &data).unwrap();
+        let grid = &volume.grids["density"];
+        let resolution = vec3(4.0, 2.0, 2.0);
+
+        assert_eq!(voxel(grid, resolution, 0, 0, 0), 0.0);
+        assert_eq!(voxel(grid, resolution, 1, 0, 0), 1.0);
+        assert_eq!(voxel(grid, resolution, 0, 1, 0), 2.0);
+        assert_eq!(voxel(grid, resolution, 1, 1, 1), 7.0);
+        assert_eq!(voxel(grid, resolution, 3, 1, 1), 5.0);
+        assert_eq!(grid.max_value(), 7.0);
+
+        // The unit cube spans the whole grid in index space.
+        let corner = volume.transform.transform_point3(Vec3::ONE);
+        assert!((corner - resolution).length() < 1e-6);
+    }
+
+    #[test]
+    fn missing_bricks_are_background() {
+        let bricks = [([2, 0, 0], vec![5.0; 8])];
+        let data = write([4, 2, 2], 2, IDENTITY, &[("density", 0.5, &bricks)]);
+        let volume = read_volume("test.sbvx", &data).unwrap();
+        let grid = &volume.grids["density"];
+        assert_eq!(voxel(grid, vec3(4.0, 2.0, 2.0), 1, 1, 0), 0.5);
+    }
+
+    #[test]
+    fn rejects_truncated_files() {
+        let data = two_bricks();
+        for length in [0, 3, 20, data.len() - 1] {
+            assert!(matches!(
+                read_volume("test.sbvx", &data[..length]),
+                Err(LoaderError::Format { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_grids_too_large_to_index() {
+        for resolution in [[u32::MAX; 3], [65536, 65536, 65536]] {
+            let data = write(resolution, 1, IDENTITY, &[]);
+            assert!(matches!(
+                read_volume("test.sbvx", &data),
+                Err(LoaderError::Format { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_bricks_off_the_grid() {
+        let bricks = [([4, 0, 0], vec![1.0; 8])];
+        let data = write([4, 2, 2], 2, IDENTITY, &[("density", 0.0, &bricks)]);
+        assert!(read_volume("test.sbvx", &data).is_err());
+
+        let bricks = [([1, 0, 0], vec![1.0; 8])];
+        let data = write([4, 2, 2], 2, IDENTITY, &[("density", 0.0, &bricks)]);
+        assert!(read_volume("test.sbvx", &data).is_err());
+    }
+}
@@ -1,38 +1,89 @@
+mod aperture;
 mod buffer;
 mod camera;
 mod color;
 mod hittable;
 mod image;
 mod interval;
+mod lens;
+mod loader;
 mod material;
 mod perlin;
+mod polynomial;
+mod projection;
 mod ray;
+mod stereo;
 mod utils;
 mod window;
+mod worley;
 
+use crate::aperture::{Aperture, ImageAperture, Polygonal};
 use crate::buffer::{Buffer, DrawBuffer};
 use crate::camera::{Camera, CameraProperties};
 use crate::color::Color;
 use crate::hittable::Hittable;
+use crate::hittable::aabb::Aabb;
 use crate::hittable::bvh::BvhNode;
+use crate::hittable::cone::Cone;
 use crate::hittable::constant_medium::ConstantMedium;
+use crate::hittable::csg::Csg;
+use crate::hittable::curve::{Curve, CurveShape};
+use crate::hittable::cutout::{AlphaTest, Cutout};
+use crate::hittable::cylinder::Cylinder;
+use crate::hittable::disk::Disk;
+use crate::hittable::grid_medium::{DensityGrid, GridMedium};
+use crate::hittable::heightfield::Heightfield;
 use crate::hittable::hittable_list::HittableList;
+use crate::hittable::mesh::Mesh;
+use crate::hittable::normal_map::{BumpMap, NormalMap};
+use crate::hittable::plane::Plane;
 use crate::hittable::quad::{Quad, cuboid};
+use crate::hittable::sdf::{Sdf, SdfHittable};
 use crate::hittable::sphere::Sphere;
+use crate::hittable::torus::Torus;
 use crate::hittable::transform::{RotateY, Translate};
+use crate::hittable::triangle::Triangle;
 use crate::image::ppm_image::PpmImage;
+use crate::interval::Interval;
+use crate::lens::LensSystem;
+use crate::loader::curves::load_curves;
+use crate::loader::lens::load_lens;
+use crate::loader::ply::load_ply;
+use crate::loader::stl::load_stl;
+use crate::loader::volume::{Volume, load_volume};
 use crate::material::Material;
 use crate::material::dielectric::Dielectric;
 use crate::material::diffuse_light::DiffuseLight;
+use crate::material::henyey_greenstein::HenyeyGreenstein;
 use crate::material::lambertian::Lambertian;
 use crate::material::metal::Metal;
+use crate::material::solid_color::SolidColor;
 use crate::material::texture::Texture;
 use crate::material::texture::checker_texture::CheckerTexture;
-use crate::material::texture::image_texture::ImageTexture;
-use crate::material::texture::noise_texture::NoiseTexture;
+use crate::material::texture::color_ramp::ColorRamp;
+use crate::material::texture::combinators::{
+    AddTexture, MixTexture, MultiplyTexture, PointTransformTexture, RampTexture, UvTransformTexture,
+};
+use crate::material::texture::image_texture::{
+    Filter, ImageTexture, MipFilter, TextureSampler, UvTransform, WrapMode,
+};
+use crate::material::texture::marble_texture::MarbleTexture;
+use crate::material::texture::noise_texture::{
+    FbmTexture, NoiseTexture, RidgedTexture, SimplexTexture,
+};
+use crate::material::texture::pattern_texture::{GradientTexture, StripeTexture};
+use crate::material::texture::texture_cache::TextureCache;
+use crate::material::texture::vertex_color_texture::VertexColorTexture;
+use crate::material::texture::wood_texture::WoodTexture;
+use crate::material::texture::worley_texture::{WorleyFeature, WorleyTexture};
+use crate::material::thin_film::ThinFilm;
+use crate::perlin::Perlin;
+use crate::projection::{Cubemap, Equirectangular, Fisheye, Orthographic, Perspective, Projection};
+use crate::stereo::{Stereo, StereoLayout};
 use crate::utils::{random_vector, random_vector_range};
 use crate::window::{SoftbufferWindow, WindowProperties};
-use glam::{Vec3, vec3};
+use glam::{Affine3A, IVec3, Quat, Vec3, vec2, vec3};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::thread;
 use winit::event::WindowEvent;
@@ -48,6 +99,27 @@ fn main() {
         7 => cornell_box(),
         8 => cornell_smoke(),
         9 => final_scene(400, Some(10_000), 40),
+        10 => thin_films(),
+        11 => bump_mapping(),
+        12 => cutouts(),
+        13 => emissive_screen(),
+        14 => tiled_textures(),
+        15 => procedural_textures(),
+        16 => primitives(),
+        17 => sdf_shapes(),
+        18 => csg(),
+        19 => terrain(),
+        20 => curves(),
+        21 => subdivision(),
+        22 => scanned_model(),
+        23 => cloud(),
+        24 => phase_functions(),
+        25 => glass_of_wine(),
+        26 => fireball(),
+        27 => simulation_volume(),
+        28 => projections(),
+        29 => bokeh(),
+        30 => stereo(),
         _ => final_scene(400, Some(250), 4),
     };
 
@@ -99,11 +171,14 @@ fn main() {
 fn bouncing_spheres() -> (HittableList, Camera) {
     let mut world: HittableList = HittableList::new();
 
-    let checker: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
-        0.32,
-        &Color::new(Vec3::new(0.2, 0.3, 0.1)),
-        &Color::new(Vec3::new(0.9, 0.9, 0.9)),
-    ));
+    let checker: Box<dyn Texture> = Box::new(
+        CheckerTexture::from_color(
+            0.32,
+            &Color::new(Vec3::new(0.2, 0.3, 0.1)),
+            &Color::new(Vec3::new(0.9, 0.9, 0.9)),
+        )
+        .filtered(),
+    );
     let ground_material = Arc::new(Lambertian::from(checker));
     world.add(Arc::new(Sphere::new_stationary(
         vec3(0.0, -1000.0, 0.0),
@@ -268,7 +343,7 @@ fn earth() -> (HittableList, Camera) {
 fn perlin_spheres() -> (HittableList, Camera) {
     let mut world = HittableList::new();
 
-    let perlin_texture: Box<dyn Texture> = Box::new(NoiseTexture::new(4.0));
+    let perlin_texture: Box<dyn Texture> = Box::new(NoiseTexture::new(4.0).with_seed(1));
     let perlin_surface = Arc::new(Lambertian::from(perlin_texture));
     world.add(Arc::new(Sphere::new_stationary(
         vec3(0.0, -1_000.0, 0.0),
@@ -360,7 +435,7 @@ fn quads() -> (HittableList, Camera) {
 fn simple_light() -> (HittableList, Camera) {
     let mut world = HittableList::new();
 
-    let perlin_texture: Box<dyn Texture> = Box::new(NoiseTexture::new(4.0));
+    let perlin_texture: Box<dyn Texture> = Box::new(NoiseTexture::new(4.0).with_seed(1));
     let perlin_surface = Arc::new(Lambertian::from(perlin_texture));
     world.add(Arc::new(Sphere::new_stationary(
         vec3(0.0, -1_000.0, 0.0),
@@ -711,3 +786,1638 @@ fn final_scene(
 
     (world, camera)
 }
+
+fn thin_films() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let checker: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
+        0.5,
+        &Color::new(Vec3::new(0.1, 0.1, 0.1)),
+        &Color::new(Vec3::new(0.9, 0.9, 0.9)),
+    ));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(0.0, -1000.0, 0.0),
+        1000.0,
+        Arc::new(Lambertian::from(checker)),
+    )));
+
+    let soap_film = ThinFilm::new(
+        Box::new(NoiseTexture::new(2.0)),
+        Interval::new(200.0, 800.0),
+        1.33,
+    );
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(0.0, 1.0, 0.0),
+        1.0,
+        Arc::new(Dielectric::new(1.0).with_thin_film(soap_film)),
+    )));
+
+    let anodised =
+        Metal::new(vec3(0.6, 0.6, 0.6), 0.05).with_thin_film(ThinFilm::uniform(250.0, 2.2));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(-2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(anodised),
+    )));
+
+    let coated_glass = Dielectric::new(1.5).with_thin_film(ThinFilm::uniform(450.0, 1.38));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(2.2, 1.0, 0.0),
+        1.0,
+        Arc::new(coated_glass),
+    )));
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 2.0, 9.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn bump_mapping() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let ground = Arc::new(Lambertian::from(Vec3::new(0.5, 0.5, 0.5)));
+    let floor: Arc<dyn Hittable> = Arc::new(Quad::new(
+        vec3(-10.0, 0.0, 10.0),
+        vec3(20.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -20.0),
+        ground,
+    ));
+    world.add(Arc::new(BumpMap::new(
+        floor,
+        Box::new(NoiseTexture::new(8.0)),
+        0.02,
+    )));
+
+    let orange = Arc::new(Lambertian::from(Vec3::new(0.8, 0.4, 0.1)));
+    let sphere: Arc<dyn Hittable> =
+        Arc::new(Sphere::new_stationary(vec3(-1.2, 1.0, 0.0), 1.0, orange));
+    world.add(Arc::new(BumpMap::new(
+        sphere,
+        Box::new(NoiseTexture::new(4.0)),
+        0.002,
+    )));
+
+    let steel = Arc::new(Metal::new(vec3(0.8, 0.8, 0.8), 0.0));
+    let sphere: Arc<dyn Hittable> =
+        Arc::new(Sphere::new_stationary(vec3(1.2, 1.0, 0.0), 1.0, steel));
+    world.add(Arc::new(BumpMap::new(
+        sphere,
+        Box::new(NoiseTexture::new(12.0)),
+        0.001,
+    )));
+
+    // Bevelled tiles from a normal map, in front of the two.
+    let blue = Arc::new(Lambertian::from(Vec3::new(0.2, 0.3, 0.7)));
+    let sphere: Arc<dyn Hittable> =
+        Arc::new(Sphere::new_stationary(vec3(0.0, 0.5, 1.8), 0.5, blue));
+    world.add(Arc::new(NormalMap::new(
+        sphere,
+        Box::new(ImageTexture::new_linear("normal_map.png")),
+        1.0,
+    )));
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 2.5, 8.0);
+    properties.look_at = vec3(0.0, 0.8, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn cutouts() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let ground = Arc::new(Lambertian::from(Vec3::new(0.48, 0.83, 0.53)));
+    world.add(Arc::new(Quad::new(
+        vec3(-10.0, 0.0, 10.0),
+        vec3(20.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -20.0),
+        ground,
+    )));
+
+    let fence: Arc<dyn Hittable> = Arc::new(Quad::new(
+        vec3(-3.0, 0.0, 1.0),
+        vec3(6.0, 0.0, 0.0),
+        vec3(0.0, 2.0, 0.0),
+        Arc::new(Lambertian::from(Vec3::new(0.6, 0.4, 0.2))),
+    ));
+    let slats: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
+        0.25,
+        &Color::new(Vec3::ZERO),
+        &Color::new(Vec3::ONE),
+    ));
+    world.add(Arc::new(Cutout::from_luminance(
+        fence,
+        slats,
+        AlphaTest::Threshold(0.5),
+    )));
+
+    let sphere: Arc<dyn Hittable> = Arc::new(Sphere::new_stationary(
+        vec3(0.0, 1.0, -1.5),
+        1.0,
+        Arc::new(Lambertian::from(Vec3::new(0.8, 0.2, 0.2))),
+    ));
+    world.add(Arc::new(Cutout::from_luminance(
+        sphere,
+        Box::new(NoiseTexture::new(6.0)),
+        AlphaTest::Stochastic,
+    )));
+
+    // Leaves cut out by the transparency of the image that colours them.
+    let leaf = Arc::new(ImageTexture::new("leaf.png"));
+    let leaf_material = Arc::new(Lambertian::from(Box::new(leaf.clone()) as Box<dyn Texture>));
+    for (corner, across, along) in [
+        (
+            vec3(-2.6, 0.01, 2.0),
+            vec3(0.8, 0.0, 0.1),
+            vec3(-0.1, 0.0, -0.8),
+        ),
+        (
+            vec3(1.8, 0.01, 2.6),
+            vec3(0.7, 0.0, -0.4),
+            vec3(0.4, 0.0, 0.7),
+        ),
+        (
+            vec3(2.2, 1.2, 1.6),
+            vec3(0.6, -0.2, 0.5),
+            vec3(-0.3, 0.7, 0.1),
+        ),
+    ] {
+        let quad: Arc<dyn Hittable> =
+            Arc::new(Quad::new(corner, across, along, leaf_material.clone()));
+        world.add(Arc::new(Cutout::new(
+            quad,
+            Box::new(leaf.clone()),
+            AlphaTest::Threshold(0.5),
+        )));
+    }
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 35.0;
+    properties.look_from = vec3(0.0, 2.0, 8.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn emissive_screen() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let white = Arc::new(Lambertian::from(Vec3::new(0.73, 0.73, 0.73)));
+    world.add(Arc::new(Quad::new(
+        vec3(-5.0, 0.0, 5.0),
+        vec3(10.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -10.0),
+        white.clone(),
+    )));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(1.5, 0.6, 1.0),
+        0.6,
+        white,
+    )));
+
+    let screen_texture: Box<dyn Texture> = Box::new(ImageTexture::new("earthmap.jpg"));
+    let screen = DiffuseLight::from(screen_texture)
+        .with_intensity(2.0)
+        .one_sided();
+    world.add(Arc::new(Quad::new(
+        vec3(-2.0, 0.5, -1.0),
+        vec3(4.0, 0.0, 0.0),
+        vec3(0.0, 2.0, 0.0),
+        Arc::new(screen),
+    )));
+
+    let lamp = DiffuseLight::blackbody(2700.0)
+        .one_sided()
+        .with_power(20.0, 0.25);
+    world.add(Arc::new(Quad::new(
+        vec3(-2.5, 3.0, 2.0),
+        vec3(0.5, 0.0, 0.0),
+        vec3(0.0, 0.0, 0.5),
+        Arc::new(lamp),
+    )));
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(100);
+    properties.max_depth = 50;
+    properties.background = Vec3::ZERO;
+    properties.v_fov = 40.0;
+    properties.look_from = vec3(0.0, 2.0, 7.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn tiled_textures() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+    let textures = TextureCache::lenient();
+    // Decoded up front, in parallel with any others listed.
+    textures.preload(&["earthmap.jpg"]).unwrap();
+
+    let tiled = TextureSampler {
+        filter: Filter::Bilinear,
+        mip_filter: MipFilter::Ewa,
+        wrap: WrapMode::Mirror,
+        transform: UvTransform {
+            scale: vec2(6.0, 6.0),
+            rotation: 30.0,
+            offset: vec2(0.25, 0.0),
+        },
+    };
+    let floor_texture: Box<dyn Texture> =
+        Box::new(textures.get_with("earthmap.jpg", tiled, None).unwrap());
+    world.add(Arc::new(Quad::new(
+        vec3(-10.0, 0.0, 10.0),
+        vec3(20.0, 0.0, 0.0),
+        vec3(0.0, 0.0, -20.0),
+        Arc::new(Lambertian::from(floor_texture)),
+    )));
+
+    let nearest = TextureSampler {
+        filter: Filter::Nearest,
+        mip_filter: MipFilter::None,
+        wrap: WrapMode::Repeat,
+        transform: UvTransform::default(),
+    };
+    let bicubic = TextureSampler {
+        filter: Filter::Bicubic,
+        mip_filter: MipFilter::Trilinear,
+        wrap: WrapMode::Clamp,
+        transform: UvTransform::default(),
+    };
+    for (x, sampler) in [(-1.2, nearest), (1.2, bicubic)] {
+        let texture: Box<dyn Texture> =
+            Box::new(textures.get_with("earthmap.jpg", sampler, None).unwrap());
+        world.add(Arc::new(Sphere::new_stationary(
+            vec3(x, 1.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::from(texture)),
+        )));
+    }
+
+    // The same decoded image once more, spun half a turn by transforming the
+    // coordinates it is looked up with.
+    let spun: Box<dyn Texture> = Box::new(UvTransformTexture::new(
+        Box::new(textures.get("earthmap.jpg").unwrap()),
+        UvTransform {
+            offset: vec2(0.5, 0.0),
+            ..UvTransform::default()
+        },
+    ));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(0.0, 0.5, 1.8),
+        0.5,
+        Arc::new(Lambertian::from(spun)),
+    )));
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(15);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 35.0;
+    properties.look_from = vec3(0.0, 2.5, 8.0);
+    properties.look_at = vec3(0.0, 0.8, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn procedural_textures() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    // Stripes fading out towards the back of the floor.
+    let stripes: Box<dyn Texture> = Box::new(StripeTexture::new(
+        vec3(1.0, 0.0, 1.0),
+        0.5,
+        Box::new(SolidColor::new(&Color::new(vec3(0.8, 0.8, 0.8)))),
+        Box::new(SolidColor::new(&Color::new(vec3(0.3, 0.3, 0.35)))),
+    ));
+    let fade: Box<dyn Texture> = Box::new(GradientTexture::new(
+        vec3(0.0, 0.0, 2.0),
+        vec3(0.0, 0.0, -8.0),
+        ColorRamp::grayscale(),
+    ));
+    let floor_texture: Box<dyn Texture> = Box::new(MixTexture::new(
+        stripes,
+        Box::new(SolidColor::new(&Color::new(vec3(0.5, 0.5, 0.5)))),
+        fade,
+    ));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(0.0, -1_000.0, 0.0),
+        1_000.0,
+        Arc::new(Lambertian::from(floor_texture)),
+    )));
+
+    let clouds: Box<dyn Texture> = Box::new(RampTexture::new(
+        Box::new(
+            FbmTexture::new(3.0, 6)
+                .with_seed(1)
+                .with_lacunarity(2.2)
+                .with_gain(0.55),
+        ),
+        ColorRamp::new(vec![(0.3, vec3(0.1, 0.3, 0.8)), (0.7, vec3(1.0, 1.0, 1.0))]),
+    ));
+    let mountains: Box<dyn Texture> = Box::new(RampTexture::new(
+        Box::new(
+            RidgedTexture::new(2.0, 6)
+                .with_seed(2)
+                .with_lacunarity(2.1)
+                .with_gain(2.2)
+                .with_offset(0.9),
+        ),
+        ColorRamp::new(vec![
+            (0.1, vec3(0.15, 0.3, 0.1)),
+            (0.5, vec3(0.4, 0.35, 0.3)),
+            (0.8, vec3(0.95, 0.95, 0.95)),
+        ]),
+    ));
+    let cells: Box<dyn Texture> = Box::new(RampTexture::new(
+        Box::new(WorleyTexture::new(4.0, WorleyFeature::F2MinusF1).with_seed(3)),
+        ColorRamp::new(vec![
+            (0.0, vec3(0.05, 0.05, 0.05)),
+            (0.1, vec3(0.7, 0.2, 0.1)),
+        ]),
+    ));
+    // Tipped over so the rings show on the side facing the camera.
+    let wood: Box<dyn Texture> = Box::new(PointTransformTexture::new(
+        Box::new(WoodTexture::new(8.0, 0.4).with_seed(4)),
+        Affine3A::from_rotation_x(std::f32::consts::FRAC_PI_2),
+    ));
+    let marble: Box<dyn Texture> = Box::new(
+        MarbleTexture::new(
+            4.0,
+            8.0,
+            ColorRamp::new(vec![
+                (0.0, vec3(0.9, 0.9, 0.88)),
+                (0.8, vec3(0.75, 0.75, 0.75)),
+                (1.0, vec3(0.2, 0.25, 0.3)),
+            ]),
+        )
+        .with_seed(5),
+    );
+
+    for (i, texture) in [clouds, mountains, cells, wood, marble]
+        .into_iter()
+        .enumerate()
+    {
+        world.add(Arc::new(Sphere::new_stationary(
+            vec3(2.4 * (i as f32 - 2.0), 1.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::from(texture)),
+        )));
+    }
+
+    // Behind, simplex noise, fbm that tiles every two units, spots and
+    // pebbles built up from cellular noise, and the same turbulence held
+    // still and boiling while the shutter is open.
+    let lava: Box<dyn Texture> = Box::new(RampTexture::new(
+        Box::new(SimplexTexture::new(3.0).with_seed(4)),
+        ColorRamp::new(vec![
+            (0.3, vec3(0.1, 0.02, 0.0)),
+            (0.6, vec3(0.9, 0.3, 0.05)),
+            (0.8, vec3(1.0, 0.9, 0.3)),
+        ]),
+    ));
+    let tiled_clouds: Box<dyn Texture> = Box::new(RampTexture::new(
+        Box::new(
+            FbmTexture::new(3.0, 6)
+                .with_seed(5)
+                .with_period(IVec3::splat(6)),
+        ),
+        ColorRamp::new(vec![(0.3, vec3(0.1, 0.3, 0.8)), (0.7, vec3(1.0, 1.0, 1.0))]),
+    ));
+    let spots: Box<dyn Texture> = Box::new(MultiplyTexture::new(
+        Box::new(SolidColor::new(&Color::new(vec3(0.85, 0.6, 0.3)))),
+        Box::new(RampTexture::new(
+            Box::new(WorleyTexture::new(5.0, WorleyFeature::F1).with_seed(7)),
+            ColorRamp::new(vec![(0.25, vec3(0.1, 0.1, 0.1)), (0.35, Vec3::ONE)]),
+        )),
+    ));
+    let pebbles: Box<dyn Texture> = Box::new(AddTexture::new(
+        Box::new(RampTexture::new(
+            Box::new(WorleyTexture::new(4.0, WorleyFeature::F2).with_seed(8)),
+            ColorRamp::new(vec![
+                (0.3, vec3(0.5, 0.45, 0.4)),
+                (1.0, vec3(0.1, 0.1, 0.1)),
+            ]),
+        )),
+        // Grit over the stones.
+        Box::new(RampTexture::new(
+            Box::new(FbmTexture::new(40.0, 3).with_seed(9)),
+            ColorRamp::new(vec![(0.4, Vec3::ZERO), (1.0, vec3(0.15, 0.15, 0.15))]),
+        )),
+    ));
+    let still: Box<dyn Texture> = Box::new(NoiseTexture::new(4.0).with_seed(6));
+    let boiling: Box<dyn Texture> = Box::new(NoiseTexture::new(4.0).with_seed(6).animated(2.0));
+
+    for (i, texture) in [lava, tiled_clouds, spots, pebbles, still, boiling]
+        .into_iter()
+        .enumerate()
+    {
+        world.add(Arc::new(Sphere::new_stationary(
+            vec3(2.4 * (i as f32 - 2.5), 1.0, -3.0),
+            1.0,
+            Arc::new(Lambertian::from(texture)),
+        )));
+    }
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(15);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 35.0;
+    properties.look_from = vec3(0.0, 6.0, 12.0);
+    properties.look_at = vec3(0.0, 0.5, -1.5);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn primitives() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let floor_texture: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
+        1.0,
+        &Color::new(vec3(0.2, 0.3, 0.1)),
+        &Color::new(vec3(0.9, 0.9, 0.9)),
+    ));
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(floor_texture)),
+    )));
+
+    world.add(Arc::new(
+        Cylinder::new(
+            vec3(-4.0, 0.0, 0.0),
+            vec3(0.0, 2.0, 0.0),
+            0.8,
+            Arc::new(Lambertian::from(vec3(0.8, 0.3, 0.2))),
+        )
+        .capped(),
+    ));
+    world.add(Arc::new(
+        Cone::new(
+            vec3(-1.5, 0.0, 0.0),
+            vec3(0.0, 2.2, 0.0),
+            0.9,
+            Arc::new(Metal::new(vec3(0.8, 0.7, 0.3), 0.1)),
+        )
+        .capped(),
+    ));
+    world.add(Arc::new(Torus::new(
+        vec3(1.2, 1.0, 0.0),
+        vec3(0.0, 0.3, 1.0),
+        0.8,
+        0.3,
+        Arc::new(Dielectric::new(1.5)),
+    )));
+    world.add(Arc::new(Disk::new(
+        vec3(3.8, 1.2, -0.5),
+        vec3(-0.3, 0.2, 1.0),
+        1.0,
+        Arc::new(Lambertian::from(vec3(0.2, 0.4, 0.8))),
+    )));
+    world.add(Arc::new(Triangle::new(
+        vec3(-2.5, 2.8, -2.0),
+        vec3(2.5, 2.8, -2.0),
+        vec3(0.0, 4.5, -2.5),
+        Arc::new(DiffuseLight::from(vec3(4.0, 4.0, 4.0))),
+    )));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 4.0, 14.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn sdf_shapes() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // A sphere melted into a rounded box.
+    let blob = Sdf::RoundedBox(vec3(0.7, 0.7, 0.7), 0.15)
+        .smooth_union(Sdf::Sphere(0.6).translate(vec3(0.0, 0.9, 0.0)), 0.3)
+        .translate(vec3(-3.0, 0.7, 0.0));
+    world.add(Arc::new(SdfHittable::new(
+        blob,
+        Aabb::from((vec3(-4.0, 0.0, -1.0), vec3(-2.0, 2.0, 1.0))),
+        Arc::new(Lambertian::from(vec3(0.8, 0.3, 0.2))),
+    )));
+
+    let twisted = Sdf::Box(vec3(0.4, 1.0, 0.4))
+        .twist(1.5)
+        .translate(vec3(-1.0, 1.0, 0.0));
+    world.add(Arc::new(
+        SdfHittable::new(
+            twisted,
+            Aabb::from((vec3(-1.7, 0.0, -0.7), vec3(-0.3, 2.0, 0.7))),
+            Arc::new(Metal::new(vec3(0.8, 0.7, 0.3), 0.05)),
+        )
+        .with_step_scale(0.5),
+    ));
+
+    // A cube with a grid of holes drilled through it.
+    let swiss = Sdf::Box(vec3(0.8, 0.8, 0.8))
+        .subtraction(
+            Sdf::Capsule(vec3(0.0, 0.0, -2.0), vec3(0.0, 0.0, 2.0), 0.15)
+                .repeat(vec3(0.5, 0.5, 0.0)),
+        )
+        .rotate(Quat::from_rotation_y(0.5))
+        .translate(vec3(1.0, 0.8, 0.0));
+    world.add(Arc::new(SdfHittable::new(
+        swiss,
+        Aabb::from((vec3(-0.2, 0.0, -1.2), vec3(2.2, 1.6, 1.2))),
+        Arc::new(Lambertian::from(vec3(0.9, 0.8, 0.4))),
+    )));
+
+    let bulb = Sdf::Mandelbulb {
+        power: 8.0,
+        iterations: 10,
+    }
+    .scale(0.9)
+    .translate(vec3(3.2, 1.1, 0.0));
+    world.add(Arc::new(SdfHittable::new(
+        bulb,
+        Aabb::from((vec3(2.0, -0.1, -1.2), vec3(4.4, 2.3, 1.2))),
+        Arc::new(Lambertian::from(vec3(0.3, 0.5, 0.8))),
+    )));
+
+    // Two links of a chain, one lying flat and one stood up through it.
+    let link = || Sdf::Torus(0.5, 0.12);
+    let chain = link()
+        .translate(vec3(-0.45, 0.0, 0.0))
+        .union(
+            link()
+                .rotate(Quat::from_rotation_x(std::f32::consts::FRAC_PI_2))
+                .translate(vec3(0.45, 0.0, 0.0)),
+        )
+        .translate(vec3(-1.8, 0.62, 2.5));
+    world.add(Arc::new(SdfHittable::new(
+        chain,
+        Aabb::from((vec3(-2.9, 0.0, 1.8), vec3(-0.7, 1.3, 3.2))),
+        Arc::new(Metal::new(vec3(0.7, 0.7, 0.75), 0.1)),
+    )));
+
+    // A glass lens, where two spheres overlap.
+    let lens = Sdf::Sphere(1.0)
+        .translate(vec3(0.0, 0.0, -0.6))
+        .intersection(Sdf::Sphere(1.0).translate(vec3(0.0, 0.0, 0.6)))
+        .translate(vec3(1.8, 0.85, 2.5));
+    world.add(Arc::new(SdfHittable::new(
+        lens,
+        Aabb::from((vec3(0.9, 0.0, 2.0), vec3(2.7, 1.7, 3.0))),
+        Arc::new(Dielectric::new(1.5)),
+    )));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 3.0, 12.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn csg() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // The classic rounded cube: a box trimmed by a sphere.
+    let rounded = Csg::intersection(
+        Arc::new(cuboid(
+            vec3(-3.8, 0.0, -0.8),
+            vec3(-2.2, 1.6, 0.8),
+            Arc::new(Lambertian::from(vec3(0.8, 0.3, 0.2))),
+        )),
+        Arc::new(Sphere::new_stationary(
+            vec3(-3.0, 0.8, 0.0),
+            1.05,
+            Arc::new(Lambertian::from(vec3(0.9, 0.8, 0.4))),
+        )),
+    );
+    world.add(Arc::new(rounded));
+
+    // A box with a spherical bite taken out of its corner.
+    let bitten = Csg::difference(
+        Arc::new(cuboid(
+            vec3(-0.8, 0.0, -0.8),
+            vec3(0.8, 1.6, 0.8),
+            Arc::new(Lambertian::from(vec3(0.3, 0.5, 0.8))),
+        )),
+        Arc::new(Sphere::new_stationary(
+            vec3(0.8, 1.6, 0.8),
+            0.9,
+            Arc::new(Lambertian::from(vec3(0.9, 0.9, 0.9))),
+        )),
+    );
+    world.add(Arc::new(bitten));
+
+    // Two overlapping glass spheres with no wall between them.
+    let glass = Arc::new(Dielectric::new(1.5));
+    let lens = Csg::union(
+        Arc::new(Sphere::new_stationary(
+            vec3(2.7, 0.8, 0.0),
+            0.8,
+            glass.clone(),
+        )),
+        Arc::new(Sphere::new_stationary(vec3(3.5, 0.8, 0.0), 0.8, glass)),
+    );
+    world.add(Arc::new(lens));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 4.0, 12.0);
+    properties.look_at = vec3(0.0, 0.8, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn terrain() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let perlin = Perlin::with_seed(7);
+    let ramp = ColorRamp::new(vec![
+        (0.0, vec3(0.15, 0.3, 0.1)),
+        (0.5, vec3(0.4, 0.35, 0.2)),
+        (0.75, vec3(0.45, 0.4, 0.35)),
+        (0.85, vec3(0.95, 0.95, 0.95)),
+    ]);
+    let texture: Box<dyn Texture> =
+        Box::new(GradientTexture::new(Vec3::ZERO, vec3(0.0, 4.0, 0.0), ramp));
+    let material = Arc::new(Lambertian::from(texture));
+    let corner = vec3(-10.0, 0.0, -10.0);
+    let size = vec3(20.0, 4.0, 20.0);
+
+    // Any grayscale height map will do, 16-bit for smooth slopes; without
+    // one, fractal hills.
+    let hills = Heightfield::from_image("heightmap.png", corner, size, material.clone())
+        .unwrap_or_else(|error| {
+            eprintln!("{error}");
+            Heightfield::from_fn(
+                256,
+                256,
+                corner,
+                size,
+                |u, v| {
+                    let p = vec3(u, 0.0, v) * 4.0;
+                    (0.5 + 0.5 * perlin.fbm(p, 6, 2.0, 0.5)).clamp(0.0, 1.0)
+                },
+                material,
+            )
+        });
+    world.add(Arc::new(hills));
+
+    // A lake filling the valleys.
+    world.add(Arc::new(Plane::new(
+        vec3(0.0, 1.2, 0.0),
+        Vec3::Y,
+        Arc::new(Metal::new(vec3(0.3, 0.45, 0.6), 0.02)),
+    )));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 40.0;
+    properties.look_from = vec3(0.0, 8.0, 16.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn curves() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.3, 0.25, 0.15))),
+    )));
+
+    // A tuft of grass, each blade leaning and curling away from the centre.
+    let grass = Arc::new(Lambertian::from(vec3(0.2, 0.5, 0.1)));
+    for _ in 0..2_000 {
+        let angle = rand::random_range(0.0..std::f32::consts::TAU);
+        let radius = 1.5 * rand::random::<f32>().sqrt();
+        let root = vec3(angle.cos(), 0.0, angle.sin()) * radius;
+        let lean = vec3(angle.cos(), 0.0, angle.sin()) * rand::random_range(0.1..0.5);
+        let height = rand::random_range(0.6..1.2);
+
+        let blade = Curve::new(
+            [
+                root,
+                root + vec3(0.0, height / 3.0, 0.0),
+                root + lean * 0.5 + vec3(0.0, 2.0 * height / 3.0, 0.0),
+                root + lean + vec3(0.0, height * 0.9, 0.0),
+            ],
+            [0.03, 0.002],
+            CurveShape::Ribbon,
+            grass.clone(),
+        );
+        world.add(Arc::new(blade));
+    }
+
+    // A thick copper coil, shaded as a tube.
+    let coil: Vec<Vec3> = (0..=24)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::FRAC_PI_4;
+            vec3(
+                2.6 + 0.4 * angle.cos(),
+                0.2 + 0.05 * i as f32,
+                0.4 * angle.sin(),
+            )
+        })
+        .collect();
+    let copper = Arc::new(Metal::new(vec3(0.9, 0.55, 0.3), 0.2));
+    for segment in Curve::strand(&coil, [0.08, 0.08], CurveShape::Tube, copper) {
+        world.add(Arc::new(segment));
+    }
+
+    // A tuft of curly hair, from a file as groomed hair usually comes.
+    let hair = Arc::new(Lambertian::from(vec3(0.35, 0.2, 0.1)));
+    match load_curves("hair.curves", CurveShape::Ribbon, hair) {
+        Ok(tuft) => world.add(Arc::new(Translate::new(
+            Arc::new(tuft.into_bvh()),
+            vec3(-2.6, 0.0, 0.0),
+        ))),
+        Err(error) => eprintln!("{error}"),
+    }
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 3.0, 9.0);
+    properties.look_at = vec3(0.5, 0.6, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn subdivision() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // A cube, rounded off by Catmull-Clark.
+    let corners = (0..8)
+        .map(|i| {
+            let corner = vec3((i & 1) as f32, ((i >> 1) & 1) as f32, ((i >> 2) & 1) as f32);
+            corner * 2.0 + vec3(-3.5, 0.0, -1.0)
+        })
+        .collect();
+    let cube = Mesh::new(
+        corners,
+        vec![
+            vec![0, 2, 3, 1],
+            vec![4, 5, 7, 6],
+            vec![0, 4, 6, 2],
+            vec![1, 3, 7, 5],
+            vec![0, 1, 5, 4],
+            vec![2, 6, 7, 3],
+        ],
+    );
+    let rounded = cube.catmull_clark(4);
+    world.add(Arc::new(
+        rounded
+            .triangles(Arc::new(Lambertian::from(vec3(0.8, 0.3, 0.2))))
+            .into_bvh(),
+    ));
+
+    // An octahedron smoothed by Loop subdivision, then made lumpy with noise.
+    let octahedron = Mesh::new(
+        vec![
+            vec3(1.0, 1.0, 0.0),
+            vec3(-1.0, 1.0, 0.0),
+            vec3(0.0, 2.0, 0.0),
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 1.0),
+            vec3(0.0, 1.0, -1.0),
+        ],
+        vec![
+            vec![0, 2, 4],
+            vec![2, 1, 4],
+            vec![1, 3, 4],
+            vec![3, 0, 4],
+            vec![2, 0, 5],
+            vec![1, 2, 5],
+            vec![3, 1, 5],
+            vec![0, 3, 5],
+        ],
+    );
+    let noise = NoiseTexture::new(6.0).with_seed(2);
+    let rock = octahedron.loop_subdivide(5).displace(&noise, 0.25);
+    world.add(Arc::new(
+        rock.triangles(Arc::new(Lambertian::from(vec3(0.6, 0.55, 0.5))))
+            .into_bvh(),
+    ));
+
+    // The same cube again, lumpy and in glass.
+    let blob = cube
+        .catmull_clark(2)
+        .loop_subdivide(1)
+        .displace(&noise, 0.3);
+    let blob = Mesh {
+        positions: blob
+            .positions
+            .iter()
+            .map(|p| p + vec3(5.0, 0.0, 0.0))
+            .collect(),
+        ..blob
+    };
+    world.add(Arc::new(
+        blob.triangles(Arc::new(Dielectric::new(1.5))).into_bvh(),
+    ));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 4.0, 12.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn scanned_model() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // Any PLY with vertex colours will do; without one, a colourful
+    // stand-in blended from the corners of an octahedron.
+    let model = load_ply("model.ply").unwrap_or_else(|error| {
+        eprintln!("{error}");
+        let octahedron = Mesh::new(
+            vec![
+                vec3(1.0, 1.0, 0.0),
+                vec3(-1.0, 1.0, 0.0),
+                vec3(0.0, 2.0, 0.0),
+                vec3(0.0, 0.0, 0.0),
+                vec3(0.0, 1.0, 1.0),
+                vec3(0.0, 1.0, -1.0),
+            ],
+            vec![
+                vec![0, 2, 4],
+                vec![2, 1, 4],
+                vec![1, 3, 4],
+                vec![3, 0, 4],
+                vec![2, 0, 5],
+                vec![1, 2, 5],
+                vec![3, 1, 5],
+                vec![0, 3, 5],
+            ],
+        )
+        .with_colors(vec![
+            vec3(0.9, 0.1, 0.1),
+            vec3(0.1, 0.9, 0.1),
+            vec3(0.9, 0.9, 0.9),
+            vec3(0.1, 0.1, 0.1),
+            vec3(0.1, 0.1, 0.9),
+            vec3(0.9, 0.8, 0.1),
+        ]);
+        octahedron.loop_subdivide(4)
+    });
+
+    let texture: Box<dyn Texture> = Box::new(VertexColorTexture::new(vec3(0.8, 0.8, 0.8)));
+    world.add(Arc::new(
+        model
+            .triangles(Arc::new(Lambertian::from(texture)))
+            .into_bvh(),
+    ));
+
+    // A machined part beside it, flat shaded as STL files are.
+    match load_stl("part.stl") {
+        Ok(part) => {
+            let part = part
+                .triangles(Arc::new(Metal::new(vec3(0.8, 0.8, 0.85), 0.2)))
+                .into_bvh();
+            world.add(Arc::new(Translate::new(
+                Arc::new(part),
+                vec3(1.6, 0.0, 0.3),
+            )));
+        }
+        Err(error) => eprintln!("{error}"),
+    }
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 30.0;
+    properties.look_from = vec3(0.0, 3.0, 6.0);
+    properties.look_at = vec3(0.0, 0.8, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn cloud() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.3, 0.5, 0.2))),
+    )));
+
+    // Billowing noise, thinning out towards the edge of a ball.
+    let perlin = Perlin::with_seed(5);
+    let grid = DensityGrid::from_fn([64, 32, 64], |p| {
+        let falloff = 1.0 - (p - 0.5).length() * 2.0;
+        let billow = perlin.fbm(p * 6.0, 5, 2.0, 0.5);
+        (falloff + 0.5 * billow) * 4.0
+    });
+    // Water droplets scatter mostly forwards.
+    world.add(Arc::new(
+        GridMedium::from_color(
+            grid,
+            Aabb::from((vec3(-4.0, 2.0, -4.0), vec3(4.0, 6.0, 4.0))),
+            1.0,
+            vec3(0.95, 0.95, 0.95),
+        )
+        .with_phase_function(Arc::new(HenyeyGreenstein::from_color(
+            0.6,
+            vec3(0.95, 0.95, 0.95),
+        ))),
+    ));
+
+    // Smoke streaked with soot.
+    let smoke = DensityGrid::from_noise([32, 32, 32], &perlin, 4.0);
+    let soot = MixTexture::new(
+        Box::new(SolidColor::new(&Color::new(vec3(0.4, 0.4, 0.4)))),
+        Box::new(SolidColor::new(&Color::new(vec3(0.05, 0.05, 0.05)))),
+        Box::new(NoiseTexture::new(3.0).with_seed(2)),
+    );
+    world.add(Arc::new(GridMedium::new(
+        smoke,
+        Aabb::from((vec3(4.0, 0.0, -1.0), vec3(6.0, 2.0, 1.0))),
+        2.0,
+        Box::new(soot),
+    )));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(100);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 40.0;
+    properties.look_from = vec3(0.0, 3.0, 16.0);
+    properties.look_at = vec3(0.0, 3.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn phase_functions() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.4, 0.4, 0.4))),
+    )));
+
+    // A light behind the fog, so forward scattering glows and back
+    // scattering stays dark from the front.
+    world.add(Arc::new(Quad::new(
+        vec3(-6.0, 0.5, -4.0),
+        vec3(12.0, 0.0, 0.0),
+        vec3(0.0, 4.0, 0.0),
+        Arc::new(DiffuseLight::from(Vec3::splat(4.0))),
+    )));
+
+    let phase_functions = [
+        HenyeyGreenstein::from_color(-0.6, Vec3::splat(0.9)),
+        HenyeyGreenstein::from_color(0.0, Vec3::splat(0.9)),
+        HenyeyGreenstein::from_color(0.8, Vec3::splat(0.9)),
+        HenyeyGreenstein::from_color(0.8, Vec3::splat(0.9)).with_second_lobe(-0.4, 0.2),
+    ];
+    for (i, phase_function) in phase_functions.into_iter().enumerate() {
+        let boundary = Arc::new(Sphere::new_stationary(
+            vec3(-3.3 + 2.2 * i as f32, 1.0, 0.0),
+            1.0,
+            Arc::new(Lambertian::from(Vec3::ZERO)),
+        ));
+        world.add(Arc::new(
+            ConstantMedium::from_color(boundary, 1.5, Vec3::ZERO)
+                .with_phase_function(Arc::new(phase_function)),
+        ));
+    }
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(200);
+    properties.max_depth = 50;
+    properties.background = vec3(0.05, 0.05, 0.08);
+    properties.v_fov = 40.0;
+    properties.look_from = vec3(0.0, 2.0, 10.0);
+    properties.look_at = vec3(0.0, 1.0, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn glass_of_wine() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let checker: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
+        0.5,
+        &Color::new(vec3(0.2, 0.2, 0.2)),
+        &Color::new(vec3(0.9, 0.9, 0.9)),
+    ));
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(checker)),
+    )));
+
+    // A tumbler is a solid glass cylinder with a hollow of air, and the wine
+    // fills the hollow and reaches into the wall, so each liquid surface
+    // borders on exactly the glass or air that it should.
+    world.add(Arc::new(
+        Cylinder::new(
+            Vec3::ZERO,
+            vec3(0.0, 3.0, 0.0),
+            1.0,
+            Arc::new(Dielectric::new(1.5).with_priority(1)),
+        )
+        .capped(),
+    ));
+    world.add(Arc::new(
+        Cylinder::new(
+            vec3(0.0, 0.3, 0.0),
+            vec3(0.0, 3.0, 0.0),
+            0.9,
+            Arc::new(Dielectric::new(1.0).with_priority(2)),
+        )
+        .capped(),
+    ));
+    let wine = Dielectric::new(1.34)
+        .with_absorption(vec3(0.8, 7.0, 5.0))
+        .with_priority(3);
+    world.add(Arc::new(
+        Cylinder::new(
+            vec3(0.0, 0.25, 0.0),
+            vec3(0.0, 1.75, 0.0),
+            0.95,
+            Arc::new(wine),
+        )
+        .capped(),
+    ));
+
+    let bottle_glass = Dielectric::new(1.5).with_absorption(vec3(1.2, 0.2, 1.0));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(-2.6, 1.0, 0.0),
+        1.0,
+        Arc::new(bottle_glass),
+    )));
+
+    let milk = Dielectric::new(1.35)
+        .with_scattering(vec3(8.0, 8.5, 9.0))
+        .with_absorption(vec3(0.02, 0.05, 0.1))
+        .with_phase_function(Arc::new(HenyeyGreenstein::from_color(0.7, Vec3::ONE)));
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(2.6, 1.0, 0.0),
+        1.0,
+        Arc::new(milk),
+    )));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(200);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 35.0;
+    properties.look_from = vec3(0.0, 3.5, 9.0);
+    properties.look_at = vec3(0.0, 1.2, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn fireball() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.4, 0.4, 0.4))),
+    )));
+
+    // Sooty smoke around a core that is hottest in the middle, with the
+    // same noise breaking up both.
+    let perlin = Perlin::with_seed(11);
+    let billow = |p: Vec3| perlin.fbm(p * 5.0, 5, 2.0, 0.5);
+    let smoke = DensityGrid::from_fn([48, 48, 48], |p| {
+        let falloff = 1.0 - (p - 0.5).length() * 2.0;
+        (falloff + 0.6 * billow(p)) * 6.0
+    });
+    let temperature = DensityGrid::from_fn([48, 48, 48], |p| {
+        let heat = 1.0 - (p - 0.5).length() * 2.2 + 0.4 * billow(p);
+        1900.0 * heat.clamp(0.0, 1.0)
+    });
+    // Specks of glowing soot, thrown out past the flames.
+    let embers = DensityGrid::from_fn([48, 48, 48], |p| {
+        let shell = 1.0 - ((p - 0.5).length() * 2.0 - 0.8).abs() * 4.0;
+        (perlin.noise(p * 24.0) - 0.5).max(0.0) * shell.max(0.0) * 8.0
+    });
+
+    world.add(Arc::new(
+        GridMedium::from_color(
+            smoke,
+            Aabb::from((vec3(-2.0, 0.5, -2.0), vec3(2.0, 4.5, 2.0))),
+            0.3,
+            vec3(0.3, 0.3, 0.3),
+        )
+        .with_temperature_grid(temperature, 1e-4)
+        .with_emission_grid(embers, vec3(1.0, 0.35, 0.05)),
+    ));
+
+    // A faint haze of hot air around it all.
+    let haze = Arc::new(Sphere::new_stationary(
+        vec3(0.0, 2.5, 0.0),
+        3.0,
+        Arc::new(Lambertian::from(Vec3::ZERO)),
+    ));
+    world.add(Arc::new(
+        ConstantMedium::from_color(haze, 0.05, vec3(0.5, 0.5, 0.5)).with_emission(Box::new(
+            SolidColor::new(&Color::new(vec3(0.12, 0.04, 0.01))),
+        )),
+    ));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(100);
+    properties.max_depth = 50;
+    properties.background = vec3(0.02, 0.02, 0.04);
+    properties.v_fov = 40.0;
+    properties.look_from = vec3(0.0, 3.0, 12.0);
+    properties.look_at = vec3(0.0, 2.5, 0.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn simulation_volume() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(vec3(0.5, 0.5, 0.5))),
+    )));
+
+    // Any sparse brick volume with a density grid will do, and one with a
+    // temperature grid as well burns; without one, a tilted block of smoke.
+    let volume = load_volume("volume.sbvx").unwrap_or_else(|error| {
+        eprintln!("{error}");
+        let density = DensityGrid::from_noise([32, 32, 32], &Perlin::with_seed(3), 3.0);
+        Volume {
+            transform: Affine3A::from_rotation_translation(
+                Quat::from_rotation_y(0.6),
+                vec3(-1.5, 0.5, -1.0),
+            ) * Affine3A::from_scale(Vec3::splat(3.0)),
+            grids: HashMap::from([("density".to_string(), density)]),
+        }
+    });
+    let mut grids = volume.grids;
+    let density = grids
+        .remove("density")
+        .expect("the volume has no \"density\" grid");
+    let mut medium = GridMedium::transformed(density, volume.transform, 2.0, vec3(0.8, 0.8, 0.8));
+    if let Some(temperature) = grids.remove("temperature") {
+        medium = medium.with_temperature_grid(temperature, 1e-4);
+    }
+
+    // Frame the volume, whatever size and wherever it is.
+    let bounds = medium.bounding_box();
+    let center = vec3(
+        (bounds.x.min + bounds.x.max) / 2.0,
+        (bounds.y.min + bounds.y.max) / 2.0,
+        (bounds.z.min + bounds.z.max) / 2.0,
+    );
+    let extent = vec3(bounds.x.size(), bounds.y.size(), bounds.z.size()).length();
+
+    world.add(Arc::new(medium));
+
+    let world = world.into_bvh();
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(100);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.v_fov = 40.0;
+    properties.look_from = center + vec3(0.0, 0.3, 1.0) * extent * 1.5;
+    properties.look_at = center;
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.defocus_angle = 0.0;
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn projections() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let checker: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
+        1.0,
+        &Color::new(vec3(0.2, 0.3, 0.1)),
+        &Color::new(vec3(0.9, 0.9, 0.9)),
+    ));
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(checker)),
+    )));
+
+    // A ring of spheres all the way around the camera, so the wide
+    // projections have something to show in every direction.
+    let hues = ColorRamp::new(vec![
+        (0.0, vec3(0.8, 0.1, 0.1)),
+        (0.33, vec3(0.1, 0.7, 0.1)),
+        (0.67, vec3(0.1, 0.2, 0.8)),
+        (1.0, vec3(0.8, 0.1, 0.1)),
+    ]);
+    for i in 0..12 {
+        let angle = i as f32 / 12.0 * std::f32::consts::TAU;
+        world.add(Arc::new(Sphere::new_stationary(
+            vec3(angle.sin() * 5.0, 1.0, -angle.cos() * 5.0),
+            1.0,
+            Arc::new(Lambertian::from(hues.sample(i as f32 / 12.0))),
+        )));
+    }
+
+    let world = world.into_bvh();
+
+    let (projection, aspect_ratio): (Box<dyn Projection>, f32) = match 2 {
+        1 => (Box::new(Orthographic::new(8.0)), 16.0 / 9.0),
+        2 => (Box::new(Fisheye::equisolid(180.0)), 1.0),
+        3 => (Box::new(Fisheye::equidistant(360.0)), 1.0),
+        4 => (Box::new(Equirectangular), 2.0),
+        _ => (Box::new(Cubemap), 6.0),
+    };
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = aspect_ratio;
+    properties.image_width = 600;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.look_from = vec3(0.0, 1.5, 0.0);
+    properties.look_at = vec3(0.0, 1.5, -1.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.projection = Some(projection);
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn bokeh() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let checker: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
+        0.03,
+        &Color::new(vec3(0.05, 0.05, 0.05)),
+        &Color::new(vec3(0.3, 0.3, 0.3)),
+    ));
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(checker)),
+    )));
+
+    // In focus, lit by the lights behind.
+    for x in [-0.1, 0.0, 0.1] {
+        world.add(Arc::new(Sphere::new_stationary(
+            vec3(x, 0.04, -0.6),
+            0.04,
+            Arc::new(Lambertian::from(vec3(0.7, 0.6, 0.5))),
+        )));
+    }
+
+    world.add(Arc::new(Sphere::new_stationary(
+        vec3(1.0, 2.0, 0.0),
+        0.5,
+        Arc::new(DiffuseLight::from(vec3(1.0, 0.9, 0.8) * 4.0)),
+    )));
+
+    // Small bright lights far behind, which blur into the aperture's shape.
+    let hues = ColorRamp::new(vec![
+        (0.0, vec3(1.0, 0.6, 0.2)),
+        (0.5, vec3(1.0, 0.9, 0.6)),
+        (1.0, vec3(0.4, 0.6, 1.0)),
+    ]);
+    for i in 0..40 {
+        let position = vec3(
+            rand::random_range(-2.0..2.0),
+            rand::random_range(0.2..1.5),
+            rand::random_range(-6.0..-4.0),
+        );
+        let color = hues.sample(rand::random::<f32>());
+        world.add(Arc::new(Sphere::new_stationary(
+            position,
+            0.02 + 0.01 * (i % 3) as f32,
+            Arc::new(DiffuseLight::from(color * 6.0)),
+        )));
+    }
+
+    let world = world.into_bvh();
+
+    // A 50 mm lens, whether modelled as a thin lens or as its glass.
+    let focus_dist = 0.6;
+    let projection: Box<dyn Projection> = match 1 {
+        1 => Box::new(
+            Perspective::new(19.5, 2.4, focus_dist)
+                .with_aperture(Box::new(Polygonal::new(6, 15.0)))
+                .with_cat_eye(0.7),
+        ),
+        2 => {
+            let aperture: Box<dyn Aperture> = match ImageAperture::load("aperture.png") {
+                Ok(aperture) => Box::new(aperture),
+                Err(error) => {
+                    eprintln!("{error}");
+                    Box::new(Polygonal::new(5, 90.0))
+                }
+            };
+            Box::new(Perspective::new(19.5, 2.4, focus_dist).with_aperture(aperture))
+        }
+        // Any of pbrt's lens files will do; this one is a double Gauss design
+        // from Tronnier's 1954 patent, stopped down to f/2.8.
+        _ => match load_lens("dgauss.50mm.dat") {
+            Ok(elements) => Box::new(
+                LensSystem::new(elements, 35.0, focus_dist)
+                    .with_aperture(Box::new(Polygonal::new(6, 15.0)))
+                    .with_aperture_diameter(12.2),
+            ),
+            Err(error) => {
+                eprintln!("{error}");
+                Box::new(Perspective::new(19.5, 2.4, focus_dist))
+            }
+        },
+    };
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = 16.0 / 9.0;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(200);
+    properties.max_depth = 50;
+    properties.background = vec3(0.01, 0.01, 0.02);
+    properties.look_from = vec3(0.0, 0.1, 0.0);
+    properties.look_at = vec3(0.0, 0.07, -1.0);
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.projection = Some(projection);
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
+
+fn stereo() -> (HittableList, Camera) {
+    let mut world = HittableList::new();
+
+    let checker: Box<dyn Texture> = Box::new(CheckerTexture::from_color(
+        0.5,
+        &Color::new(vec3(0.2, 0.3, 0.1)),
+        &Color::new(vec3(0.9, 0.9, 0.9)),
+    ));
+    world.add(Arc::new(Plane::new(
+        Vec3::ZERO,
+        Vec3::Y,
+        Arc::new(Lambertian::from(checker)),
+    )));
+
+    // A spiral of spheres from arm's length out to the distance, all the
+    // way around, so there's depth to see in every direction.
+    let hues = ColorRamp::new(vec![
+        (0.0, vec3(0.8, 0.1, 0.1)),
+        (0.33, vec3(0.1, 0.7, 0.1)),
+        (0.67, vec3(0.1, 0.2, 0.8)),
+        (1.0, vec3(0.8, 0.1, 0.1)),
+    ]);
+    for i in 0..24 {
+        let angle = i as f32 / 8.0 * std::f32::consts::TAU;
+        let distance = 1.0 + i as f32 * 0.5;
+        let radius = 0.2 + i as f32 * 0.05;
+        world.add(Arc::new(Sphere::new_stationary(
+            vec3(angle.sin() * distance, radius, -angle.cos() * distance),
+            radius,
+            Arc::new(Lambertian::from(hues.sample(i as f32 / 24.0))),
+        )));
+    }
+
+    let world = world.into_bvh();
+
+    // A panorama has to be level, or its horizon waves.
+    let (projection, stereo, aspect_ratio, look_at): (Box<dyn Projection>, Stereo, f32, Vec3) =
+        match 1 {
+            1 => (
+                Box::new(Perspective::new(70.0, 0.0, 1.0)),
+                Stereo::new(0.064, StereoLayout::SideBySide).with_convergence(3.0),
+                1.0,
+                vec3(0.0, 0.6, -3.0),
+            ),
+            _ => (
+                Box::new(Equirectangular),
+                Stereo::new(0.064, StereoLayout::TopBottom).omni_directional(),
+                2.0,
+                vec3(0.0, 1.6, -1.0),
+            ),
+        };
+
+    let mut properties = CameraProperties::default();
+
+    properties.aspect_ratio = aspect_ratio;
+    properties.image_width = 400;
+    properties.samples_per_pixel = Some(50);
+    properties.max_depth = 50;
+    properties.background = vec3(0.7, 0.8, 1.0);
+    properties.look_from = vec3(0.0, 1.6, 0.0);
+    properties.look_at = look_at;
+    properties.up = vec3(0.0, 1.0, 0.0);
+    properties.projection = Some(projection);
+    properties.stereo = Some(stereo);
+
+    let camera = Camera::new(properties);
+
+    (world, camera)
+}
@@ -1,43 +1,182 @@
 use crate::hittable::HitRecord;
+use crate::material::interior::Interior;
+use crate::material::thin_film::{Substrate, ThinFilm};
 use crate::material::{Material, ScatterResult};
 use crate::ray::Ray;
-use glam::vec3;
+use glam::{Vec3, vec3};
+use std::sync::Arc;
 
+/// Glass, water and the like: surfaces that reflect or refract light into
+/// whatever fills the shape, which must be closed.
 pub struct Dielectric {
-    refraction_index: f32,
+    interior: Interior,
+    thin_film: Option<ThinFilm>,
 }
 
 impl Dielectric {
     pub fn new(refraction_index: f32) -> Self {
-        Self { refraction_index }
+        Self {
+            interior: Interior::new(refraction_index),
+            thin_film: None,
+        }
+    }
+
+    pub fn with_thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
+    }
+
+    /// Beer-Lambert absorption per unit length inside, per channel, which
+    /// tints thick parts more deeply than thin ones.
+    pub fn with_absorption(mut self, absorption: Vec3) -> Self {
+        self.interior.absorption = absorption;
+        self
+    }
+
+    /// Scattering per unit length inside, per channel, for murky or milky
+    /// contents.
+    pub fn with_scattering(mut self, scattering: Vec3) -> Self {
+        self.interior.scattering = scattering;
+        self
+    }
+
+    /// How the contents scatter, isotropically by default.
+    pub fn with_phase_function(mut self, phase_function: Arc<dyn Material>) -> Self {
+        self.interior.phase_function = phase_function;
+        self
+    }
+
+    /// Where this shape overlaps others, the one with the highest priority
+    /// fills the overlap. A liquid should outrank the glass holding it.
+    pub fn with_priority(mut self, priority: u32) -> Self {
+        self.interior.priority = priority;
+        self
+    }
+
+    /// The refractive indices on the incoming and outgoing sides of the
+    /// surface, or `None` where a shape of higher priority fills both sides
+    /// and the surface isn't really there.
+    fn refraction_indices(&self, r_in: &Ray, rec: &HitRecord) -> Option<(f32, f32)> {
+        let beyond = if rec.front_face {
+            r_in.media.clone()
+        } else {
+            r_in.media.exited(&rec.material)
+        };
+        if beyond
+            .current()
+            .is_some_and(|interior| interior.priority > self.interior.priority)
+        {
+            return None;
+        }
+
+        let inside = self.interior.refraction_index;
+        let outside = beyond.refraction_index();
+        Some(if rec.front_face {
+            (outside, inside)
+        } else {
+            (inside, outside)
+        })
     }
 }
 
 impl Material for Dielectric {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterResult> {
-        let refractive_index = if rec.front_face {
-            self.refraction_index.recip()
-        } else {
-            self.refraction_index
+        let Some((incident_ior, transmitted_ior)) = self.refraction_indices(r_in, rec) else {
+            let result = ScatterResult {
+                scattered: Ray::new(rec.point, r_in.direction, r_in.time)
+                    .with_differentials(rec.refracted_differentials(r_in, 1.0)),
+                attenuation: Vec3::ONE,
+            };
+            return Some(result);
         };
+        let refractive_index = incident_ior / transmitted_ior;
         let unit_direction = r_in.direction.normalize();
         let cos_theta = (-unit_direction).dot(rec.normal).min(1.0);
         let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
 
-        let direction = if (refractive_index * sin_theta > 1.0)
+        if let Some(thin_film) = &self.thin_film {
+            return Some(self.scatter_coated(
+                thin_film,
+                r_in,
+                rec,
+                unit_direction,
+                cos_theta,
+                (incident_ior, transmitted_ior),
+            ));
+        }
+
+        let (direction, differentials) = if (refractive_index * sin_theta > 1.0)
             || (reflectance(cos_theta, refractive_index) > rand::random::<f32>())
         {
-            unit_direction.reflect(rec.normal)
+            (
+                unit_direction.reflect(rec.normal),
+                rec.reflected_differentials(r_in),
+            )
         } else {
-            unit_direction.refract(rec.normal, refractive_index)
+            (
+                unit_direction.refract(rec.normal, refractive_index),
+                rec.refracted_differentials(r_in, refractive_index),
+            )
         };
 
         let result = ScatterResult {
-            scattered: Ray::new(rec.point, direction, r_in.time),
+            scattered: Ray::new(rec.point, direction, r_in.time).with_differentials(differentials),
             attenuation: vec3(1.0, 1.0, 1.0),
         };
         Some(result)
     }
+
+    fn interior(&self) -> Option<&Interior> {
+        Some(&self.interior)
+    }
+}
+
+impl Dielectric {
+    /// With a coating the reflectance differs per channel, so the branch is
+    /// picked on the average and the attenuation carries the colour.
+    fn scatter_coated(
+        &self,
+        thin_film: &ThinFilm,
+        r_in: &Ray,
+        rec: &HitRecord,
+        unit_direction: Vec3,
+        cos_theta: f32,
+        (outside_ior, inside_ior): (f32, f32),
+    ) -> ScatterResult {
+        let refracted = unit_direction.refract(rec.normal, outside_ior / inside_ior);
+        let reflectance = thin_film.reflectance(
+            rec,
+            cos_theta,
+            outside_ior,
+            Substrate::Dielectric(inside_ior),
+        );
+        let reflect_probability = reflectance.element_sum() / 3.0;
+
+        let (direction, differentials, attenuation) =
+            if refracted == Vec3::ZERO || rand::random::<f32>() < reflect_probability {
+                let attenuation = if refracted == Vec3::ZERO {
+                    Vec3::ONE
+                } else {
+                    reflectance / reflect_probability
+                };
+                (
+                    unit_direction.reflect(rec.normal),
+                    rec.reflected_differentials(r_in),
+                    attenuation,
+                )
+            } else {
+                (
+                    refracted,
+                    rec.refracted_differentials(r_in, outside_ior / inside_ior),
+                    (Vec3::ONE - reflectance) / (1.0 - reflect_probability),
+                )
+            };
+
+        ScatterResult {
+            scattered: Ray::new(rec.point, direction, r_in.time).with_differentials(differentials),
+            attenuation,
+        }
+    }
 }
 
 fn reflectance(cosine: f32, refraction_idx: f32) -> f32 {
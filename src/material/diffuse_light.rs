@@ -1,37 +1,115 @@
-use crate::color::Color;
+use crate::color::{Color, blackbody};
+use crate::hittable::HitRecord;
+use crate::material::Material;
 use crate::material::solid_color::SolidColor;
 use crate::material::texture::Texture;
-use crate::material::Material;
+use crate::ray::Ray;
 use glam::Vec3;
+use std::f32::consts::PI;
 
 pub struct DiffuseLight {
     texture: Box<dyn Texture>,
+    intensity: Intensity,
+    two_sided: bool,
+}
+
+/// How bright a light was asked to be. Power is kept as given, since the
+/// radiance it takes depends on how many sides emit.
+enum Intensity {
+    Radiance(f32),
+    Power { watts: f32, area: f32 },
+}
+
+impl DiffuseLight {
+    /// A light with the colour of a black body at `temperature` kelvin.
+    pub fn blackbody(temperature: f32) -> Self {
+        Self::from(blackbody(temperature))
+    }
+
+    /// Scales the emitted radiance, in nits, independently of the colour.
+    pub fn with_intensity(mut self, intensity: f32) -> Self {
+        self.intensity = Intensity::Radiance(intensity);
+        self
+    }
+
+    /// Sets the intensity from the total power in watts leaving a surface of
+    /// `area`, assuming each emitting side radiates uniformly over its hemisphere.
+    pub fn with_power(mut self, watts: f32, area: f32) -> Self {
+        self.intensity = Intensity::Power { watts, area };
+        self
+    }
+
+    /// Only emit from the side the surface's outward normal points to.
+    pub fn one_sided(mut self) -> Self {
+        self.two_sided = false;
+        self
+    }
+
+    /// The scale on the texture's colour, in nits.
+    fn radiance(&self) -> f32 {
+        match self.intensity {
+            Intensity::Radiance(radiance) => radiance,
+            Intensity::Power { watts, area } => {
+                let sides = if self.two_sided { 2.0 } else { 1.0 };
+                watts / (sides * PI * area)
+            }
+        }
+    }
 }
 
 impl From<Vec3> for DiffuseLight {
     fn from(color: Vec3) -> Self {
-        Self {
-            texture: Box::new(SolidColor::new(&Color::new(color))),
-        }
+        Self::from(&Color::new(color))
     }
 }
 
 impl From<&Color> for DiffuseLight {
     fn from(color: &Color) -> Self {
-        Self {
-            texture: Box::new(SolidColor::new(color)),
-        }
+        Self::from(Box::new(SolidColor::new(color)) as Box<dyn Texture>)
     }
 }
 
 impl From<Box<dyn Texture>> for DiffuseLight {
     fn from(texture: Box<dyn Texture>) -> Self {
-        Self { texture }
+        Self {
+            texture,
+            intensity: Intensity::Radiance(1.0),
+            two_sided: true,
+        }
     }
 }
 
 impl Material for DiffuseLight {
-    fn emitted(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
-        self.texture.value(u, v, p)
+    fn emitted(&self, _r_in: &Ray, rec: &HitRecord) -> Vec3 {
+        if !self.two_sided && !rec.front_face {
+            return Vec3::ZERO;
+        }
+        self.texture.value_at(rec) * self.radiance()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn power_does_not_depend_on_builder_order() {
+        let before = DiffuseLight::from(Vec3::ONE)
+            .with_power(20.0, 0.25)
+            .one_sided();
+        let after = DiffuseLight::from(Vec3::ONE)
+            .one_sided()
+            .with_power(20.0, 0.25);
+        assert_eq!(before.radiance(), after.radiance());
+        assert!((before.radiance() - 20.0 / (PI * 0.25)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn two_sided_lights_split_their_power() {
+        let one = DiffuseLight::from(Vec3::ONE)
+            .one_sided()
+            .with_power(10.0, 1.0);
+        let two = DiffuseLight::from(Vec3::ONE).with_power(10.0, 1.0);
+        assert!((one.radiance() - 2.0 * two.radiance()).abs() < 1e-5);
     }
 }
@@ -0,0 +1,158 @@
+use crate::color::Color;
+use crate::hittable::HitRecord;
+use crate::material::solid_color::SolidColor;
+use crate::material::texture::Texture;
+use crate::material::{Material, ScatterResult};
+use crate::ray::Ray;
+use glam::Vec3;
+use std::f32::consts::{PI, TAU};
+
+/// The Henyey-Greenstein phase function, for media that scatter light mostly
+/// onwards or mostly back rather than evenly like `Isotropic`.
+///
+/// The asymmetry `g` in (-1, 1) is the average cosine of the scattering
+/// angle: positive for forward scattering as in cloud and haze, negative for
+/// back scattering, and 0 for isotropic. Directions are drawn exactly in
+/// proportion to the phase function, so the attenuation is just the albedo.
+pub struct HenyeyGreenstein {
+    texture: Box<dyn Texture>,
+    g: f32,
+    /// A second lobe and the share of scattering that goes through it.
+    second_lobe: Option<(f32, f32)>,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(g: f32, texture: Box<dyn Texture>) -> Self {
+        assert!(g.abs() < 1.0);
+        Self {
+            texture,
+            g,
+            second_lobe: None,
+        }
+    }
+
+    pub fn from_color(g: f32, albedo: Vec3) -> Self {
+        Self::new(g, Box::new(SolidColor::new(&Color::new(albedo))))
+    }
+
+    /// Blends in a second lobe with asymmetry `g`, taking `weight` of the
+    /// scattering. A strong forward lobe with a weak backward one matches
+    /// clouds far better than either alone.
+    pub fn with_second_lobe(mut self, g: f32, weight: f32) -> Self {
+        assert!(g.abs() < 1.0 && (0.0..=1.0).contains(&weight));
+        self.second_lobe = Some((g, weight));
+        self
+    }
+
+    /// The density of scattering from travelling along `direction` to
+    /// travelling along `scattered`, per steradian.
+    #[cfg(test)]
+    pub fn pdf(&self, direction: Vec3, scattered: Vec3) -> f32 {
+        let cos_theta = direction.normalize().dot(scattered.normalize());
+        match self.second_lobe {
+            None => lobe_pdf(self.g, cos_theta),
+            Some((g, weight)) => {
+                (1.0 - weight) * lobe_pdf(self.g, cos_theta) + weight * lobe_pdf(g, cos_theta)
+            }
+        }
+    }
+
+    /// A unit direction to carry on along after scattering from `direction`,
+    /// distributed as `pdf`.
+    pub fn sample(&self, direction: Vec3) -> Vec3 {
+        let g = match self.second_lobe {
+            Some((g, weight)) if rand::random::<f32>() < weight => g,
+            _ => self.g,
+        };
+        let cos_theta = sample_lobe(g, rand::random());
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = TAU * rand::random::<f32>();
+
+        let forward = direction.normalize();
+        let (e1, e2) = forward.any_orthonormal_pair();
+        sin_theta * (phi.cos() * e1 + phi.sin() * e2) + cos_theta * forward
+    }
+}
+
+#[cfg(test)]
+fn lobe_pdf(g: f32, cos_theta: f32) -> f32 {
+    let denominator = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+}
+
+/// Inverts the lobe's cumulative distribution over the cosine.
+fn sample_lobe(g: f32, xi: f32) -> f32 {
+    if g.abs() < 1e-3 {
+        return 1.0 - 2.0 * xi;
+    }
+    let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * xi);
+    ((1.0 + g * g - s * s) / (2.0 * g)).clamp(-1.0, 1.0)
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterResult> {
+        let res = ScatterResult {
+            scattered: Ray::new(rec.point, self.sample(r_in.direction), r_in.time),
+            attenuation: self.texture.value_at(rec),
+        };
+        Some(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::vec3;
+
+    fn phase_functions() -> [(HenyeyGreenstein, f32); 5] {
+        [
+            (HenyeyGreenstein::from_color(0.0, Vec3::ONE), 0.0),
+            (HenyeyGreenstein::from_color(0.7, Vec3::ONE), 0.7),
+            (HenyeyGreenstein::from_color(-0.5, Vec3::ONE), -0.5),
+            (HenyeyGreenstein::from_color(0.95, Vec3::ONE), 0.95),
+            (
+                HenyeyGreenstein::from_color(0.8, Vec3::ONE).with_second_lobe(-0.4, 0.2),
+                0.8 * 0.8 - 0.4 * 0.2,
+            ),
+        ]
+    }
+
+    #[test]
+    fn mean_cosine_is_the_asymmetry() {
+        const SAMPLES: usize = 200_000;
+        let direction = vec3(1.0, 2.0, -2.0);
+        for (phase_function, mean_cosine) in phase_functions() {
+            let total: f32 = (0..SAMPLES)
+                .map(|_| {
+                    let scattered = phase_function.sample(direction);
+                    assert!((scattered.length() - 1.0).abs() < 1e-4);
+                    scattered.dot(direction.normalize())
+                })
+                .sum();
+            let mean = total / SAMPLES as f32;
+            assert!(
+                (mean - mean_cosine).abs() < 0.01,
+                "mean cosine {mean}, expected {mean_cosine}"
+            );
+        }
+    }
+
+    #[test]
+    fn pdf_integrates_to_one() {
+        const STEPS: usize = 100_000;
+        for (phase_function, _) in phase_functions() {
+            // Over the sphere, in bands of equal width in cos θ.
+            let total: f64 = (0..STEPS)
+                .map(|i| {
+                    let cos_theta = -1.0 + 2.0 * (i as f32 + 0.5) / STEPS as f32;
+                    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                    let scattered = vec3(sin_theta, 0.0, cos_theta);
+                    (phase_function.pdf(Vec3::Z, scattered) * TAU) as f64
+                })
+                .sum::<f64>()
+                * 2.0
+                / STEPS as f64;
+            assert!((total - 1.0).abs() < 1e-3, "integrates to {total}");
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use crate::hittable::HitRecord;
+use crate::material::Material;
+use crate::material::isotropic::Isotropic;
+use glam::Vec3;
+use std::sync::Arc;
+
+/// What fills a closed dielectric shape: how strongly it absorbs and
+/// scatters each channel per unit length, as a tinted glass or a glass of
+/// wine does, and its priority where shapes overlap.
+///
+/// Overlapping shapes let a liquid be modelled as filling its glass right up
+/// to the outer wall. Wherever the ray is inside several, the one with the
+/// highest priority wins, and the surfaces of the others are ignored there,
+/// so the liquid-glass interface refracts by the ratio of their indices.
+pub struct Interior {
+    pub(crate) refraction_index: f32,
+    pub(crate) priority: u32,
+    pub(crate) absorption: Vec3,
+    pub(crate) scattering: Vec3,
+    pub(crate) phase_function: Arc<dyn Material>,
+}
+
+/// The next thing to happen to light crossing an interior.
+pub enum Collision {
+    /// Scattered off the medium `distance` along the ray.
+    Scattered { distance: f32, weight: Vec3 },
+    /// Made it through to the surface at the end of the segment.
+    Passed { weight: Vec3 },
+}
+
+impl Interior {
+    pub fn new(refraction_index: f32) -> Self {
+        Self {
+            refraction_index,
+            priority: 0,
+            absorption: Vec3::ZERO,
+            scattering: Vec3::ZERO,
+            phase_function: Arc::new(Isotropic::from(Vec3::ONE)),
+        }
+    }
+
+    /// Whether light passes through without absorption or scattering.
+    pub fn is_clear(&self) -> bool {
+        self.absorption == Vec3::ZERO && self.scattering == Vec3::ZERO
+    }
+
+    /// Samples where light travelling `distance` through the interior first
+    /// scatters, if it does before reaching the end.
+    ///
+    /// Distances are drawn for the channel that scatters most, and `weight`
+    /// makes up the difference for each channel, including the absorption
+    /// along the way.
+    pub fn sample_collision(&self, distance: f32) -> Collision {
+        let extinction = self.absorption + self.scattering;
+        let majorant = self.scattering.max_element();
+        if majorant <= 0.0 {
+            return Collision::Passed {
+                weight: (-extinction * distance).exp(),
+            };
+        }
+
+        let scatter_distance = -(1.0 - rand::random::<f32>()).ln() / majorant;
+        if scatter_distance < distance {
+            Collision::Scattered {
+                distance: scatter_distance,
+                weight: (-(extinction - majorant) * scatter_distance).exp() * self.scattering
+                    / majorant,
+            }
+        } else {
+            Collision::Passed {
+                weight: (-(extinction - majorant) * distance).exp(),
+            }
+        }
+    }
+}
+
+/// The dielectric shapes a ray is inside, innermost last, so refraction can
+/// use the index on each side of a surface.
+#[derive(Clone, Default)]
+pub struct MediumStack {
+    materials: Vec<Arc<dyn Material>>,
+}
+
+impl MediumStack {
+    /// The interior the ray is actually travelling through: the one with the
+    /// highest priority, or the most recently entered of equals.
+    pub fn current(&self) -> Option<&Interior> {
+        self.materials
+            .iter()
+            .filter_map(|material| material.interior())
+            .max_by_key(|interior| interior.priority)
+    }
+
+    /// The refractive index around the ray, 1 in air.
+    pub fn refraction_index(&self) -> f32 {
+        self.current()
+            .map_or(1.0, |interior| interior.refraction_index)
+    }
+
+    pub fn entered(&self, material: Arc<dyn Material>) -> Self {
+        let mut media = self.clone();
+        media.materials.push(material);
+        media
+    }
+
+    /// The stack once the ray leaves `material`, which is unchanged if the
+    /// ray was never recorded as inside it.
+    pub fn exited(&self, material: &Arc<dyn Material>) -> Self {
+        let mut media = self.clone();
+        if let Some(index) = media
+            .materials
+            .iter()
+            .rposition(|entered| Arc::ptr_eq(entered, material))
+        {
+            media.materials.remove(index);
+        }
+        media
+    }
+
+    /// The stack for a ray leaving the surface at `rec` along `direction`,
+    /// which changes only when it passes through into or out of a shape with
+    /// an interior.
+    pub fn through(&self, rec: &HitRecord, direction: Vec3) -> Self {
+        let crosses = direction.dot(rec.normal) < 0.0;
+        if !crosses || rec.material.interior().is_none() {
+            self.clone()
+        } else if rec.front_face {
+            self.entered(rec.material.clone())
+        } else {
+            self.exited(&rec.material)
+        }
+    }
+}
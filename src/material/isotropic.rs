@@ -37,7 +37,7 @@ impl Material for Isotropic {
     fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterResult> {
         let res = ScatterResult {
             scattered: Ray::new(rec.point, random_unit_vector(), r_in.time),
-            attenuation: self.texture.value(rec.u, rec.v, rec.point),
+            attenuation: self.texture.value_at(rec),
         };
         Some(res)
     }
@@ -43,7 +43,7 @@ impl Material for Lambertian {
 
         let result = ScatterResult {
             scattered: Ray::new(rec.point, scatter_direction, r_in.time),
-            attenuation: self.texture.value(rec.u, rec.v, rec.point),
+            attenuation: self.texture.value_at(rec),
         };
         Some(result)
     }
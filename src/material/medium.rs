@@ -0,0 +1,59 @@
+use crate::hittable::HitRecord;
+use crate::material::texture::Texture;
+use crate::material::{Material, ScatterResult};
+use crate::ray::Ray;
+use glam::Vec3;
+use std::sync::Arc;
+
+/// What a participating medium does at a collision: scatter by its phase
+/// function, and add any light the medium gives off there, as fire or
+/// glowing gas does.
+///
+/// Every collision adds the emission as radiance, so a region dense enough
+/// to be opaque glows with the emission, and a thin one proportionally
+/// less. Empty space never glows, however hot.
+///
+/// The emission is not weighted by the share of collisions that absorb
+/// rather than scatter, σa/σt, as a physical medium's would be: a medium
+/// glows as brightly whatever its albedo, so the emission given is what an
+/// opaque region of it looks like.
+#[derive(Clone)]
+pub struct MediumMaterial {
+    phase_function: Arc<dyn Material>,
+    emission: Vec<Arc<dyn Texture>>,
+}
+
+impl MediumMaterial {
+    pub fn new(phase_function: Arc<dyn Material>) -> Self {
+        Self {
+            phase_function,
+            emission: Vec::new(),
+        }
+    }
+
+    pub fn with_phase_function(mut self, phase_function: Arc<dyn Material>) -> Self {
+        self.phase_function = phase_function;
+        self
+    }
+
+    /// Adds a source of light, looked up at the collision point, to any
+    /// already given.
+    pub fn with_emission(mut self, emission: Arc<dyn Texture>) -> Self {
+        self.emission.push(emission);
+        self
+    }
+}
+
+impl Material for MediumMaterial {
+    fn scatter(&self, r_in: &Ray, rec: &HitRecord) -> Option<ScatterResult> {
+        self.phase_function.scatter(r_in, rec)
+    }
+
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Vec3 {
+        let mut light = self.phase_function.emitted(r_in, rec);
+        for emission in &self.emission {
+            light += emission.value_at(rec);
+        }
+        light
+    }
+}
@@ -1,5 +1,6 @@
 use glam::Vec3;
 use crate::hittable::HitRecord;
+use crate::material::thin_film::{Substrate, ThinFilm};
 use crate::material::{Material, ScatterResult};
 use crate::ray::Ray;
 use crate::utils::random_unit_vector;
@@ -7,12 +8,22 @@ use crate::utils::random_unit_vector;
 pub struct Metal{
     albedo: Vec3,
     fuzz: f32,
+    thin_film: Option<ThinFilm>,
 }
 
 impl Metal {
     pub fn new(albedo: Vec3, fuzz: f32) -> Self {
         assert!(0.0 <= fuzz && fuzz <= 1.0);
-        Self { albedo, fuzz }
+        Self {
+            albedo,
+            fuzz,
+            thin_film: None,
+        }
+    }
+
+    pub fn with_thin_film(mut self, thin_film: ThinFilm) -> Self {
+        self.thin_film = Some(thin_film);
+        self
     }
 }
 
@@ -23,10 +34,27 @@ impl Material for Metal {
             reflected = reflected.normalize() + (self.fuzz * random_unit_vector());
         }
 
+        let attenuation = match &self.thin_film {
+            Some(thin_film) => {
+                let cos_theta = (-r_in.direction.normalize())
+                    .dot(rec.normal)
+                    .clamp(0.0, 1.0);
+                thin_film.reflectance(rec, cos_theta, 1.0, Substrate::Conductor(self.albedo))
+            }
+            None => self.albedo,
+        };
+
+        // A fuzzy reflection spreads out too much for the differentials to help.
+        let differentials = if self.fuzz > 0.0 {
+            None
+        } else {
+            rec.reflected_differentials(r_in)
+        };
+
         let result = ScatterResult {
-            scattered: Ray::new(rec.point, reflected, r_in.time),
-            attenuation: self.albedo,
+            scattered: Ray::new(rec.point, reflected, r_in.time).with_differentials(differentials),
+            attenuation,
         };
         Some(result)
     }
-}
\ No newline at end of file
+}
@@ -5,8 +5,13 @@ pub mod texture;
 pub mod solid_color;
 pub mod diffuse_light;
 pub mod isotropic;
+pub mod thin_film;
+pub mod henyey_greenstein;
+pub mod interior;
+pub mod medium;
 
 use crate::hittable::HitRecord;
+use crate::material::interior::Interior;
 use crate::ray::Ray;
 use glam::Vec3;
 
@@ -20,7 +25,12 @@ pub trait Material: Sync + Send {
         None
     }
 
-    fn emitted(&self, u: f32, v: f32, p: Vec3) -> Vec3 {
+    fn emitted(&self, r_in: &Ray, rec: &HitRecord) -> Vec3 {
         Vec3::ZERO
     }
+
+    /// What fills the shape, for materials that light passes into.
+    fn interior(&self) -> Option<&Interior> {
+        None
+    }
 }
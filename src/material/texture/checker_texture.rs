@@ -1,5 +1,6 @@
 use glam::Vec3;
 use crate::color::Color;
+use crate::hittable::HitRecord;
 use crate::material::solid_color::SolidColor;
 use crate::material::texture::Texture;
 
@@ -7,6 +8,7 @@ pub struct CheckerTexture {
     inverse_scale: f32,
     even: Box<dyn Texture>,
     odd: Box<dyn Texture>,
+    filtered: bool,
 }
 
 impl CheckerTexture {
@@ -15,6 +17,7 @@ impl CheckerTexture {
             inverse_scale: scale.recip(),
             even,
             odd,
+            filtered: false,
         }
     }
 
@@ -23,8 +26,16 @@ impl CheckerTexture {
             inverse_scale: scale.recip(),
             even: Box::new(SolidColor::new(c1)),
             odd: Box::new(SolidColor::new(c2)),
+            filtered: false,
         }
     }
+
+    /// Box filters the pattern over the pixel footprint, so distant checkers
+    /// fade to the average colour instead of aliasing.
+    pub fn filtered(mut self) -> Self {
+        self.filtered = true;
+        self
+    }
 }
 
 impl Texture for CheckerTexture {
@@ -37,4 +48,47 @@ impl Texture for CheckerTexture {
 
        if is_even { self.even.value(u, v, point) } else {self.odd.value(u, v, point)}
     }
-}
\ No newline at end of file
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        let footprint = &rec.footprint;
+        let width = footprint.dpdx.abs().max(footprint.dpdy.abs()) * self.inverse_scale;
+
+        if !self.filtered || width.max_element() < 1e-4 {
+            let point = self.inverse_scale * rec.point;
+            let is_even = (point.x.floor() + point.y.floor() + point.z.floor()) as i32 % 2 == 0;
+            return if is_even {
+                self.even.value_at(rec)
+            } else {
+                self.odd.value_at(rec)
+            };
+        }
+
+        // The pattern is the product of a +-1 square wave along each axis,
+        // so its box filtered value is the product of the filtered waves.
+        let point = self.inverse_scale * rec.point;
+        let mut parity = 1.0;
+        for axis in 0..3 {
+            parity *= filtered_square_wave(point[axis], width[axis]);
+        }
+        let even_weight = 0.5 * (1.0 + parity);
+
+        even_weight * self.even.value_at(rec) + (1.0 - even_weight) * self.odd.value_at(rec)
+    }
+}
+
+/// Average of (-1)^floor(x) over [x - width / 2, x + width / 2].
+pub(super) fn filtered_square_wave(x: f32, width: f32) -> f32 {
+    if width < 1e-4 {
+        return if x.floor() as i32 % 2 == 0 { 1.0 } else { -1.0 };
+    }
+
+    // Integral of the indicator that floor(x) is odd.
+    let odd_integral = |x: f32| {
+        let half = x / 2.0;
+        half.floor() + 2.0 * (half - half.floor() - 0.5).max(0.0)
+    };
+    let x0 = x - width / 2.0;
+    let x1 = x + width / 2.0;
+    let odd_fraction = (odd_integral(x1) - odd_integral(x0)) / width;
+    1.0 - 2.0 * odd_fraction
+}
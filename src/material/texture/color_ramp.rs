@@ -0,0 +1,35 @@
+use glam::Vec3;
+
+/// Maps a scalar onto colours by interpolating linearly between stops.
+/// Values outside the first and last stop take the colour of that stop.
+#[derive(Clone)]
+pub struct ColorRamp {
+    stops: Vec<(f32, Vec3)>,
+}
+
+impl ColorRamp {
+    pub fn new(mut stops: Vec<(f32, Vec3)>) -> Self {
+        assert!(!stops.is_empty());
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    /// Black at 0 to white at 1.
+    pub fn grayscale() -> Self {
+        Self::new(vec![(0.0, Vec3::ZERO), (1.0, Vec3::ONE)])
+    }
+
+    pub fn sample(&self, t: f32) -> Vec3 {
+        let next = self.stops.partition_point(|&(position, _)| position <= t);
+        if next == 0 {
+            return self.stops[0].1;
+        }
+        if next == self.stops.len() {
+            return self.stops[next - 1].1;
+        }
+
+        let (t0, c0) = self.stops[next - 1];
+        let (t1, c1) = self.stops[next];
+        c0.lerp(c1, (t - t0) / (t1 - t0))
+    }
+}
@@ -0,0 +1,159 @@
+use crate::hittable::HitRecord;
+use crate::material::texture::Texture;
+use crate::material::texture::color_ramp::ColorRamp;
+use crate::material::texture::image_texture::UvTransform;
+use glam::{Affine3A, Vec3, vec2};
+
+/// Blends `a` into `b` by the first channel of `mask`, 0 giving `a`.
+pub struct MixTexture {
+    a: Box<dyn Texture>,
+    b: Box<dyn Texture>,
+    mask: Box<dyn Texture>,
+}
+
+impl MixTexture {
+    pub fn new(a: Box<dyn Texture>, b: Box<dyn Texture>, mask: Box<dyn Texture>) -> Self {
+        Self { a, b, mask }
+    }
+}
+
+impl Texture for MixTexture {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+        let t = self.mask.value(u, v, point).x.clamp(0.0, 1.0);
+        self.a.value(u, v, point).lerp(self.b.value(u, v, point), t)
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        let t = self.mask.value_at(rec).x.clamp(0.0, 1.0);
+        self.a.value_at(rec).lerp(self.b.value_at(rec), t)
+    }
+}
+
+pub struct MultiplyTexture {
+    a: Box<dyn Texture>,
+    b: Box<dyn Texture>,
+}
+
+impl MultiplyTexture {
+    pub fn new(a: Box<dyn Texture>, b: Box<dyn Texture>) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Texture for MultiplyTexture {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+        self.a.value(u, v, point) * self.b.value(u, v, point)
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        self.a.value_at(rec) * self.b.value_at(rec)
+    }
+}
+
+pub struct AddTexture {
+    a: Box<dyn Texture>,
+    b: Box<dyn Texture>,
+}
+
+impl AddTexture {
+    pub fn new(a: Box<dyn Texture>, b: Box<dyn Texture>) -> Self {
+        Self { a, b }
+    }
+}
+
+impl Texture for AddTexture {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+        self.a.value(u, v, point) + self.b.value(u, v, point)
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        self.a.value_at(rec) + self.b.value_at(rec)
+    }
+}
+
+/// Recolours the first channel of `input` through a colour ramp, typically to
+/// turn a grayscale noise into a material colour.
+pub struct RampTexture {
+    input: Box<dyn Texture>,
+    ramp: ColorRamp,
+}
+
+impl RampTexture {
+    pub fn new(input: Box<dyn Texture>, ramp: ColorRamp) -> Self {
+        Self { input, ramp }
+    }
+}
+
+impl Texture for RampTexture {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+        self.ramp.sample(self.input.value(u, v, point).x)
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        self.ramp.sample(self.input.value_at(rec).x)
+    }
+}
+
+/// Looks `texture` up at transformed (u, v), for tiling or rotating any
+/// texture rather than just images.
+pub struct UvTransformTexture {
+    texture: Box<dyn Texture>,
+    transform: UvTransform,
+}
+
+impl UvTransformTexture {
+    pub fn new(texture: Box<dyn Texture>, transform: UvTransform) -> Self {
+        Self { texture, transform }
+    }
+}
+
+impl Texture for UvTransformTexture {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+        let uv = self.transform.apply(vec2(u, v));
+        self.texture.value(uv.x, uv.y, point)
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        let mut rec = rec.clone();
+        let uv = self.transform.apply(vec2(rec.u, rec.v));
+        (rec.u, rec.v) = (uv.x, uv.y);
+
+        let jacobian = self.transform.jacobian();
+        let footprint = &mut rec.footprint;
+        let dx = jacobian * vec2(footprint.dudx, footprint.dvdx);
+        let dy = jacobian * vec2(footprint.dudy, footprint.dvdy);
+        (footprint.dudx, footprint.dvdx) = (dx.x, dx.y);
+        (footprint.dudy, footprint.dvdy) = (dy.x, dy.y);
+
+        self.texture.value_at(&rec)
+    }
+}
+
+/// Looks a solid `texture` up at a transformed point, to move, rotate or
+/// stretch a procedural pattern independently of the object.
+pub struct PointTransformTexture {
+    texture: Box<dyn Texture>,
+    transform: Affine3A,
+}
+
+impl PointTransformTexture {
+    pub fn new(texture: Box<dyn Texture>, transform: Affine3A) -> Self {
+        Self { texture, transform }
+    }
+}
+
+impl Texture for PointTransformTexture {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+        self.texture
+            .value(u, v, self.transform.transform_point3(point))
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        let mut rec = rec.clone();
+        rec.point = self.transform.transform_point3(rec.point);
+        rec.footprint.dpdx = self.transform.transform_vector3(rec.footprint.dpdx);
+        rec.footprint.dpdy = self.transform.transform_vector3(rec.footprint.dpdy);
+
+        self.texture.value_at(&rec)
+    }
+}
@@ -1,29 +1,528 @@
+use crate::color::srgb_to_linear;
+use crate::hittable::{Footprint, HitRecord};
 use crate::material::texture::Texture;
-use glam::Vec3;
-use image::{open, Pixel, RgbImage};
-use crate::color::Color;
+use glam::{Mat2, Vec2, Vec3, Vec4, vec2};
+use image::{ColorType, ImageError, open};
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Arc;
 
+/// How texels are reconstructed between their centres.
+#[derive(Copy, Clone)]
+pub enum Filter {
+    Nearest,
+    Bilinear,
+    /// Catmull-Rom over the surrounding 4x4 texels.
+    Bicubic,
+}
+
+/// What happens to texture coordinates outside [0, 1].
+#[derive(Copy, Clone)]
+pub enum WrapMode {
+    Repeat,
+    Clamp,
+    Mirror,
+}
+
+/// Applied to (u, v) before lookup: scaled, then rotated by `rotation`
+/// degrees about the origin, then offset.
+#[derive(Copy, Clone)]
+pub struct UvTransform {
+    pub scale: Vec2,
+    pub rotation: f32,
+    pub offset: Vec2,
+}
+
+impl Default for UvTransform {
+    fn default() -> Self {
+        Self {
+            scale: Vec2::ONE,
+            rotation: 0.0,
+            offset: Vec2::ZERO,
+        }
+    }
+}
+
+impl UvTransform {
+    pub fn apply(&self, uv: Vec2) -> Vec2 {
+        Vec2::from_angle(self.rotation.to_radians()).rotate(uv * self.scale) + self.offset
+    }
+
+    pub fn jacobian(&self) -> Mat2 {
+        Mat2::from_angle(self.rotation.to_radians()) * Mat2::from_diagonal(self.scale)
+    }
+}
+
+/// How the values stored in an image file relate to linear light.
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Gamma encoded colour, as used by most 8-bit images.
+    Srgb,
+    /// Already linear, as in HDR images and data such as normal maps.
+    Linear,
+}
+
+/// How a footprint larger than a texel is averaged over.
+#[derive(Copy, Clone)]
+pub enum MipFilter {
+    /// Always sample the full resolution image.
+    None,
+    /// Bilinear lookups in the two nearest pyramid levels, blended.
+    Trilinear,
+    /// Elliptically weighted average over the anisotropic footprint.
+    Ewa,
+}
+
+#[derive(Copy, Clone)]
+pub struct TextureSampler {
+    pub filter: Filter,
+    pub mip_filter: MipFilter,
+    pub wrap: WrapMode,
+    pub transform: UvTransform,
+}
+
+impl Default for TextureSampler {
+    fn default() -> Self {
+        Self {
+            filter: Filter::Bilinear,
+            mip_filter: MipFilter::Trilinear,
+            wrap: WrapMode::Repeat,
+            transform: UvTransform::default(),
+        }
+    }
+}
+
+/// Footprints are clamped to this ratio of major to minor axis, trading some
+/// blur for bounded EWA cost.
+const MAX_ANISOTROPY: f32 = 8.0;
+
+struct MipLevel {
+    width: usize,
+    height: usize,
+    /// Linear RGB with straight alpha, row by row from the top of the image.
+    texels: Vec<Vec4>,
+}
+
+impl MipLevel {
+    /// Box filters 2x2 blocks, repeating the last row or column of odd sizes.
+    fn downsample(&self) -> MipLevel {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+
+        let texels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let x0 = (2 * x).min(self.width - 1);
+                let x1 = (2 * x + 1).min(self.width - 1);
+                let y0 = (2 * y).min(self.height - 1);
+                let y1 = (2 * y + 1).min(self.height - 1);
+                0.25 * (self.texels[y0 * self.width + x0]
+                    + self.texels[y0 * self.width + x1]
+                    + self.texels[y1 * self.width + x0]
+                    + self.texels[y1 * self.width + x1])
+            })
+            .collect();
+
+        MipLevel {
+            width,
+            height,
+            texels,
+        }
+    }
+}
+
+/// The decoded image and its mip pyramid, shared between every texture that
+/// samples the same file.
+pub struct MipMap {
+    /// Full resolution first, halving down to a single texel.
+    levels: Vec<MipLevel>,
+}
+
+impl MipMap {
+    pub fn load(file_path: &str, color_space: Option<ColorSpace>) -> Result<MipMap, TextureError> {
+        let image = open(file_path).map_err(|source| TextureError {
+            path: file_path.to_string(),
+            source,
+        })?;
+        let color_space = color_space.unwrap_or(match image.color() {
+            ColorType::Rgb32F | ColorType::Rgba32F => ColorSpace::Linear,
+            _ => ColorSpace::Srgb,
+        });
+
+        // Converting to float keeps the full range of HDR and 16-bit images.
+        let image = image.into_rgba32f();
+
+        // Decode once up front rather than on every lookup.
+        let decode = |c: f32| match color_space {
+            ColorSpace::Srgb => srgb_to_linear(c),
+            ColorSpace::Linear => c,
+        };
+        let texels = image
+            .pixels()
+            .map(|p| Vec4::new(decode(p[0]), decode(p[1]), decode(p[2]), p[3]))
+            .collect();
+
+        Ok(Self::from_texels(
+            image.width() as usize,
+            image.height() as usize,
+            texels,
+        ))
+    }
+
+    /// A loud magenta and black checkerboard, substituted for images that
+    /// failed to load.
+    pub fn missing() -> MipMap {
+        let size = 8;
+        let texels = (0..size * size)
+            .map(|i| {
+                if (i % size + i / size) % 2 == 0 {
+                    Vec4::new(1.0, 0.0, 1.0, 1.0)
+                } else {
+                    Vec4::new(0.0, 0.0, 0.0, 1.0)
+                }
+            })
+            .collect();
+        Self::from_texels(size, size, texels)
+    }
+
+    fn from_texels(width: usize, height: usize, texels: Vec<Vec4>) -> MipMap {
+        let mut levels = vec![MipLevel {
+            width,
+            height,
+            texels,
+        }];
+        while let Some(last) = levels.last()
+            && (last.width > 1 || last.height > 1)
+        {
+            let next = last.downsample();
+            levels.push(next);
+        }
+
+        Self { levels }
+    }
+}
+
+/// An image file that could not be opened or decoded.
+#[derive(Debug)]
+pub struct TextureError {
+    pub path: String,
+    pub source: ImageError,
+}
+
+impl Display for TextureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot load texture \"{}\": {}", self.path, self.source)
+    }
+}
+
+impl Error for TextureError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[derive(Clone)]
 pub struct ImageTexture {
-    image: RgbImage,
+    mipmap: Arc<MipMap>,
+    sampler: TextureSampler,
 }
 
 impl ImageTexture {
+    /// Loads an image, treating floating point formats such as `.hdr` and
+    /// `.exr` as linear and everything else as sRGB.
+    ///
+    /// Panics if the image cannot be loaded; use `ImageTexture::open` or a
+    /// `TextureCache` to handle the error instead.
     pub fn new(file_path: &str) -> ImageTexture {
-        Self {
-            image: open(file_path).expect("Cannot open file").into_rgb8(),
+        Self::with_sampler(file_path, TextureSampler::default())
+    }
+
+    pub fn with_sampler(file_path: &str, sampler: TextureSampler) -> ImageTexture {
+        Self::open(file_path, sampler, None).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    pub fn with_color_space(
+        file_path: &str,
+        sampler: TextureSampler,
+        color_space: ColorSpace,
+    ) -> ImageTexture {
+        Self::open(file_path, sampler, Some(color_space)).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Loads an image whose values are data rather than colour, such as a
+    /// normal or roughness map, so no sRGB decoding is applied.
+    pub fn new_linear(file_path: &str) -> ImageTexture {
+        Self::with_color_space(file_path, TextureSampler::default(), ColorSpace::Linear)
+    }
+
+    /// Loads an image, picking the colour space from its format when
+    /// `color_space` is `None`.
+    pub fn open(
+        file_path: &str,
+        sampler: TextureSampler,
+        color_space: Option<ColorSpace>,
+    ) -> Result<ImageTexture, TextureError> {
+        let mipmap = MipMap::load(file_path, color_space)?;
+        Ok(Self::from_mipmap(Arc::new(mipmap), sampler))
+    }
+
+    pub fn from_mipmap(mipmap: Arc<MipMap>, sampler: TextureSampler) -> ImageTexture {
+        Self { mipmap, sampler }
+    }
+
+    fn texel(&self, level: usize, x: i64, y: i64) -> Vec4 {
+        let level = &self.mipmap.levels[level];
+        let x = wrap(x, level.width, self.sampler.wrap);
+        let y = wrap(y, level.height, self.sampler.wrap);
+        level.texels[y * level.width + x]
+    }
+
+    /// Continuous texel coordinates in `level`, with texel centres at integers.
+    fn texel_coords(&self, level: usize, uv: Vec2) -> Vec2 {
+        let level = &self.mipmap.levels[level];
+        vec2(
+            uv.x * level.width as f32 - 0.5,
+            (1.0 - uv.y) * level.height as f32 - 0.5,
+        )
+    }
+
+    fn bilinear(&self, level: usize, uv: Vec2) -> Vec4 {
+        let p = self.texel_coords(level, uv);
+        let (x0, y0) = (p.x.floor(), p.y.floor());
+        let (tx, ty) = (p.x - x0, p.y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self
+            .texel(level, x0, y0)
+            .lerp(self.texel(level, x0 + 1, y0), tx);
+        let bottom = self
+            .texel(level, x0, y0 + 1)
+            .lerp(self.texel(level, x0 + 1, y0 + 1), tx);
+        top.lerp(bottom, ty)
+    }
+
+    /// Reconstructs the full resolution image with the configured filter.
+    fn magnify(&self, uv: Vec2) -> Vec4 {
+        match self.sampler.filter {
+            Filter::Nearest => {
+                let p = self.texel_coords(0, uv);
+                self.texel(0, p.x.round() as i64, p.y.round() as i64)
+            }
+            Filter::Bilinear => self.bilinear(0, uv),
+            Filter::Bicubic => {
+                let p = self.texel_coords(0, uv);
+                let (x0, y0) = (p.x.floor(), p.y.floor());
+                let wx = catmull_rom_weights(p.x - x0);
+                let wy = catmull_rom_weights(p.y - y0);
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let mut acc = Vec4::ZERO;
+                for (j, wy) in wy.iter().enumerate() {
+                    for (i, wx) in wx.iter().enumerate() {
+                        acc += wx * wy * self.texel(0, x0 + i as i64 - 1, y0 + j as i64 - 1);
+                    }
+                }
+                // Catmull-Rom overshoots around sharp edges.
+                acc.max(Vec4::ZERO)
+            }
+        }
+    }
+
+    fn sample(&self, u: f32, v: f32) -> Vec4 {
+        self.magnify(self.sampler.transform.apply(vec2(u, v)))
+    }
+
+    fn sample_filtered(&self, u: f32, v: f32, footprint: &Footprint) -> Vec4 {
+        let transform = &self.sampler.transform;
+        let uv = transform.apply(vec2(u, v));
+
+        // Pixel-to-pixel change in full resolution texels, with y running
+        // down the image like the texel rows do.
+        let jacobian = transform.jacobian();
+        let base = &self.mipmap.levels[0];
+        let to_texels = vec2(base.width as f32, -(base.height as f32));
+        let dx = jacobian * vec2(footprint.dudx, footprint.dvdx) * to_texels;
+        let dy = jacobian * vec2(footprint.dudy, footprint.dvdy) * to_texels;
+
+        let last_level = (self.mipmap.levels.len() - 1) as f32;
+
+        match self.sampler.mip_filter {
+            MipFilter::None => self.magnify(uv),
+            MipFilter::Trilinear => {
+                let width = dx.length().max(dy.length());
+                let lod = width.max(1e-8).log2();
+                if lod <= 0.0 {
+                    return self.magnify(uv);
+                }
+
+                let lod = lod.min(last_level);
+                let level = lod.floor();
+                let lower = self.bilinear(level as usize, uv);
+                if level == last_level {
+                    return lower;
+                }
+                lower.lerp(self.bilinear(level as usize + 1, uv), lod - level)
+            }
+            MipFilter::Ewa => {
+                let (major, mut minor) = if dx.length() >= dy.length() {
+                    (dx, dy)
+                } else {
+                    (dy, dx)
+                };
+                let major_length = major.length();
+                let mut minor_length = minor.length();
+
+                // Very thin ellipses would cover a huge number of texels, so
+                // widen them and read from a coarser level instead.
+                if minor_length * MAX_ANISOTROPY < major_length && minor_length > 0.0 {
+                    let scale = major_length / (minor_length * MAX_ANISOTROPY);
+                    minor *= scale;
+                    minor_length *= scale;
+                }
+                if minor_length == 0.0 {
+                    return self.magnify(uv);
+                }
+
+                let lod = minor_length.log2().clamp(0.0, last_level);
+                let level = lod.floor();
+                let lower = self.ewa(level as usize, uv, major, minor);
+                if level == last_level {
+                    return lower;
+                }
+                lower.lerp(self.ewa(level as usize + 1, uv, major, minor), lod - level)
+            }
+        }
+    }
+
+    /// Gaussian weighted average over the ellipse spanned by `axis0` and
+    /// `axis1`, given in full resolution texels.
+    fn ewa(&self, level: usize, uv: Vec2, axis0: Vec2, axis1: Vec2) -> Vec4 {
+        let base = &self.mipmap.levels[0];
+        let current = &self.mipmap.levels[level];
+        let scale = vec2(
+            current.width as f32 / base.width as f32,
+            current.height as f32 / base.height as f32,
+        );
+        let st = self.texel_coords(level, uv);
+        let d0 = axis0 * scale;
+        let d1 = axis1 * scale;
+
+        // Implicit ellipse A s^2 + B s t + C t^2 < 1, padded by a texel so the
+        // filter never falls between texel centres.
+        let mut a = d0.y * d0.y + d1.y * d1.y + 1.0;
+        let mut b = -2.0 * (d0.x * d0.y + d1.x * d1.y);
+        let mut c = d0.x * d0.x + d1.x * d1.x + 1.0;
+        let inv_f = (a * c - b * b * 0.25).recip();
+        a *= inv_f;
+        b *= inv_f;
+        c *= inv_f;
+
+        let det = -b * b + 4.0 * a * c;
+        let inv_det = det.recip();
+        let u_sqrt = (det * c).sqrt();
+        let v_sqrt = (a * det).sqrt();
+        let s0 = (st.x - 2.0 * inv_det * u_sqrt).ceil() as i64;
+        let s1 = (st.x + 2.0 * inv_det * u_sqrt).floor() as i64;
+        let t0 = (st.y - 2.0 * inv_det * v_sqrt).ceil() as i64;
+        let t1 = (st.y + 2.0 * inv_det * v_sqrt).floor() as i64;
+
+        let alpha: f32 = 2.0;
+        let edge = (-alpha).exp();
+        let mut sum = Vec4::ZERO;
+        let mut weight_sum = 0.0;
+        for it in t0..=t1 {
+            let tt = it as f32 - st.y;
+            for is in s0..=s1 {
+                let ss = is as f32 - st.x;
+                let r2 = a * ss * ss + b * ss * tt + c * tt * tt;
+                if r2 < 1.0 {
+                    let weight = (-alpha * r2).exp() - edge;
+                    sum += weight * self.texel(level, is, it);
+                    weight_sum += weight;
+                }
+            }
+        }
+
+        if weight_sum > 0.0 {
+            sum / weight_sum
+        } else {
+            self.bilinear(level, uv)
         }
     }
 }
 
 impl Texture for ImageTexture {
     fn value(&self, u: f32, v: f32, _: Vec3) -> Vec3 {
-        assert!(u >= 0.0 && u <= 1.0 && v >= 0.0 && v <= 1.0, "{u} {v}");
+        self.sample(u, v).truncate()
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        self.sample_filtered(rec.u, rec.v, &rec.footprint)
+            .truncate()
+    }
+
+    fn alpha(&self, u: f32, v: f32, _: Vec3) -> f32 {
+        self.sample(u, v).w.clamp(0.0, 1.0)
+    }
+
+    fn alpha_at(&self, rec: &HitRecord) -> f32 {
+        self.sample_filtered(rec.u, rec.v, &rec.footprint)
+            .w
+            .clamp(0.0, 1.0)
+    }
+}
+
+fn wrap(i: i64, size: usize, mode: WrapMode) -> usize {
+    let size = size as i64;
+    let i = match mode {
+        WrapMode::Repeat => i.rem_euclid(size),
+        WrapMode::Clamp => i.clamp(0, size - 1),
+        WrapMode::Mirror => {
+            let i = i.rem_euclid(2 * size);
+            if i < size { i } else { 2 * size - 1 - i }
+        }
+    };
+    i as usize
+}
+
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn linear_images_are_not_srgb_decoded() {
+        let directory = env::temp_dir().join("image_texture_test");
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("grey16.png");
+        ImageBuffer::<Rgb<u16>, Vec<u16>>::from_pixel(2, 2, Rgb([32768; 3]))
+            .save(&file)
+            .unwrap();
+        let path = file.to_str().unwrap();
+        let grey = 32768.0 / 65535.0;
 
-        let i = (u * ((self.image.width() - 1) as f32)) as u32;
-        let j = ((1.0 - v) * ((self.image.height() - 1) as f32)) as u32;
-        let pixel = self.image.get_pixel(i, j).channels();
+        let linear =
+            ImageTexture::with_color_space(path, TextureSampler::default(), ColorSpace::Linear);
+        let value = linear.value(0.5, 0.5, Vec3::ZERO);
+        assert!(value.abs_diff_eq(Vec3::splat(grey), 1e-4), "{value}");
 
-        let color = *Color::from_rgb(pixel[0], pixel[1], pixel[2]).vec3();
-        color * color // remove gamma correction I think
+        // 16-bit PNGs are otherwise taken to be gamma encoded.
+        let srgb = ImageTexture::new(path).value(0.5, 0.5, Vec3::ZERO);
+        let decoded = srgb_to_linear(grey);
+        assert!(srgb.abs_diff_eq(Vec3::splat(decoded), 1e-4), "{srgb}");
+        assert!(decoded < 0.25);
     }
 }
@@ -0,0 +1,36 @@
+use crate::material::texture::Texture;
+use crate::material::texture::color_ramp::ColorRamp;
+use crate::perlin::Perlin;
+use glam::Vec3;
+
+/// Veins along the z axis, bent by turbulence like `NoiseTexture`, and coloured
+/// through a ramp running from the base stone at 0 to the veins at 1.
+pub struct MarbleTexture {
+    noise: Perlin,
+    scale: f32,
+    turbulence: f32,
+    ramp: ColorRamp,
+}
+
+impl MarbleTexture {
+    pub fn new(scale: f32, turbulence: f32, ramp: ColorRamp) -> Self {
+        Self {
+            noise: Perlin::new(),
+            scale,
+            turbulence,
+            ramp,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::with_seed(seed);
+        self
+    }
+}
+
+impl Texture for MarbleTexture {
+    fn value(&self, _: f32, _: f32, point: Vec3) -> Vec3 {
+        let phase = self.scale * point.z + self.turbulence * self.noise.turbulence(point, 7);
+        self.ramp.sample(0.5 * (1.0 - phase.sin()))
+    }
+}
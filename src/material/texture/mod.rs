@@ -1,9 +1,54 @@
+use crate::hittable::HitRecord;
 use glam::Vec3;
+use std::sync::Arc;
 
 pub mod checker_texture;
+pub mod color_ramp;
+pub mod combinators;
 pub mod image_texture;
+pub mod marble_texture;
 pub mod noise_texture;
+pub mod pattern_texture;
+pub mod texture_cache;
+pub mod vertex_color_texture;
+pub mod wood_texture;
+pub mod worley_texture;
 
 pub trait Texture : Sync + Send {
    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3;
+
+   /// Looks the texture up for a hit, which lets filtered textures use the
+   /// pixel footprint. Defaults to a point sample.
+   fn value_at(&self, rec: &HitRecord) -> Vec3 {
+      self.value(rec.u, rec.v, rec.point)
+   }
+
+   /// Opacity in [0, 1]; textures without an alpha channel are fully opaque.
+   fn alpha(&self, _u: f32, _v: f32, _point: Vec3) -> f32 {
+      1.0
+   }
+
+   /// Opacity at a hit, filtered over its footprint like `value_at`.
+   fn alpha_at(&self, rec: &HitRecord) -> f32 {
+      self.alpha(rec.u, rec.v, rec.point)
+   }
+}
+
+/// Lets one texture be shared between several materials.
+impl<T: Texture + ?Sized> Texture for Arc<T> {
+   fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+      (**self).value(u, v, point)
+   }
+
+   fn value_at(&self, rec: &HitRecord) -> Vec3 {
+      (**self).value_at(rec)
+   }
+
+   fn alpha(&self, u: f32, v: f32, point: Vec3) -> f32 {
+      (**self).alpha(u, v, point)
+   }
+
+   fn alpha_at(&self, rec: &HitRecord) -> f32 {
+      (**self).alpha_at(rec)
+   }
 }
\ No newline at end of file
@@ -1,10 +1,12 @@
+use crate::hittable::HitRecord;
 use crate::material::texture::Texture;
 use crate::perlin::Perlin;
-use glam::Vec3;
+use glam::{IVec3, Vec3};
 
 pub struct NoiseTexture {
     noise: Perlin,
     scale: f32,
+    speed: f32,
 }
 
 impl NoiseTexture {
@@ -12,8 +14,21 @@ impl NoiseTexture {
         Self {
             noise: Perlin::new(),
             scale,
+            speed: 0.0,
         }
     }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::with_seed(seed);
+        self
+    }
+
+    /// Lets the turbulence evolve over the ray time, `speed` noise cells per
+    /// unit of time, for motion blurred or animated renders.
+    pub fn animated(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
 }
 
 impl Texture for NoiseTexture {
@@ -21,4 +36,160 @@ impl Texture for NoiseTexture {
         Vec3::splat(0.5)
             * (1.0 + f32::sin(self.scale * point.z + 10.0 * self.noise.turbulence(point, 7)))
     }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        if self.speed == 0.0 {
+            return self.value(rec.u, rec.v, rec.point);
+        }
+
+        let turbulence = self
+            .noise
+            .turbulence4(rec.point.extend(self.speed * rec.time), 7);
+        Vec3::splat(0.5) * (1.0 + f32::sin(self.scale * rec.point.z + 10.0 * turbulence))
+    }
+}
+
+/// Grayscale fractional Brownian motion, remapped to [0, 1]. Pair it with a
+/// `RampTexture` for colour.
+pub struct FbmTexture {
+    noise: Perlin,
+    scale: f32,
+    octaves: u32,
+    lacunarity: f32,
+    gain: f32,
+    period: Option<IVec3>,
+}
+
+impl FbmTexture {
+    pub fn new(scale: f32, octaves: u32) -> Self {
+        Self {
+            noise: Perlin::new(),
+            scale,
+            octaves,
+            lacunarity: 2.0,
+            gain: 0.5,
+            period: None,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::with_seed(seed);
+        self
+    }
+
+    /// Frequency multiplier between octaves, 2 by default.
+    pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Amplitude multiplier between octaves, 0.5 by default. Higher is rougher.
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Repeats the pattern every `period` noise cells along each axis, which
+    /// is `period / scale` in space, so it tiles seamlessly. Octaves then
+    /// always double in frequency, whatever the lacunarity.
+    pub fn with_period(mut self, period: IVec3) -> Self {
+        self.period = Some(period);
+        self
+    }
+}
+
+impl Texture for FbmTexture {
+    fn value(&self, _: f32, _: f32, point: Vec3) -> Vec3 {
+        let p = self.scale * point;
+        let n = match self.period {
+            Some(period) => self.noise.periodic_fbm(p, period, self.octaves, self.gain),
+            None => self.noise.fbm(p, self.octaves, self.lacunarity, self.gain),
+        };
+        Vec3::splat(0.5 * (1.0 + n))
+    }
+}
+
+/// Grayscale simplex noise, remapped to [0, 1]. Smoother than `NoiseTexture`
+/// and without the blocky look Perlin noise has along the axes.
+pub struct SimplexTexture {
+    noise: Perlin,
+    scale: f32,
+}
+
+impl SimplexTexture {
+    pub fn new(scale: f32) -> Self {
+        Self {
+            noise: Perlin::new(),
+            scale,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::with_seed(seed);
+        self
+    }
+}
+
+impl Texture for SimplexTexture {
+    fn value(&self, _: f32, _: f32, point: Vec3) -> Vec3 {
+        Vec3::splat(0.5 * (1.0 + self.noise.simplex(self.scale * point)))
+    }
+}
+
+/// Grayscale ridged multifractal, good for mountain ranges, veins and cracks.
+pub struct RidgedTexture {
+    noise: Perlin,
+    scale: f32,
+    octaves: u32,
+    lacunarity: f32,
+    gain: f32,
+    offset: f32,
+}
+
+impl RidgedTexture {
+    pub fn new(scale: f32, octaves: u32) -> Self {
+        Self {
+            noise: Perlin::new(),
+            scale,
+            octaves,
+            lacunarity: 2.0,
+            gain: 2.0,
+            offset: 1.0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::with_seed(seed);
+        self
+    }
+
+    pub fn with_lacunarity(mut self, lacunarity: f32) -> Self {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// How strongly each octave is confined to the ridges of the last, 2 by
+    /// default.
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Height of the ridges before squaring, 1 by default.
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = offset;
+        self
+    }
+}
+
+impl Texture for RidgedTexture {
+    fn value(&self, _: f32, _: f32, point: Vec3) -> Vec3 {
+        Vec3::splat(self.noise.ridged(
+            self.scale * point,
+            self.octaves,
+            self.lacunarity,
+            self.gain,
+            self.offset,
+        ))
+    }
 }
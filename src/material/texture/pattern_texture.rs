@@ -0,0 +1,78 @@
+use crate::hittable::HitRecord;
+use crate::material::texture::Texture;
+use crate::material::texture::checker_texture::filtered_square_wave;
+use crate::material::texture::color_ramp::ColorRamp;
+use glam::Vec3;
+
+/// A linear gradient through space, 0 at `start` and 1 at `end`, coloured by
+/// a ramp.
+pub struct GradientTexture {
+    start: Vec3,
+    axis: Vec3,
+    ramp: ColorRamp,
+}
+
+impl GradientTexture {
+    pub fn new(start: Vec3, end: Vec3, ramp: ColorRamp) -> Self {
+        let span = end - start;
+        assert!(span.length_squared() > 0.0);
+        Self {
+            start,
+            axis: span / span.length_squared(),
+            ramp,
+        }
+    }
+}
+
+impl Texture for GradientTexture {
+    fn value(&self, _: f32, _: f32, point: Vec3) -> Vec3 {
+        self.ramp.sample((point - self.start).dot(self.axis))
+    }
+}
+
+/// Parallel bands `width` wide across `direction`, alternating between two
+/// textures. Filtered over the pixel footprint like `CheckerTexture`.
+pub struct StripeTexture {
+    direction: Vec3,
+    inverse_width: f32,
+    even: Box<dyn Texture>,
+    odd: Box<dyn Texture>,
+}
+
+impl StripeTexture {
+    pub fn new(direction: Vec3, width: f32, even: Box<dyn Texture>, odd: Box<dyn Texture>) -> Self {
+        Self {
+            direction: direction.normalize(),
+            inverse_width: width.recip(),
+            even,
+            odd,
+        }
+    }
+
+    fn position(&self, point: Vec3) -> f32 {
+        point.dot(self.direction) * self.inverse_width
+    }
+}
+
+impl Texture for StripeTexture {
+    fn value(&self, u: f32, v: f32, point: Vec3) -> Vec3 {
+        if self.position(point).floor() as i32 % 2 == 0 {
+            self.even.value(u, v, point)
+        } else {
+            self.odd.value(u, v, point)
+        }
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        let footprint = &rec.footprint;
+        let width = footprint
+            .dpdx
+            .dot(self.direction)
+            .abs()
+            .max(footprint.dpdy.dot(self.direction).abs())
+            * self.inverse_width;
+
+        let even_weight = 0.5 * (1.0 + filtered_square_wave(self.position(rec.point), width));
+        even_weight * self.even.value_at(rec) + (1.0 - even_weight) * self.odd.value_at(rec)
+    }
+}
@@ -0,0 +1,162 @@
+use crate::material::texture::image_texture::{
+    ColorSpace, ImageTexture, MipMap, TextureError, TextureSampler,
+};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+type CacheKey = (PathBuf, Option<ColorSpace>);
+
+/// Loads each image file once and shares its pixels between every texture
+/// that uses it, whatever sampler settings they ask for.
+///
+/// A strict cache reports files that fail to load. A lenient one prints a
+/// warning and substitutes a magenta checkerboard instead, so a single bad
+/// path doesn't end a long render.
+pub struct TextureCache {
+    images: Mutex<HashMap<CacheKey, Arc<MipMap>>>,
+    lenient: bool,
+}
+
+impl TextureCache {
+    pub fn new() -> Self {
+        Self {
+            images: Mutex::new(HashMap::new()),
+            lenient: false,
+        }
+    }
+
+    pub fn lenient() -> Self {
+        Self {
+            lenient: true,
+            ..Self::new()
+        }
+    }
+
+    pub fn get(&self, file_path: &str) -> Result<ImageTexture, TextureError> {
+        self.get_with(file_path, TextureSampler::default(), None)
+    }
+
+    /// Like `ImageTexture::open`, but the image is only read the first time
+    /// a given file and colour space is requested, however its path is
+    /// spelled.
+    pub fn get_with(
+        &self,
+        file_path: &str,
+        sampler: TextureSampler,
+        color_space: Option<ColorSpace>,
+    ) -> Result<ImageTexture, TextureError> {
+        let key = (key_path(file_path), color_space);
+        let cached = self.images.lock().unwrap().get(&key).cloned();
+        let mipmap = match cached {
+            Some(mipmap) => mipmap,
+            None => {
+                // Decoding happens outside the lock so other threads aren't held
+                // up; if two race for the same file the first insert wins.
+                let mipmap = self.load(file_path, color_space)?;
+                self.images
+                    .lock()
+                    .unwrap()
+                    .entry(key)
+                    .or_insert(mipmap)
+                    .clone()
+            }
+        };
+
+        Ok(ImageTexture::from_mipmap(mipmap, sampler))
+    }
+
+    /// Decodes every file not already cached in parallel, using each format's
+    /// default colour space.
+    pub fn preload(&self, file_paths: &[&str]) -> Result<(), TextureError> {
+        let missing: Vec<&str> = {
+            let images = self.images.lock().unwrap();
+            file_paths
+                .iter()
+                .copied()
+                .filter(|path| !images.contains_key(&(key_path(path), None)))
+                .collect()
+        };
+
+        let loaded = missing
+            .par_iter()
+            .map(|path| Ok((key_path(path), self.load(path, None)?)))
+            .collect::<Result<Vec<_>, TextureError>>()?;
+
+        let mut images = self.images.lock().unwrap();
+        for (path, mipmap) in loaded {
+            images.entry((path, None)).or_insert(mipmap);
+        }
+        Ok(())
+    }
+
+    fn load(
+        &self,
+        file_path: &str,
+        color_space: Option<ColorSpace>,
+    ) -> Result<Arc<MipMap>, TextureError> {
+        match MipMap::load(file_path, color_space) {
+            Ok(mipmap) => Ok(Arc::new(mipmap)),
+            Err(error) if self.lenient => {
+                eprintln!("warning: {error}, using a placeholder");
+                Ok(Arc::new(MipMap::missing()))
+            }
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// The file's full path with any links resolved, so different ways of naming
+/// it share one entry. Paths that can't be resolved, such as missing files,
+/// are kept as given.
+fn key_path(file_path: &str) -> PathBuf {
+    fs::canonicalize(file_path).unwrap_or_else(|_| PathBuf::from(file_path))
+}
+
+impl Default for TextureCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+    use std::env;
+
+    #[test]
+    fn shares_a_file_however_it_is_named() {
+        let directory = env::temp_dir().join("texture_cache_test");
+        fs::create_dir_all(&directory).unwrap();
+        let file = directory.join("red.png");
+        RgbImage::from_pixel(2, 2, Rgb([255, 0, 0]))
+            .save(&file)
+            .unwrap();
+
+        let cache = TextureCache::new();
+        let path = file.to_str().unwrap();
+        let roundabout = directory
+            .join("..")
+            .join("texture_cache_test")
+            .join("red.png");
+        cache.preload(&[path]).unwrap();
+        cache.get(roundabout.to_str().unwrap()).unwrap();
+        cache.get(path).unwrap();
+        assert_eq!(cache.images.lock().unwrap().len(), 1);
+
+        // A different colour space is a different decoding.
+        cache
+            .get_with(path, TextureSampler::default(), Some(ColorSpace::Linear))
+            .unwrap();
+        assert_eq!(cache.images.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn strict_caches_report_missing_files() {
+        assert!(TextureCache::new().get("no/such/texture.png").is_err());
+        assert!(TextureCache::lenient().get("no/such/texture.png").is_ok());
+    }
+}
@@ -0,0 +1,25 @@
+use crate::hittable::HitRecord;
+use crate::material::texture::Texture;
+use glam::Vec3;
+
+/// The colour stored at the corners of a mesh, such as a scanned model loaded
+/// with its vertex colours. Surfaces without any show `fallback`.
+pub struct VertexColorTexture {
+    fallback: Vec3,
+}
+
+impl VertexColorTexture {
+    pub fn new(fallback: Vec3) -> Self {
+        Self { fallback }
+    }
+}
+
+impl Texture for VertexColorTexture {
+    fn value(&self, _u: f32, _v: f32, _point: Vec3) -> Vec3 {
+        self.fallback
+    }
+
+    fn value_at(&self, rec: &HitRecord) -> Vec3 {
+        rec.vertex_color.unwrap_or(self.fallback)
+    }
+}
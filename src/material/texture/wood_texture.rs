@@ -0,0 +1,55 @@
+use crate::material::texture::Texture;
+use crate::material::texture::color_ramp::ColorRamp;
+use crate::perlin::Perlin;
+use glam::{Vec3, vec3};
+
+/// Concentric growth rings around the y axis, wobbled by noise so they don't
+/// look machined. The ramp runs from early wood at 0 to the dark late wood
+/// at 1.
+pub struct WoodTexture {
+    noise: Perlin,
+    rings: f32,
+    distortion: f32,
+    ramp: ColorRamp,
+}
+
+impl WoodTexture {
+    /// `rings` per unit of radius; `distortion` is how far, in rings, the
+    /// noise may push them.
+    pub fn new(rings: f32, distortion: f32) -> Self {
+        Self::with_ramp(
+            rings,
+            distortion,
+            ColorRamp::new(vec![
+                (0.0, vec3(0.80, 0.58, 0.35)),
+                (0.6, vec3(0.70, 0.47, 0.26)),
+                (1.0, vec3(0.42, 0.24, 0.11)),
+            ]),
+        )
+    }
+
+    pub fn with_ramp(rings: f32, distortion: f32, ramp: ColorRamp) -> Self {
+        Self {
+            noise: Perlin::new(),
+            rings,
+            distortion,
+            ramp,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Perlin::with_seed(seed);
+        self
+    }
+}
+
+impl Texture for WoodTexture {
+    fn value(&self, _: f32, _: f32, point: Vec3) -> Vec3 {
+        let radius = (point.x * point.x + point.z * point.z).sqrt() * self.rings;
+        let wobble = self.distortion * self.noise.fbm(point * 2.0, 4, 2.0, 0.5);
+        let ring = (radius + wobble).fract();
+
+        // Late wood is a thin band at the end of each ring.
+        self.ramp.sample(ring.powi(3))
+    }
+}
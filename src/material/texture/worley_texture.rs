@@ -0,0 +1,48 @@
+use crate::material::texture::Texture;
+use crate::worley::Worley;
+use glam::Vec3;
+
+/// Which distance a `WorleyTexture` shows.
+#[derive(Copy, Clone)]
+pub enum WorleyFeature {
+    /// Distance to the nearest feature point: round cells, dark at the centre.
+    F1,
+    /// Distance to the second nearest: a more crumpled, pebbly look.
+    F2,
+    /// Zero along the cell borders, for cracks, scales and stone walls.
+    F2MinusF1,
+}
+
+/// Grayscale cellular noise, `scale` cells per unit.
+pub struct WorleyTexture {
+    noise: Worley,
+    scale: f32,
+    feature: WorleyFeature,
+}
+
+impl WorleyTexture {
+    pub fn new(scale: f32, feature: WorleyFeature) -> Self {
+        Self {
+            noise: Worley::new(),
+            scale,
+            feature,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Worley::with_seed(seed);
+        self
+    }
+}
+
+impl Texture for WorleyTexture {
+    fn value(&self, _: f32, _: f32, point: Vec3) -> Vec3 {
+        let (f1, f2) = self.noise.distances(self.scale * point);
+        let value = match self.feature {
+            WorleyFeature::F1 => f1,
+            WorleyFeature::F2 => f2,
+            WorleyFeature::F2MinusF1 => f2 - f1,
+        };
+        Vec3::splat(value.min(1.0))
+    }
+}
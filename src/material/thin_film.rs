@@ -0,0 +1,302 @@
+use crate::color::{Color, reflectance_spectrum_to_rgb};
+use crate::hittable::HitRecord;
+use crate::interval::Interval;
+use crate::material::solid_color::SolidColor;
+use crate::material::texture::Texture;
+use glam::Vec3;
+use std::f32::consts::PI;
+use std::ops::{Add, Div, Mul, Sub};
+
+/// What sits underneath a thin film.
+#[derive(Copy, Clone)]
+pub enum Substrate {
+    /// A dielectric with the given refractive index.
+    Dielectric(f32),
+    /// A conductor, described by its reflectance at normal incidence.
+    Conductor(Vec3),
+}
+
+/// A thin coating whose thickness is comparable to the wavelength of light,
+/// such as a soap film, an oil slick or an anodised oxide layer.
+pub struct ThinFilm {
+    thickness: Box<dyn Texture>,
+    thickness_range: Interval,
+    ior: f32,
+}
+
+impl ThinFilm {
+    /// The texture's first channel, in [0, 1], is mapped onto
+    /// `thickness_range`, given in nanometres.
+    pub fn new(thickness: Box<dyn Texture>, thickness_range: Interval, ior: f32) -> Self {
+        Self {
+            thickness,
+            thickness_range,
+            ior,
+        }
+    }
+
+    pub fn uniform(thickness: f32, ior: f32) -> Self {
+        Self {
+            thickness: Box::new(SolidColor::new(&Color::new(Vec3::ZERO))),
+            thickness_range: Interval::new(thickness, thickness),
+            ior,
+        }
+    }
+
+    fn thickness_at(&self, rec: &HitRecord) -> f32 {
+        let t = self.thickness.value_at(rec).x.clamp(0.0, 1.0);
+        self.thickness_range.min + t * self.thickness_range.size()
+    }
+
+    /// RGB reflectance of the coated interface for light arriving from a
+    /// medium of index `outside_ior` at `cos_theta` to the normal.
+    pub fn reflectance(
+        &self,
+        rec: &HitRecord,
+        cos_theta: f32,
+        outside_ior: f32,
+        substrate: Substrate,
+    ) -> Vec3 {
+        self.reflectance_with_thickness(self.thickness_at(rec), cos_theta, outside_ior, substrate)
+    }
+
+    fn reflectance_with_thickness(
+        &self,
+        thickness: f32,
+        cos_theta: f32,
+        outside_ior: f32,
+        substrate: Substrate,
+    ) -> Vec3 {
+        match substrate {
+            Substrate::Dielectric(substrate_ior) => reflectance_spectrum_to_rgb(|wavelength| {
+                airy_reflectance(
+                    cos_theta,
+                    outside_ior,
+                    self.ior,
+                    thickness,
+                    wavelength,
+                    |cos_film| {
+                        let (s, p) = fresnel_dielectric(cos_film, self.ior, substrate_ior);
+                        (Complex::real(s), Complex::real(p))
+                    },
+                )
+            }),
+            Substrate::Conductor(albedo) => reflectance_spectrum_to_rgb(|wavelength| {
+                let eta = conductor_ior(reflectance_at_wavelength(albedo, wavelength));
+                airy_reflectance(
+                    cos_theta,
+                    outside_ior,
+                    self.ior,
+                    thickness,
+                    wavelength,
+                    |cos_film| fresnel_conductor(cos_film, self.ior, eta),
+                )
+            }),
+        }
+        .clamp(Vec3::ZERO, Vec3::ONE)
+    }
+}
+
+/// Reflectance of a single film layer at one wavelength, averaged over the s
+/// and p polarisations. `base` returns the (s, p) amplitude coefficients at
+/// the bottom of the film given the cosine of the angle inside it.
+fn airy_reflectance(
+    cos_theta: f32,
+    outside_ior: f32,
+    film_ior: f32,
+    thickness: f32,
+    wavelength: f32,
+    base: impl Fn(f32) -> (Complex, Complex),
+) -> f32 {
+    let sin_theta_sq = (1.0 - cos_theta * cos_theta).max(0.0);
+    let sin_film_sq = (outside_ior / film_ior).powi(2) * sin_theta_sq;
+    if sin_film_sq >= 1.0 {
+        return 1.0;
+    }
+    let cos_film = (1.0 - sin_film_sq).sqrt();
+
+    let (r12_s, r12_p) = fresnel_dielectric(cos_theta, outside_ior, film_ior);
+    let (r23_s, r23_p) = base(cos_film);
+
+    let phase = 4.0 * PI * film_ior * thickness * cos_film / wavelength;
+    let (sin_phase, cos_phase) = phase.sin_cos();
+    let delay = Complex::new(cos_phase, sin_phase);
+
+    let airy = |r12: f32, r23: Complex| {
+        let round_trip = r23 * delay;
+        let r12 = Complex::real(r12);
+        ((r12 + round_trip) / (Complex::real(1.0) + r12 * round_trip)).norm_sqr()
+    };
+
+    0.5 * (airy(r12_s, r23_s) + airy(r12_p, r23_p))
+}
+
+/// Fresnel amplitude coefficients (s, p) for light going from `eta_i` into
+/// `eta_t`. Total internal reflection is returned as a perfect reflector.
+fn fresnel_dielectric(cos_i: f32, eta_i: f32, eta_t: f32) -> (f32, f32) {
+    let sin_t_sq = (eta_i / eta_t).powi(2) * (1.0 - cos_i * cos_i).max(0.0);
+    if sin_t_sq >= 1.0 {
+        return (1.0, 1.0);
+    }
+    let cos_t = (1.0 - sin_t_sq).sqrt();
+
+    let s = (eta_i * cos_i - eta_t * cos_t) / (eta_i * cos_i + eta_t * cos_t);
+    let p = (eta_t * cos_i - eta_i * cos_t) / (eta_t * cos_i + eta_i * cos_t);
+    (s, p)
+}
+
+/// Fresnel amplitude coefficients (s, p) for light going from a dielectric
+/// of index `eta_i` into a conductor of complex index `eta_t`.
+fn fresnel_conductor(cos_i: f32, eta_i: f32, eta_t: Complex) -> (Complex, Complex) {
+    let sin_i_sq = (1.0 - cos_i * cos_i).max(0.0);
+    let ratio = Complex::real(eta_i) / eta_t;
+    let cos_t = (Complex::real(1.0) - ratio * ratio * Complex::real(sin_i_sq)).sqrt();
+
+    let (eta_i, cos_i) = (Complex::real(eta_i), Complex::real(cos_i));
+    let s = (eta_i * cos_i - eta_t * cos_t) / (eta_i * cos_i + eta_t * cos_t);
+    let p = (eta_t * cos_i - eta_i * cos_t) / (eta_t * cos_i + eta_i * cos_t);
+    (s, p)
+}
+
+/// Rough centres of the red, green and blue primaries, in nanometres.
+const PRIMARY_WAVELENGTHS: [f32; 3] = [610.0, 550.0, 465.0];
+
+/// A smooth reflectance spectrum through an RGB reflectance, interpolating
+/// between the primaries and flat beyond them.
+fn reflectance_at_wavelength(rgb: Vec3, wavelength: f32) -> f32 {
+    let [red, green, blue] = PRIMARY_WAVELENGTHS;
+    if wavelength >= red {
+        rgb.x
+    } else if wavelength >= green {
+        rgb.y + (rgb.x - rgb.y) * (wavelength - green) / (red - green)
+    } else if wavelength >= blue {
+        rgb.z + (rgb.y - rgb.z) * (wavelength - blue) / (green - blue)
+    } else {
+        rgb.z
+    }
+}
+
+/// A complex refractive index reflecting `r0` at normal incidence from air,
+/// from Gulbrandsen's "Artist Friendly Metallic Fresnel" (2014) with the edge
+/// tint taken to be the reflectance itself.
+fn conductor_ior(r0: f32) -> Complex {
+    let r = r0.clamp(0.0, 0.99);
+    let g = r;
+    let sqrt_r = r.sqrt();
+    let n = g * (1.0 - r) / (1.0 + r) + (1.0 - g) * (1.0 + sqrt_r) / (1.0 - sqrt_r);
+    let k_sq = ((n + 1.0).powi(2) * r - (n - 1.0).powi(2)) / (1.0 - r);
+    Complex::new(n, k_sq.max(0.0).sqrt())
+}
+
+#[derive(Copy, Clone)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    fn new(re: f32, im: f32) -> Self {
+        Self { re, im }
+    }
+
+    fn real(re: f32) -> Self {
+        Self::new(re, 0.0)
+    }
+
+    fn norm_sqr(self) -> f32 {
+        self.re * self.re + self.im * self.im
+    }
+
+    /// The principal root, with a non-negative real part.
+    fn sqrt(self) -> Self {
+        let norm = self.norm_sqr().sqrt();
+        let re = (0.5 * (norm + self.re)).max(0.0).sqrt();
+        let im = (0.5 * (norm - self.re)).max(0.0).sqrt();
+        Self::new(re, if self.im < 0.0 { -im } else { im })
+    }
+}
+
+impl Add for Complex {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.re + other.re, self.im + other.im)
+    }
+}
+
+impl Sub for Complex {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self::new(self.re - other.re, self.im - other.im)
+    }
+}
+
+impl Mul for Complex {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.re * other.re - self.im * other.im,
+            self.re * other.im + self.im * other.re,
+        )
+    }
+}
+
+impl Div for Complex {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        let norm = other.norm_sqr();
+        Self::new(
+            (self.re * other.re + self.im * other.im) / norm,
+            (self.im * other.re - self.re * other.im) / norm,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conductors_reflect_their_albedo_from_air() {
+        for r0 in [0.05, 0.5, 0.95] {
+            let (s, p) = fresnel_conductor(1.0, 1.0, conductor_ior(r0));
+            assert!((s.norm_sqr() - r0).abs() < 1e-4, "{r0}: {}", s.norm_sqr());
+            assert!((p.norm_sqr() - r0).abs() < 1e-4, "{r0}: {}", p.norm_sqr());
+        }
+    }
+
+    #[test]
+    fn index_matched_films_leave_metals_unchanged() {
+        // A film matching the outside index can't interfere.
+        let film = ThinFilm::uniform(300.0, 1.0);
+        let grey = film.reflectance_with_thickness(
+            300.0,
+            1.0,
+            1.0,
+            Substrate::Conductor(Vec3::splat(0.6)),
+        );
+        assert!(grey.abs_diff_eq(Vec3::splat(0.6), 1e-3), "{grey}");
+
+        let gold = Vec3::new(1.0, 0.78, 0.34);
+        let coated = film.reflectance_with_thickness(300.0, 1.0, 1.0, Substrate::Conductor(gold));
+        assert!(coated.abs_diff_eq(gold, 0.1), "{coated}");
+    }
+
+    #[test]
+    fn films_on_metal_are_iridescent() {
+        let film = ThinFilm::uniform(0.0, 1.5);
+        let silver = Substrate::Conductor(Vec3::splat(0.95));
+        let colors: Vec<Vec3> = [200.0, 300.0, 400.0]
+            .map(|thickness| film.reflectance_with_thickness(thickness, 1.0, 1.0, silver))
+            .into();
+        assert!(
+            colors
+                .iter()
+                .any(|c| c.max_element() - c.min_element() > 0.05)
+        );
+        assert!(!colors[0].abs_diff_eq(colors[1], 0.02));
+    }
+}
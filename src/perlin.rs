@@ -1,39 +1,71 @@
-use crate::utils::random_vector_range;
-use glam::Vec3;
+use glam::{IVec3, Vec3, Vec4};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
 const POINT_COUNT: usize = 256;
 
+/// Gradient noise over a 256 cell lattice. Two fields built from the same seed
+/// are identical, so renders using them can be reproduced.
 pub struct Perlin {
     rand_vec: [Vec3; POINT_COUNT],
+    rand_vec4: [Vec4; POINT_COUNT],
     perm_x: [usize; POINT_COUNT],
     perm_y: [usize; POINT_COUNT],
     perm_z: [usize; POINT_COUNT],
+    perm_w: [usize; POINT_COUNT],
 }
 
 impl Perlin {
+    /// A randomly seeded field, different on every run.
     pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
         let mut rand_vec = [Vec3::ZERO; POINT_COUNT];
-        for i in 0..POINT_COUNT {
-            rand_vec[i] = random_vector_range(-1.0, 1.0).normalize();
+        for v in rand_vec.iter_mut() {
+            *v = Vec3::from_array([0; 3].map(|_| rng.random_range(-1.0..=1.0))).normalize_or_zero();
+        }
+        let mut rand_vec4 = [Vec4::ZERO; POINT_COUNT];
+        for v in rand_vec4.iter_mut() {
+            *v = Vec4::from_array([0; 4].map(|_| rng.random_range(-1.0..=1.0))).normalize_or_zero();
         }
 
         let mut perm_x = [0; POINT_COUNT];
         let mut perm_y = [0; POINT_COUNT];
         let mut perm_z = [0; POINT_COUNT];
+        let mut perm_w = [0; POINT_COUNT];
 
-        perlin_generate_perm(&mut perm_x);
-        perlin_generate_perm(&mut perm_y);
-        perlin_generate_perm(&mut perm_z);
+        perlin_generate_perm(&mut perm_x, &mut rng);
+        perlin_generate_perm(&mut perm_y, &mut rng);
+        perlin_generate_perm(&mut perm_z, &mut rng);
+        perlin_generate_perm(&mut perm_w, &mut rng);
 
         Self {
             rand_vec,
+            rand_vec4,
             perm_x,
             perm_y,
             perm_z,
+            perm_w,
         }
     }
 
+    /// Noise in [-1, 1], zero at every lattice point.
     pub fn noise(&self, p: Vec3) -> f32 {
+        self.lattice_noise(p, IVec3::splat(POINT_COUNT as i32))
+    }
+
+    /// Like `noise`, but repeating every `period` units along each axis, for
+    /// textures that have to tile.
+    pub fn periodic_noise(&self, p: Vec3, period: IVec3) -> f32 {
+        assert!(period.min_element() > 0);
+        self.lattice_noise(p, period)
+    }
+
+    fn lattice_noise(&self, p: Vec3, period: IVec3) -> f32 {
         let u = p.x - p.x.floor();
         let v = p.y - p.y.floor();
         let w = p.z - p.z.floor();
@@ -46,9 +78,9 @@ impl Perlin {
         for di in 0..2 {
             for dj in 0..2 {
                 for dk in 0..2 {
-                    let ix = ((i + (di as i32)) & 255) as usize;
-                    let iy = ((j + (dj as i32)) & 255) as usize;
-                    let iz = ((k + (dk as i32)) & 255) as usize;
+                    let ix = ((i + di as i32).rem_euclid(period.x) & 255) as usize;
+                    let iy = ((j + dj as i32).rem_euclid(period.y) & 255) as usize;
+                    let iz = ((k + dk as i32).rem_euclid(period.z) & 255) as usize;
 
                     let idx = self.perm_x[ix] ^ self.perm_y[iy] ^ self.perm_z[iz];
                     c[di][dj][dk] = self.rand_vec[idx];
@@ -59,6 +91,86 @@ impl Perlin {
         perlin_interpolation(&c, u, v, w)
     }
 
+    /// Four dimensional noise in [-1, 1]. Sweeping `w` with time animates a
+    /// pattern smoothly without it sliding through space.
+    pub fn noise4(&self, p: Vec4) -> f32 {
+        let cell = p.floor();
+        let f = p - cell;
+        let fade = f * f * (Vec4::splat(3.0) - 2.0 * f);
+        let base = cell.as_ivec4();
+
+        let mut acc = 0.0;
+        for corner in 0..16 {
+            let offset = Vec4::new(
+                (corner & 1) as f32,
+                ((corner >> 1) & 1) as f32,
+                ((corner >> 2) & 1) as f32,
+                ((corner >> 3) & 1) as f32,
+            );
+            let lattice = base + offset.as_ivec4();
+            let idx = self.perm_x[(lattice.x & 255) as usize]
+                ^ self.perm_y[(lattice.y & 255) as usize]
+                ^ self.perm_z[(lattice.z & 255) as usize]
+                ^ self.perm_w[(lattice.w & 255) as usize];
+
+            let weight =
+                (offset * fade + (Vec4::ONE - offset) * (Vec4::ONE - fade)).element_product();
+            acc += weight * self.rand_vec4[idx].dot(f - offset);
+        }
+        acc
+    }
+
+    /// Simplex noise in [-1, 1]. Cheaper than `noise` and free of its axis
+    /// aligned artefacts, as it blends four corners of a tetrahedron instead of
+    /// the eight of a cube.
+    pub fn simplex(&self, p: Vec3) -> f32 {
+        const SKEW: f32 = 1.0 / 3.0;
+        const UNSKEW: f32 = 1.0 / 6.0;
+
+        // Find the simplex cell containing p in the skewed lattice.
+        let cell = (p + Vec3::splat(p.element_sum() * SKEW)).floor();
+        let origin = cell - Vec3::splat(cell.element_sum() * UNSKEW);
+        let x0 = p - origin;
+
+        // The middle two corners step along the axes in decreasing order of x0.
+        let (first, second) = if x0.x >= x0.y {
+            if x0.y >= x0.z {
+                (Vec3::X, Vec3::new(1.0, 1.0, 0.0))
+            } else if x0.x >= x0.z {
+                (Vec3::X, Vec3::new(1.0, 0.0, 1.0))
+            } else {
+                (Vec3::Z, Vec3::new(1.0, 0.0, 1.0))
+            }
+        } else if x0.y < x0.z {
+            (Vec3::Z, Vec3::new(0.0, 1.0, 1.0))
+        } else if x0.x < x0.z {
+            (Vec3::Y, Vec3::new(0.0, 1.0, 1.0))
+        } else {
+            (Vec3::Y, Vec3::new(1.0, 1.0, 0.0))
+        };
+
+        let base = cell.as_ivec3();
+        let mut acc = 0.0;
+        for (step, corner) in [Vec3::ZERO, first, second, Vec3::ONE]
+            .into_iter()
+            .enumerate()
+        {
+            let offset = x0 - corner + Vec3::splat(step as f32 * UNSKEW);
+            let falloff = 0.5 - offset.length_squared();
+            if falloff <= 0.0 {
+                continue;
+            }
+
+            let lattice = base + corner.as_ivec3();
+            let idx = self.perm_x[(lattice.x & 255) as usize]
+                ^ self.perm_y[(lattice.y & 255) as usize]
+                ^ self.perm_z[(lattice.z & 255) as usize];
+            acc += falloff.powi(4) * self.rand_vec[idx].dot(offset);
+        }
+
+        SIMPLEX_SCALE * acc
+    }
+
     pub fn turbulence(&self, p: Vec3, depth: i32) -> f32 {
         let mut acc = 0.0;
         let mut temp_p = p;
@@ -72,18 +184,104 @@ impl Perlin {
 
         acc.abs()
     }
+
+    /// `turbulence` over four dimensions; only x, y and z are scaled per
+    /// octave, so `w` still moves every octave at the same rate.
+    pub fn turbulence4(&self, p: Vec4, depth: i32) -> f32 {
+        let mut acc = 0.0;
+        let mut temp_p = p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            acc += weight * self.noise4(temp_p);
+            weight *= 0.5;
+            temp_p = (2.0 * temp_p.truncate()).extend(temp_p.w);
+        }
+
+        acc.abs()
+    }
+
+    /// Fractional Brownian motion: `octaves` layers of noise, each `lacunarity`
+    /// times the frequency and `gain` times the amplitude of the last.
+    /// Normalised by the total amplitude, so it stays within [-1, 1].
+    pub fn fbm(&self, p: Vec3, octaves: u32, lacunarity: f32, gain: f32) -> f32 {
+        let mut acc = 0.0;
+        let mut total = 0.0;
+        let mut temp_p = p;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            acc += weight * self.noise(temp_p);
+            total += weight;
+            weight *= gain;
+            temp_p *= lacunarity;
+        }
+
+        if total > 0.0 { acc / total } else { 0.0 }
+    }
+
+    /// `fbm` that repeats every `period` units. Octaves double in frequency,
+    /// so each one still tiles over the same period.
+    pub fn periodic_fbm(&self, p: Vec3, period: IVec3, octaves: u32, gain: f32) -> f32 {
+        let mut acc = 0.0;
+        let mut total = 0.0;
+        let mut temp_p = p;
+        let mut temp_period = period;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            acc += weight * self.periodic_noise(temp_p, temp_period);
+            total += weight;
+            weight *= gain;
+            temp_p *= 2.0;
+            temp_period *= 2;
+        }
+
+        if total > 0.0 { acc / total } else { 0.0 }
+    }
+
+    /// Musgrave's ridged multifractal. Each octave is folded into sharp ridges
+    /// along the noise's zero crossings and weighted by the octave before, so
+    /// detail gathers on the ridges. Roughly within [0, 1].
+    pub fn ridged(&self, p: Vec3, octaves: u32, lacunarity: f32, gain: f32, offset: f32) -> f32 {
+        let mut acc = 0.0;
+        let mut total = 0.0;
+        let mut temp_p = p;
+        let mut amplitude = 1.0;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            let mut signal = offset - self.noise(temp_p).abs();
+            signal = signal * signal * weight;
+            weight = (signal * gain).clamp(0.0, 1.0);
+
+            acc += signal * amplitude;
+            total += offset * offset * amplitude;
+            amplitude /= lacunarity;
+            temp_p *= lacunarity;
+        }
+
+        if total > 0.0 { acc / total } else { 0.0 }
+    }
 }
 
-fn perlin_generate_perm(p: &mut [usize]) {
+/// Brings simplex noise to [-1, 1]. A corner adds at most `(0.5 - r²)⁴ r`,
+/// with its gradient pointing straight at the sample, which peaks at
+/// r² = 1/18. The four corners together add the most, 0.0092891, at that
+/// distance from one corner along an edge of the simplex, `√3/2` long, where
+/// the far corner adds the tail of its falloff; this is the reciprocal.
+const SIMPLEX_SCALE: f32 = 107.65;
+
+pub fn perlin_generate_perm(p: &mut [usize], rng: &mut impl Rng) {
     for i in 0..POINT_COUNT {
         p[i] = i;
     }
-    permute(p)
+    permute(p, rng)
 }
 
-fn permute(p: &mut [usize]) {
+fn permute(p: &mut [usize], rng: &mut impl Rng) {
     for i in (0..p.len()).rev() {
-        let target = rand::random_range(0..=i);
+        let target = rng.random_range(0..=i);
         let tmp = p[i];
         p[i] = p[target];
         p[target] = tmp;
@@ -113,3 +311,91 @@ pub fn perlin_interpolation(c: &[[[Vec3; 2]; 2]; 2], u: f32, v: f32, w: f32) ->
     }
     acc
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_points() -> impl Iterator<Item = Vec3> {
+        let mut rng = StdRng::seed_from_u64(7);
+        (0..20_000).map(move |_| Vec3::from_array([0; 3].map(|_| rng.random_range(-50.0..50.0))))
+    }
+
+    #[test]
+    fn same_seed_gives_same_noise() {
+        let a = Perlin::with_seed(42);
+        let b = Perlin::with_seed(42);
+        for p in sample_points().take(1_000) {
+            assert_eq!(a.noise(p), b.noise(p));
+            assert_eq!(a.simplex(p), b.simplex(p));
+            assert_eq!(a.noise4(p.extend(p.x)), b.noise4(p.extend(p.x)));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_noise() {
+        let a = Perlin::with_seed(1);
+        let b = Perlin::with_seed(2);
+        assert!(sample_points().take(100).any(|p| a.noise(p) != b.noise(p)));
+    }
+
+    #[test]
+    fn noise_stays_in_range() {
+        let perlin = Perlin::with_seed(3);
+        for p in sample_points() {
+            assert!((-1.0..=1.0).contains(&perlin.noise(p)));
+            assert!((-1.0..=1.0).contains(&perlin.noise4(p.extend(p.y - p.z))));
+            assert!((-1.0..=1.0).contains(&perlin.simplex(p)));
+            assert!((-1.0..=1.0).contains(&perlin.fbm(p, 6, 2.0, 0.5)));
+        }
+    }
+
+    #[test]
+    fn noise_is_zero_on_the_lattice() {
+        let perlin = Perlin::with_seed(4);
+        for p in sample_points().take(1_000) {
+            let p = p.floor();
+            assert_eq!(perlin.noise(p), 0.0);
+            assert_eq!(perlin.noise4(p.extend(3.0)), 0.0);
+        }
+    }
+
+    #[test]
+    fn simplex_scale_covers_the_largest_sum() {
+        // Either end of an edge contributing, with gradients along it.
+        let corner = |r: f32| (0.5 - r * r).max(0.0).powi(4) * r;
+        let edge = 3.0f32.sqrt() / 2.0;
+        let largest = (0..=10_000)
+            .map(|i| {
+                let r = i as f32 / 10_000.0 * edge;
+                corner(r) + corner(edge - r)
+            })
+            .fold(0.0, f32::max);
+        assert!(largest * SIMPLEX_SCALE <= 1.0);
+        assert!(largest * SIMPLEX_SCALE > 0.999);
+    }
+
+    #[test]
+    fn simplex_uses_most_of_its_range() {
+        let perlin = Perlin::with_seed(5);
+        let max = sample_points()
+            .map(|p| perlin.simplex(p).abs())
+            .fold(0.0, f32::max);
+        assert!(max > 0.5, "simplex peaked at {max}");
+    }
+
+    #[test]
+    fn periodic_noise_tiles() {
+        let perlin = Perlin::with_seed(6);
+        let period = IVec3::new(4, 8, 3);
+        for p in sample_points().take(1_000) {
+            let expected = perlin.periodic_noise(p, period);
+            let shifted = p + (period * IVec3::new(1, -2, 5)).as_vec3();
+            assert!((perlin.periodic_noise(shifted, period) - expected).abs() < 1e-4);
+
+            let fbm = perlin.periodic_fbm(p, period, 4, 0.5);
+            let shifted = p + period.as_vec3();
+            assert!((perlin.periodic_fbm(shifted, period, 4, 0.5) - fbm).abs() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::f64::consts::PI;
+use std::ops::Deref;
+
+const EPSILON: f64 = 1e-9;
+
+/// Up to four real roots, in no particular order.
+#[derive(Copy, Clone, Default)]
+pub struct Roots {
+    values: [f64; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn push(&mut self, root: f64) {
+        self.values[self.len] = root;
+        self.len += 1;
+    }
+}
+
+impl Deref for Roots {
+    type Target = [f64];
+
+    fn deref(&self) -> &[f64] {
+        &self.values[..self.len]
+    }
+}
+
+fn is_zero(x: f64) -> bool {
+    x.abs() < EPSILON
+}
+
+/// Roots of `c[2] x^2 + c[1] x + c[0]`.
+pub fn solve_quadratic(c: [f64; 3]) -> Roots {
+    let mut roots = Roots::default();
+    let p = c[1] / (2.0 * c[2]);
+    let q = c[0] / c[2];
+    let discriminant = p * p - q;
+
+    if is_zero(discriminant) {
+        roots.push(-p);
+    } else if discriminant > 0.0 {
+        let sqrt_d = discriminant.sqrt();
+        roots.push(sqrt_d - p);
+        roots.push(-sqrt_d - p);
+    }
+    roots
+}
+
+/// Roots of `c[3] x^3 + c[2] x^2 + c[1] x + c[0]`.
+pub fn solve_cubic(c: [f64; 4]) -> Roots {
+    let mut roots = Roots::default();
+
+    // Normal form x^3 + Ax^2 + Bx + C, then substitute x = y - A/3 to get
+    // the depressed cubic y^3 + 3py + 2q.
+    let a = c[2] / c[3];
+    let b = c[1] / c[3];
+    let c = c[0] / c[3];
+
+    let sq_a = a * a;
+    let p = (-sq_a / 3.0 + b) / 3.0;
+    let q = (2.0 / 27.0 * a * sq_a - a * b / 3.0 + c) / 2.0;
+
+    let cb_p = p * p * p;
+    let discriminant = q * q + cb_p;
+
+    if is_zero(discriminant) {
+        if is_zero(q) {
+            roots.push(0.0);
+        } else {
+            let u = (-q).cbrt();
+            roots.push(2.0 * u);
+            roots.push(-u);
+        }
+    } else if discriminant < 0.0 {
+        // Three real roots, found trigonometrically.
+        let phi = (-q / (-cb_p).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let t = 2.0 * (-p).sqrt();
+        roots.push(t * phi.cos());
+        roots.push(-t * (phi + PI / 3.0).cos());
+        roots.push(-t * (phi - PI / 3.0).cos());
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        let u = (sqrt_d - q).cbrt();
+        let v = -(sqrt_d + q).cbrt();
+        roots.push(u + v);
+    }
+
+    let shift = a / 3.0;
+    for root in &mut roots.values[..roots.len] {
+        *root -= shift;
+    }
+    roots
+}
+
+/// Roots of `c[4] x^4 + c[3] x^3 + c[2] x^2 + c[1] x + c[0]`, by Ferrari's
+/// method as in Schwarze's "Cubic and Quartic Roots" (Graphics Gems). Worked
+/// in `f64` and polished with a couple of Newton steps, as it loses too much
+/// precision in `f32`.
+pub fn solve_quartic(c: [f64; 5]) -> Roots {
+    let mut roots = Roots::default();
+
+    // Normal form x^4 + Ax^3 + Bx^2 + Cx + D, then substitute x = y - A/4 to
+    // get the depressed quartic y^4 + py^2 + qy + r.
+    let a = c[3] / c[4];
+    let b = c[2] / c[4];
+    let cc = c[1] / c[4];
+    let d = c[0] / c[4];
+
+    let sq_a = a * a;
+    let p = -3.0 / 8.0 * sq_a + b;
+    let q = sq_a * a / 8.0 - a * b / 2.0 + cc;
+    let r = -3.0 / 256.0 * sq_a * sq_a + sq_a * b / 16.0 - a * cc / 4.0 + d;
+
+    if is_zero(r) {
+        // y (y^3 + py + q) = 0
+        roots.push(0.0);
+        for &root in solve_cubic([q, p, 0.0, 1.0]).iter() {
+            roots.push(root);
+        }
+    } else {
+        // Take one root of the resolvent cubic to split the quartic into two
+        // quadratics.
+        let z = solve_cubic([r * p / 2.0 - q * q / 8.0, -r, -p / 2.0, 1.0])[0];
+
+        let u = z * z - r;
+        let v = 2.0 * z - p;
+        let u = if is_zero(u) {
+            0.0
+        } else if u > 0.0 {
+            u.sqrt()
+        } else {
+            return roots;
+        };
+        let v = if is_zero(v) {
+            0.0
+        } else if v > 0.0 {
+            v.sqrt()
+        } else {
+            return roots;
+        };
+
+        let v = if q < 0.0 { -v } else { v };
+        for &root in solve_quadratic([z - u, v, 1.0]).iter() {
+            roots.push(root);
+        }
+        for &root in solve_quadratic([z + u, -v, 1.0]).iter() {
+            roots.push(root);
+        }
+    }
+
+    let shift = a / 4.0;
+    for root in &mut roots.values[..roots.len] {
+        *root -= shift;
+        for _ in 0..2 {
+            let x = *root;
+            let f = (((c[4] * x + c[3]) * x + c[2]) * x + c[1]) * x + c[0];
+            let df = ((4.0 * c[4] * x + 3.0 * c[3]) * x + 2.0 * c[2]) * x + c[1];
+            if df != 0.0 {
+                *root -= f / df;
+            }
+        }
+    }
+    roots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The coefficients of the monic quartic with the given roots.
+    fn with_roots(roots: [f64; 4]) -> [f64; 5] {
+        let mut c = [1.0, 0.0, 0.0, 0.0, 0.0];
+        for (degree, root) in roots.into_iter().enumerate() {
+            // Multiply by (x - root), highest power first.
+            for i in (1..=degree + 1).rev() {
+                c[i] = c[i - 1] - root * c[i];
+            }
+            c[0] *= -root;
+        }
+        c
+    }
+
+    fn sorted(roots: Roots) -> Vec<f64> {
+        let mut roots = roots.to_vec();
+        roots.sort_by(f64::total_cmp);
+        roots
+    }
+
+    #[test]
+    fn finds_four_real_roots() {
+        let roots = sorted(solve_quartic(with_roots([3.0, -2.0, 0.5, 1.25])));
+        assert_eq!(roots.len(), 4, "{roots:?}");
+        for (root, expected) in roots.iter().zip([-2.0, 0.5, 1.25, 3.0]) {
+            assert!((root - expected).abs() < 1e-9, "{roots:?}");
+        }
+    }
+
+    #[test]
+    fn finds_a_double_root() {
+        let roots = sorted(solve_quartic(with_roots([1.0, 1.0, -2.0, 3.0])));
+        assert!(
+            roots.iter().any(|root| (root + 2.0).abs() < 1e-9),
+            "{roots:?}"
+        );
+        assert!(
+            roots.iter().any(|root| (root - 3.0).abs() < 1e-9),
+            "{roots:?}"
+        );
+        // Precision is lost at a double root, as the curve only touches zero.
+        assert!(
+            roots.iter().any(|root| (root - 1.0).abs() < 1e-4),
+            "{roots:?}"
+        );
+        assert!(roots.iter().all(|root| {
+            [-2.0, 1.0, 3.0]
+                .iter()
+                .any(|expected| (root - expected).abs() < 1e-4)
+        }));
+    }
+
+    #[test]
+    fn finds_no_roots_when_there_are_none() {
+        // (x^2 + 1)(x^2 + 4)
+        assert!(solve_quartic([4.0, 0.0, 5.0, 0.0, 1.0]).is_empty());
+        // (x^2 - 2x + 2)(x^2 + 6x + 10), off centre.
+        assert!(solve_quartic([20.0, -8.0, 0.0, 4.0, 1.0]).is_empty());
+    }
+}
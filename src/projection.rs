@@ -0,0 +1,286 @@
+use crate::aperture::{Aperture, Circular};
+use glam::{Vec2, Vec3, vec2, vec3};
+use std::f32::consts::{PI, TAU};
+
+/// How a camera maps its image onto rays.
+///
+/// Rays are in camera space, looking down -z with +y up and +x to the
+/// right, and the camera moves them into place. Image points run from (0, 0)
+/// at the top left to (1, 1) at the bottom right.
+pub trait Projection: Sync + Send {
+    /// The origin and direction of the ray through `point` on an image
+    /// `aspect_ratio` wide for each unit high, or `None` where the image shows
+    /// nothing. `lens` is a random point in the unit square for projections
+    /// that sample a lens.
+    fn ray(&self, point: Vec2, aspect_ratio: f32, lens: Vec2) -> Option<(Vec3, Vec3)>;
+}
+
+/// A thin lens perspective projection, as a photograph.
+pub struct Perspective {
+    /// Vertical field of view in degrees.
+    v_fov: f32,
+    /// Variation in angle of rays through each pixel, in degrees.
+    defocus_angle: f32,
+    /// Distance to the plane in perfect focus.
+    focus_dist: f32,
+    aperture: Box<dyn Aperture>,
+    /// How far the rim of the lens barrel slides across the aperture at the
+    /// corners of the image, as a fraction of its radius.
+    cat_eye: f32,
+}
+
+impl Perspective {
+    pub fn new(v_fov: f32, defocus_angle: f32, focus_dist: f32) -> Self {
+        Self {
+            v_fov,
+            defocus_angle,
+            focus_dist,
+            aperture: Box::new(Circular),
+            cat_eye: 0.0,
+        }
+    }
+
+    /// Gives the lens opening a shape, which out of focus highlights take.
+    pub fn with_aperture(mut self, aperture: Box<dyn Aperture>) -> Self {
+        self.aperture = aperture;
+        self
+    }
+
+    /// Clips the aperture by the lens barrel away from the centre of the
+    /// image, so out of focus highlights there narrow to a cat's eye and the
+    /// corners darken. 1 cuts the corners' aperture in half.
+    pub fn with_cat_eye(mut self, cat_eye: f32) -> Self {
+        self.cat_eye = cat_eye;
+        self
+    }
+}
+
+impl Projection for Perspective {
+    fn ray(&self, point: Vec2, aspect_ratio: f32, lens: Vec2) -> Option<(Vec3, Vec3)> {
+        let viewport_height = 2.0 * (self.v_fov.to_radians() / 2.0).tan() * self.focus_dist;
+        let viewport_width = viewport_height * aspect_ratio;
+        let target = vec3(
+            (point.x - 0.5) * viewport_width,
+            (0.5 - point.y) * viewport_height,
+            -self.focus_dist,
+        );
+
+        let origin = if self.defocus_angle <= 0.0 {
+            Vec3::ZERO
+        } else {
+            let lens = self.aperture.sample(lens);
+            // Relative to the centre, in units of half the image diagonal.
+            let centered = vec2((point.x - 0.5) * aspect_ratio, 0.5 - point.y) * 2.0
+                / (1.0 + aspect_ratio * aspect_ratio).sqrt();
+            if (lens + self.cat_eye * centered).length_squared() > 1.0 {
+                return None;
+            }
+
+            let defocus_radius = self.focus_dist * (self.defocus_angle / 2.0).to_radians().tan();
+            (lens * defocus_radius).extend(0.0)
+        };
+        Some((origin, target - origin))
+    }
+}
+
+/// Parallel rays, so sizes don't shrink with distance, for architectural
+/// and technical views.
+pub struct Orthographic {
+    /// The height of the view in world units.
+    height: f32,
+}
+
+impl Orthographic {
+    pub fn new(height: f32) -> Self {
+        Self { height }
+    }
+}
+
+impl Projection for Orthographic {
+    fn ray(&self, point: Vec2, aspect_ratio: f32, _lens: Vec2) -> Option<(Vec3, Vec3)> {
+        let origin = vec3(
+            (point.x - 0.5) * self.height * aspect_ratio,
+            (0.5 - point.y) * self.height,
+            0.0,
+        );
+        Some((origin, -Vec3::Z))
+    }
+}
+
+#[derive(Copy, Clone)]
+pub enum FisheyeMapping {
+    /// Distance from the centre of the image proportional to the angle off
+    /// axis, as in most fisheye lenses sold for wide views.
+    Equidistant,
+    /// Area on the image proportional to solid angle, as in lenses for
+    /// measuring sky cover.
+    Equisolid,
+}
+
+/// A circular fisheye image, filling the shorter side of the frame and
+/// black outside the circle.
+pub struct Fisheye {
+    mapping: FisheyeMapping,
+    /// Field of view across the circle, in degrees, up to 360.
+    fov: f32,
+}
+
+impl Fisheye {
+    pub fn new(mapping: FisheyeMapping, fov: f32) -> Self {
+        assert!(fov > 0.0 && fov <= 360.0);
+        Self { mapping, fov }
+    }
+
+    pub fn equidistant(fov: f32) -> Self {
+        Self::new(FisheyeMapping::Equidistant, fov)
+    }
+
+    pub fn equisolid(fov: f32) -> Self {
+        Self::new(FisheyeMapping::Equisolid, fov)
+    }
+}
+
+impl Projection for Fisheye {
+    fn ray(&self, point: Vec2, aspect_ratio: f32, _lens: Vec2) -> Option<(Vec3, Vec3)> {
+        // Relative to the centre, in units of the image height with +y up.
+        let centered = vec2((point.x - 0.5) * aspect_ratio, 0.5 - point.y);
+        let radius = centered.length() / (0.5 * aspect_ratio.min(1.0));
+        if radius > 1.0 {
+            return None;
+        }
+
+        let max_theta = self.fov.to_radians() / 2.0;
+        let theta = match self.mapping {
+            FisheyeMapping::Equidistant => radius * max_theta,
+            FisheyeMapping::Equisolid => 2.0 * (radius * (max_theta / 2.0).sin()).asin(),
+        };
+        let phi = centered.y.atan2(centered.x);
+        let direction = vec3(
+            theta.sin() * phi.cos(),
+            theta.sin() * phi.sin(),
+            -theta.cos(),
+        );
+        Some((Vec3::ZERO, direction))
+    }
+}
+
+/// The whole sphere of directions, longitude across and latitude down,
+/// looking ahead at the centre, for 360° panoramas and environment maps.
+/// The image should be twice as wide as it is high.
+pub struct Equirectangular;
+
+impl Projection for Equirectangular {
+    fn ray(&self, point: Vec2, _aspect_ratio: f32, _lens: Vec2) -> Option<(Vec3, Vec3)> {
+        let longitude = (point.x - 0.5) * TAU;
+        let latitude = (0.5 - point.y) * PI;
+        let direction = vec3(
+            longitude.sin() * latitude.cos(),
+            latitude.sin(),
+            -longitude.cos() * latitude.cos(),
+        );
+        Some((Vec3::ZERO, direction))
+    }
+}
+
+/// The six faces of a cube around the camera side by side, in the order +x,
+/// -x, +y, -y, +z, -z along the camera's axes and oriented as graphics APIs
+/// expect cube map faces. The image should be six times as wide as it is
+/// high.
+pub struct Cubemap;
+
+impl Projection for Cubemap {
+    fn ray(&self, point: Vec2, _aspect_ratio: f32, _lens: Vec2) -> Option<(Vec3, Vec3)> {
+        let face = ((point.x * 6.0) as usize).min(5);
+        let sc = 2.0 * (point.x * 6.0 - face as f32) - 1.0;
+        let tc = 2.0 * point.y - 1.0;
+        let direction = match face {
+            0 => vec3(1.0, -tc, -sc),
+            1 => vec3(-1.0, -tc, sc),
+            2 => vec3(sc, 1.0, tc),
+            3 => vec3(sc, -1.0, -tc),
+            4 => vec3(sc, -tc, 1.0),
+            _ => vec3(-sc, -tc, -1.0),
+        };
+        Some((Vec3::ZERO, direction))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn direction(projection: &dyn Projection, point: Vec2, aspect_ratio: f32) -> Vec3 {
+        let (_, direction) = projection.ray(point, aspect_ratio, vec2(0.5, 0.5)).unwrap();
+        direction.normalize()
+    }
+
+    #[test]
+    fn the_centre_looks_ahead() {
+        let projections: [(Box<dyn Projection>, f32); 6] = [
+            (Box::new(Perspective::new(40.0, 2.0, 5.0)), 1.5),
+            (Box::new(Orthographic::new(4.0)), 1.5),
+            (Box::new(Fisheye::equidistant(360.0)), 1.0),
+            (Box::new(Fisheye::equisolid(180.0)), 1.0),
+            (Box::new(Fisheye::equisolid(360.0)), 1.0),
+            (Box::new(Equirectangular), 2.0),
+        ];
+        for (projection, aspect_ratio) in projections {
+            let ahead = direction(projection.as_ref(), vec2(0.5, 0.5), aspect_ratio);
+            assert!(ahead.abs_diff_eq(-Vec3::Z, 1e-5), "{ahead}");
+        }
+
+        // The middle of the cube map's last face.
+        let ahead = direction(&Cubemap, vec2(11.0 / 12.0, 0.5), 6.0);
+        assert!(ahead.abs_diff_eq(-Vec3::Z, 1e-5), "{ahead}");
+    }
+
+    /// The face and (s, t) coordinates a direction looks up in an OpenGL cube
+    /// map, from table 8.19 of the OpenGL 4.6 specification.
+    fn gl_cube_lookup(r: Vec3) -> (usize, Vec2) {
+        let (face, sc, tc, ma) = if r.x.abs() >= r.y.abs() && r.x.abs() >= r.z.abs() {
+            if r.x > 0.0 {
+                (0, -r.z, -r.y, r.x)
+            } else {
+                (1, r.z, -r.y, r.x)
+            }
+        } else if r.y.abs() >= r.z.abs() {
+            if r.y > 0.0 {
+                (2, r.x, r.z, r.y)
+            } else {
+                (3, r.x, -r.z, r.y)
+            }
+        } else if r.z > 0.0 {
+            (4, r.x, -r.y, r.z)
+        } else {
+            (5, -r.x, -r.y, r.z)
+        };
+        (face, vec2(sc / ma.abs() + 1.0, tc / ma.abs() + 1.0) / 2.0)
+    }
+
+    #[test]
+    fn cube_map_faces_follow_the_gl_convention() {
+        for face in 0..6 {
+            for (s, t) in [(0.5, 0.5), (0.2, 0.3), (0.9, 0.1), (0.3, 0.8)] {
+                let point = vec2((face as f32 + s) / 6.0, t);
+                let (found, st) = gl_cube_lookup(direction(&Cubemap, point, 6.0));
+                assert_eq!(found, face);
+                assert!(st.abs_diff_eq(vec2(s, t), 1e-5), "face {face}: {st}");
+            }
+        }
+    }
+
+    #[test]
+    fn equirectangular_images_wrap_around_behind() {
+        for y in [0.1, 0.3, 0.5, 0.8] {
+            let left = direction(&Equirectangular, vec2(0.0, y), 2.0);
+            let right = direction(&Equirectangular, vec2(1.0, y), 2.0);
+            assert!(left.abs_diff_eq(right, 1e-5), "{left} {right}");
+            assert!(left.z > 0.0);
+
+            // Either side of the seam are neighbours.
+            let past = direction(&Equirectangular, vec2(0.001, y), 2.0);
+            let before = direction(&Equirectangular, vec2(0.999, y), 2.0);
+            assert!(past.distance(before) < 0.02);
+        }
+    }
+}
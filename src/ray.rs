@@ -1,9 +1,23 @@
+use crate::material::interior::MediumStack;
 use glam::Vec3;
 
+/// Offset rays for neighbouring pixels in x and y, used to estimate the
+/// footprint of a pixel on the surfaces it hits.
+#[derive(Copy, Clone)]
+pub struct RayDifferentials {
+    pub rx_origin: Vec3,
+    pub rx_direction: Vec3,
+    pub ry_origin: Vec3,
+    pub ry_direction: Vec3,
+}
+
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
     pub time: f32,
+    pub differentials: Option<RayDifferentials>,
+    /// The dielectric shapes the ray starts out inside.
+    pub media: MediumStack,
 }
 
 impl Ray {
@@ -12,9 +26,21 @@ impl Ray {
             origin,
             direction,
             time,
+            differentials: None,
+            media: MediumStack::default(),
         }
     }
 
+    pub fn with_differentials(mut self, differentials: Option<RayDifferentials>) -> Self {
+        self.differentials = differentials;
+        self
+    }
+
+    pub fn with_media(mut self, media: MediumStack) -> Self {
+        self.media = media;
+        self
+    }
+
     pub fn at(&self, t: f32) -> Vec3 {
         self.origin + t * self.direction
     }
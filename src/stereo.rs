@@ -0,0 +1,108 @@
+use glam::{Vec3, vec3};
+
+/// Where the two eyes' views go in the one image.
+#[derive(Copy, Clone)]
+pub enum StereoLayout {
+    /// The left eye on the left half.
+    SideBySide,
+    /// The left eye on the top half.
+    TopBottom,
+}
+
+#[derive(Copy, Clone)]
+pub enum Eye {
+    Left,
+    Right,
+}
+
+/// Renders a view for each eye, for VR headsets and 3D displays.
+pub struct Stereo {
+    /// Distance between the eyes, in world units, 0.064 for people in a
+    /// scene in metres.
+    interocular: f32,
+    /// Distance along each ray from the centre of the eyes at which the two
+    /// views meet, appearing at the depth of the screen.
+    convergence: f32,
+    /// Whether each ray has its own eye position, for panoramas.
+    omni_directional: bool,
+    layout: StereoLayout,
+}
+
+impl Stereo {
+    /// Parallel eyes, which converge at infinity.
+    pub fn new(interocular: f32, layout: StereoLayout) -> Self {
+        Self {
+            interocular,
+            convergence: f32::INFINITY,
+            omni_directional: false,
+            layout,
+        }
+    }
+
+    /// Turns the eyes in to meet `distance` away, so nearer things stand out
+    /// of the screen and further ones sit behind it.
+    pub fn with_convergence(mut self, distance: f32) -> Self {
+        self.convergence = distance;
+        self
+    }
+
+    /// Omni-directional stereo, where the eyes turn with each ray around a
+    /// circle as a head turning to look that way would.
+    ///
+    /// A pair of cameras side by side only gives depth straight ahead, and
+    /// none looking to the sides; this gives the right depth in every
+    /// direction around, so suits 360° panoramas such as `Equirectangular`.
+    /// Looking straight up or down there is no depth.
+    pub fn omni_directional(mut self) -> Self {
+        self.omni_directional = true;
+        self
+    }
+
+    /// The size of the whole image for a view of `width` by `height` for
+    /// each eye.
+    pub fn image_size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self.layout {
+            StereoLayout::SideBySide => (width * 2, height),
+            StereoLayout::TopBottom => (width, height * 2),
+        }
+    }
+
+    /// Which eye's view pixel (`i`, `j`) of the whole image is in, and where
+    /// in that view.
+    pub fn eye_pixel(
+        &self,
+        i: usize,
+        j: usize,
+        width: usize,
+        height: usize,
+    ) -> (Eye, usize, usize) {
+        match self.layout {
+            StereoLayout::SideBySide if i < width => (Eye::Left, i, j),
+            StereoLayout::SideBySide => (Eye::Right, i - width, j),
+            StereoLayout::TopBottom if j < height => (Eye::Left, i, j),
+            StereoLayout::TopBottom => (Eye::Right, i, j - height),
+        }
+    }
+
+    /// Moves a ray from the centre of the eyes, in camera space, to `eye`.
+    pub fn eye_ray(&self, eye: Eye, origin: Vec3, direction: Vec3) -> (Vec3, Vec3) {
+        let side = match eye {
+            Eye::Left => -0.5,
+            Eye::Right => 0.5,
+        };
+        let right = if self.omni_directional {
+            // To the right of where the ray is looking, in the horizontal.
+            vec3(-direction.z, 0.0, direction.x).normalize_or_zero()
+        } else {
+            Vec3::X
+        };
+        let offset = side * self.interocular * right;
+
+        if self.convergence.is_finite() {
+            let target = origin + direction.normalize() * self.convergence;
+            (origin + offset, target - origin - offset)
+        } else {
+            (origin + offset, direction)
+        }
+    }
+}
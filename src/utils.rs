@@ -26,20 +26,6 @@ pub fn random_unit_vector() -> Vec3 {
     }
 }
 
-pub fn random_in_unit_disk() -> Vec3 {
-    loop {
-        let p = Vec3 {
-            x: rand::random::<f32>(),
-            y: rand::random::<f32>(),
-            z: 0.0,
-        };
-        let len_sq = p.length_squared();
-        if len_sq < 1.0 {
-            return p;
-        }
-    }
-}
-
 pub fn near_zero(v: Vec3) -> bool {
     v.x.abs() < f32::EPSILON && v.y.abs() < f32::EPSILON && v.z.abs() < f32::EPSILON
 }
@@ -0,0 +1,63 @@
+use crate::perlin::perlin_generate_perm;
+use glam::{IVec3, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const POINT_COUNT: usize = 256;
+
+/// Cellular noise: one feature point scattered in every unit cell, and the
+/// distances from a lookup point to the nearest of them.
+pub struct Worley {
+    offsets: [Vec3; POINT_COUNT],
+    perm: [usize; POINT_COUNT],
+}
+
+impl Worley {
+    pub fn new() -> Self {
+        Self::with_seed(rand::random())
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        let mut offsets = [Vec3::ZERO; POINT_COUNT];
+        for offset in offsets.iter_mut() {
+            *offset = Vec3::from_array([0; 3].map(|_| rng.random()));
+        }
+
+        let mut perm = [0; POINT_COUNT];
+        perlin_generate_perm(&mut perm, &mut rng);
+
+        Self { offsets, perm }
+    }
+
+    /// Distances to the nearest and second nearest feature points.
+    pub fn distances(&self, p: Vec3) -> (f32, f32) {
+        let cell = p.floor().as_ivec3();
+        let mut f1 = f32::INFINITY;
+        let mut f2 = f32::INFINITY;
+
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let neighbour = cell + IVec3::new(dx, dy, dz);
+                    let distance = self.feature_point(neighbour).distance(p);
+                    if distance < f1 {
+                        f2 = f1;
+                        f1 = distance;
+                    } else if distance < f2 {
+                        f2 = distance;
+                    }
+                }
+            }
+        }
+
+        (f1, f2)
+    }
+
+    fn feature_point(&self, cell: IVec3) -> Vec3 {
+        let hash = |i: i32, h: usize| self.perm[(h + (i & 255) as usize) & 255];
+        let idx = hash(cell.z, hash(cell.y, hash(cell.x, 0)));
+        cell.as_vec3() + self.offsets[idx]
+    }
+}